@@ -0,0 +1,400 @@
+use reqwest::ResponseBuilderExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::commands::diagnostics::{redact_url, RequestLog};
+use crate::commands::http::is_sensitive_header;
+
+// Exchanges captured per set_capture call unless asked otherwise, and the most allowed
+const DEFAULT_CAPTURE_COUNT: u32 = 20;
+const MAX_CAPTURE_COUNT: u32 = 100;
+
+// Capture switches itself off after this long, and captured exchanges are dropped after it too
+const CAPTURE_TTL_SECS: i64 = 30 * 60;
+
+// Request bodies longer than this are cut off in the capture, and response bodies are only read
+// into it when their Content-Length is within it (the caller still gets the full response)
+const MAX_CAPTURED_BODY_BYTES: usize = 1024 * 1024;
+
+// Written into exported HAR files
+const HAR_VERSION: &str = "1.2";
+
+// JSON keys and form fields whose values are replaced with "[REDACTED]" in captured bodies
+const SENSITIVE_BODY_FIELDS: &[&str] = &[
+    "password",
+    "apptoken",
+    "authenticationtoken",
+    "authorization",
+    "token",
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "secret",
+];
+
+/// One full request/response pair, redacted, for a support bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedExchange {
+    // Unix time in milliseconds when the request was sent
+    pub timestamp: i64,
+    pub method: String,
+    pub url_redacted: String,
+    pub request_headers: HashMap<String, String>,
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub response_headers: HashMap<String, String>,
+    // A note in brackets instead when the body was streamed or too long to capture
+    pub response_body: String,
+    // Milliseconds from sending the request to reading the whole response body (or its headers,
+    // when the body wasn't captured)
+    pub elapsed_ms: i64,
+    // e.g. "HTTP/1.1", as negotiated for the response
+    pub http_version: String,
+}
+
+/// Whether capture is on, and what it holds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureStatus {
+    pub enabled: bool,
+    // Requests still to be captured before capture switches itself off
+    pub remaining: u32,
+    // Unix time in milliseconds when capture switches off regardless
+    pub expires_at: Option<i64>,
+    pub captured: u32,
+}
+
+/// A request claimed for capture, waiting for its response
+pub(crate) struct PendingCapture {
+    timestamp: i64,
+    method: String,
+    url_redacted: String,
+    request_headers: HashMap<String, String>,
+    request_body: Option<String>,
+}
+
+#[derive(Default)]
+struct CaptureState {
+    remaining: u32,
+    expires_at: Option<i64>,
+    exchanges: Vec<CapturedExchange>,
+}
+
+impl CaptureState {
+    /// Switch off an expired capture and forget exchanges older than the TTL
+    fn expire(&mut self, now: i64) {
+        if self.expires_at.is_some_and(|at| now >= at) {
+            self.remaining = 0;
+            self.expires_at = None;
+        }
+        self.exchanges.retain(|e| now - e.timestamp < CAPTURE_TTL_SECS * 1000);
+    }
+
+    fn status(&self) -> CaptureStatus {
+        CaptureStatus {
+            enabled: self.remaining > 0,
+            remaining: self.remaining,
+            expires_at: self.expires_at,
+            captured: self.exchanges.len() as u32,
+        }
+    }
+}
+
+/// Opt-in capture of full request/response pairs (held by `RequestLog`, off by default, not persisted)
+#[derive(Default)]
+pub struct CaptureBuffer {
+    state: Mutex<CaptureState>,
+}
+
+impl CaptureBuffer {
+    fn lock(&self) -> std::sync::MutexGuard<'_, CaptureState> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.expire(chrono::Utc::now().timestamp_millis());
+        state
+    }
+
+    /// Take one capture slot for `request` if capture is on
+    pub(crate) fn claim(&self, request: &reqwest::Request) -> Option<PendingCapture> {
+        let mut state = self.lock();
+        if state.remaining == 0 {
+            return None;
+        }
+        state.remaining -= 1;
+        if state.remaining == 0 {
+            state.expires_at = None;
+        }
+        drop(state);
+
+        Some(PendingCapture {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            method: request.method().to_string(),
+            url_redacted: redact_url(request.url()),
+            request_headers: redact_headers(request.headers()),
+            request_body: request.body()
+                .and_then(reqwest::Body::as_bytes)
+                .map(redact_body),
+        })
+    }
+
+    /// Read the response body into the capture, then hand back an equivalent response. A body
+    /// without a Content-Length (e.g. a streamed NDJSON export) or longer than the capture limit is
+    /// left unread for the caller, and only the headers are captured.
+    pub(crate) async fn record(
+        &self,
+        pending: PendingCapture,
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response, String> {
+        let status = response.status();
+        let version = response.version();
+        let exchange = |response_headers: &reqwest::header::HeaderMap, response_body: String| CapturedExchange {
+            timestamp: pending.timestamp,
+            method: pending.method,
+            url_redacted: pending.url_redacted,
+            request_headers: pending.request_headers,
+            request_body: pending.request_body,
+            status: status.as_u16(),
+            response_headers: redact_headers(response_headers),
+            response_body,
+            elapsed_ms: chrono::Utc::now().timestamp_millis() - pending.timestamp,
+            http_version: format!("{:?}", version),
+        };
+
+        let skipped = match response.content_length() {
+            None => Some("[body not captured: no Content-Length]".to_string()),
+            Some(len) if len > MAX_CAPTURED_BODY_BYTES as u64 => {
+                Some(format!("[body not captured: {} bytes, over the {} byte limit]", len, MAX_CAPTURED_BODY_BYTES))
+            }
+            Some(_) => None,
+        };
+        if let Some(note) = skipped {
+            self.lock().exchanges.push(exchange(response.headers(), note));
+            return Ok(response);
+        }
+
+        let url = response.url().clone();
+        let headers = response.headers().clone();
+        let body = response.bytes()
+            .await
+            .map_err(|e| format!("Failed to read response body: {}", e))?;
+        self.lock().exchanges.push(exchange(&headers, redact_body(&body)));
+
+        let mut rebuilt = http::Response::builder().status(status).version(version).url(url);
+        if let Some(rebuilt_headers) = rebuilt.headers_mut() {
+            *rebuilt_headers = headers;
+        }
+        rebuilt.body(body)
+            .map(reqwest::Response::from)
+            .map_err(|e| format!("Failed to rebuild captured response: {}", e))
+    }
+}
+
+fn redact_headers(headers: &reqwest::header::HeaderMap) -> HashMap<String, String> {
+    headers.iter()
+        .map(|(name, value)| {
+            let value = if is_sensitive_header(name.as_str()) {
+                "[REDACTED]".to_string()
+            } else {
+                value.to_str().unwrap_or("[binary]").to_string()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+fn is_sensitive_field(name: &str) -> bool {
+    SENSITIVE_BODY_FIELDS.iter().any(|f| f.eq_ignore_ascii_case(name))
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_sensitive_field(key) {
+                    *value = Value::String("[REDACTED]".to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+/// Redact secrets from a JSON or form-encoded body; anything else is kept as text
+fn redact_body(bytes: &[u8]) -> String {
+    let bytes = &bytes[..bytes.len().min(MAX_CAPTURED_BODY_BYTES)];
+    let text = String::from_utf8_lossy(bytes).into_owned();
+
+    if let Ok(mut json) = serde_json::from_str::<Value>(&text) {
+        redact_json(&mut json);
+        return json.to_string();
+    }
+
+    let looks_like_form = !text.is_empty() && !text.contains(char::is_whitespace) && text.contains('=');
+    if looks_like_form {
+        return url::form_urlencoded::parse(text.as_bytes())
+            .map(|(name, value)| {
+                let value = if is_sensitive_field(&name) { "[REDACTED]".into() } else { value };
+                format!("{}={}", urlencoding::encode(&name), urlencoding::encode(&value))
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+    }
+
+    text
+}
+
+/// Turn capture on for the next `count` requests (20 by default, at most 100), or off.
+/// Capture switches itself off after 30 minutes; turning it off also discards what was captured.
+#[tauri::command]
+pub fn set_capture(log: State<'_, RequestLog>, enabled: bool, count: Option<u32>) -> CaptureStatus {
+    let mut state = log.capture.lock();
+    if enabled {
+        state.remaining = count.unwrap_or(DEFAULT_CAPTURE_COUNT).clamp(1, MAX_CAPTURE_COUNT);
+        state.expires_at = Some(chrono::Utc::now().timestamp_millis() + CAPTURE_TTL_SECS * 1000);
+    } else {
+        *state = CaptureState::default();
+    }
+    state.status()
+}
+
+#[tauri::command]
+pub fn get_capture_status(log: State<'_, RequestLog>) -> CaptureStatus {
+    log.capture.lock().status()
+}
+
+/// Write the captured exchanges, the recent request log and app/OS details to a zip at `path`.
+/// Returns the number of exchanges included.
+#[tauri::command]
+pub fn export_support_bundle(log: State<'_, RequestLog>, path: String) -> Result<u32, String> {
+    let exchanges = log.capture.lock().exchanges.clone();
+
+    let system = json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "os_family": std::env::consts::FAMILY,
+        "arch": std::env::consts::ARCH,
+        "created_at": chrono::Utc::now().to_rfc3339(),
+    });
+
+    let files = [
+        ("system.json", system),
+        ("captures.json", json!(exchanges)),
+        ("request_log.json", json!(log.snapshot())),
+    ];
+
+    let file = std::fs::File::create(&path)
+        .map_err(|e| format!("Failed to create '{}': {}", path, e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, value) in files {
+        let contents = serde_json::to_vec_pretty(&value)
+            .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+        zip.start_file(name, options)
+            .map_err(|e| format!("Failed to add {} to support bundle: {}", name, e))?;
+        zip.write_all(&contents)
+            .map_err(|e| format!("Failed to write {} to support bundle: {}", name, e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish support bundle: {}", e))?;
+
+    Ok(exchanges.len() as u32)
+}
+
+fn har_headers(headers: &HashMap<String, String>) -> Vec<Value> {
+    let mut headers: Vec<_> = headers.iter().collect();
+    headers.sort();
+    headers.into_iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn header_value<'a>(headers: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// One HAR 1.2 `entries[]` item. Only the overall time is known, so it all goes to `wait`.
+fn har_entry(exchange: &CapturedExchange) -> Value {
+    let started = chrono::DateTime::from_timestamp_millis(exchange.timestamp)
+        .unwrap_or_default()
+        .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let query_string: Vec<Value> = url::Url::parse(&exchange.url_redacted)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut request = json!({
+        "method": exchange.method,
+        "url": exchange.url_redacted,
+        "httpVersion": exchange.http_version,
+        "cookies": [],
+        "headers": har_headers(&exchange.request_headers),
+        "queryString": query_string,
+        "headersSize": -1,
+        "bodySize": exchange.request_body.as_ref().map_or(0, |body| body.len() as i64),
+    });
+    if let Some(ref body) = exchange.request_body {
+        request["postData"] = json!({
+            "mimeType": header_value(&exchange.request_headers, "content-type").unwrap_or(""),
+            "text": body,
+        });
+    }
+
+    let status_text = reqwest::StatusCode::from_u16(exchange.status)
+        .ok()
+        .and_then(|status| status.canonical_reason())
+        .unwrap_or("");
+    json!({
+        "startedDateTime": started,
+        "time": exchange.elapsed_ms,
+        "request": request,
+        "response": {
+            "status": exchange.status,
+            "statusText": status_text,
+            "httpVersion": exchange.http_version,
+            "cookies": [],
+            "headers": har_headers(&exchange.response_headers),
+            "content": {
+                "size": exchange.response_body.len(),
+                "mimeType": header_value(&exchange.response_headers, "content-type").unwrap_or(""),
+                "text": exchange.response_body,
+            },
+            "redirectURL": header_value(&exchange.response_headers, "location").unwrap_or(""),
+            "headersSize": -1,
+            "bodySize": -1,
+        },
+        "cache": {},
+        "timings": { "send": 0, "wait": exchange.elapsed_ms, "receive": 0 },
+    })
+}
+
+/// Write the captured exchanges (see `set_capture`) to `path` as a HAR 1.2 file for browser devtools.
+/// Secrets are redacted as they were captured; the request log's metadata-only entries aren't included.
+#[tauri::command]
+pub fn export_har(log: State<'_, RequestLog>, path: String) -> Result<(), String> {
+    let exchanges = log.capture.lock().exchanges.clone();
+
+    let har = json!({
+        "log": {
+            "version": HAR_VERSION,
+            "creator": { "name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION") },
+            "pages": [],
+            "entries": exchanges.iter().map(har_entry).collect::<Vec<_>>(),
+        }
+    });
+    let contents = serde_json::to_vec_pretty(&har)
+        .map_err(|e| format!("Failed to serialize HAR: {}", e))?;
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Failed to write '{}': {}", path, e))
+}
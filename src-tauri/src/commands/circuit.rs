@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::State;
+
+use crate::commands::http::normalize_base_url;
+use crate::error::{AppError, ErrorKind};
+
+// Consecutive failures (within the window) that open the circuit
+const FAILURE_THRESHOLD: u32 = 5;
+const FAILURE_WINDOW: Duration = Duration::from_secs(60);
+// How long an open circuit fails fast before letting a trial request through
+const COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// Circuit state for one host, as reported to the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitStatus {
+    pub host: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    // Seconds until an open circuit lets a trial request through
+    pub retry_after_secs: Option<u64>,
+}
+
+#[derive(Debug)]
+struct HostCircuit {
+    state: CircuitState,
+    consecutive_failures: u32,
+    first_failure_at: Option<Instant>,
+    opened_at: Option<Instant>,
+    trial_in_flight: bool,
+}
+
+impl Default for HostCircuit {
+    fn default() -> Self {
+        Self {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            first_failure_at: None,
+            opened_at: None,
+            trial_in_flight: false,
+        }
+    }
+}
+
+impl HostCircuit {
+    fn retry_after(&self) -> Option<Duration> {
+        match (self.state, self.opened_at) {
+            (CircuitState::Open, Some(at)) => Some(COOLDOWN.saturating_sub(at.elapsed())),
+            _ => None,
+        }
+    }
+}
+
+/// Per-host circuit breakers (managed state), keyed by URL origin.
+/// Transport failures and 5xx responses count as failures; anything else closes the circuit.
+#[derive(Default)]
+pub struct CircuitBreakers {
+    hosts: Mutex<HashMap<String, HostCircuit>>,
+}
+
+impl CircuitBreakers {
+    /// Fail fast if the host's circuit is open; moves it to half-open (one trial) once the cooldown has passed
+    pub(crate) fn before_request(&self, host: &str) -> Result<(), AppError> {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(circuit) = hosts.get_mut(host) else {
+            return Ok(());
+        };
+
+        match circuit.state {
+            CircuitState::Closed => Ok(()),
+            CircuitState::Open => match circuit.retry_after() {
+                Some(remaining) if !remaining.is_zero() => Err(circuit_open(host, remaining)),
+                _ => {
+                    circuit.state = CircuitState::HalfOpen;
+                    circuit.trial_in_flight = true;
+                    Ok(())
+                }
+            },
+            CircuitState::HalfOpen if circuit.trial_in_flight => Err(circuit_open(host, Duration::ZERO)),
+            CircuitState::HalfOpen => {
+                circuit.trial_in_flight = true;
+                Ok(())
+            }
+        }
+    }
+
+    pub(crate) fn record_success(&self, host: &str) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        hosts.remove(host);
+    }
+
+    /// A request ended without an outcome (cancelled), so free a half-open trial slot it may hold
+    pub(crate) fn release_trial(&self, host: &str) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(circuit) = hosts.get_mut(host) {
+            circuit.trial_in_flight = false;
+        }
+    }
+
+    pub(crate) fn record_failure(&self, host: &str) {
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        let circuit = hosts.entry(host.to_string()).or_default();
+        let now = Instant::now();
+
+        if circuit.state == CircuitState::HalfOpen {
+            // Trial request failed - straight back to open for another cooldown
+            circuit.state = CircuitState::Open;
+            circuit.opened_at = Some(now);
+            circuit.trial_in_flight = false;
+            return;
+        }
+
+        let window_expired = circuit
+            .first_failure_at
+            .is_none_or(|at| now.duration_since(at) > FAILURE_WINDOW);
+        if window_expired {
+            circuit.consecutive_failures = 0;
+            circuit.first_failure_at = Some(now);
+        }
+        circuit.consecutive_failures += 1;
+
+        if circuit.consecutive_failures >= FAILURE_THRESHOLD {
+            circuit.state = CircuitState::Open;
+            circuit.opened_at = Some(now);
+        }
+    }
+
+    fn status(&self, host: &str) -> CircuitStatus {
+        let hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        match hosts.get(host) {
+            Some(circuit) => CircuitStatus {
+                host: host.to_string(),
+                state: circuit.state,
+                consecutive_failures: circuit.consecutive_failures,
+                retry_after_secs: circuit.retry_after().map(|d| d.as_secs()),
+            },
+            None => CircuitStatus {
+                host: host.to_string(),
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                retry_after_secs: None,
+            },
+        }
+    }
+
+    fn reset(&self, host: &str) {
+        self.hosts.lock().unwrap_or_else(|e| e.into_inner()).remove(host);
+    }
+}
+
+fn circuit_open(host: &str, retry_after: Duration) -> AppError {
+    AppError::typed(ErrorKind::CircuitOpen {
+        host: host.to_string(),
+        retry_after_secs: retry_after.as_secs(),
+    })
+}
+
+/// Circuit key for a URL: its origin (`scheme://host[:port]`)
+pub(crate) fn circuit_key(url: &url::Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+fn key_for_base_url(base_url: &str) -> Result<String, String> {
+    let normalized = normalize_base_url(base_url)?;
+    let parsed = url::Url::parse(&normalized)
+        .map_err(|e| format!("Invalid base URL '{}': {}", base_url, e))?;
+    Ok(circuit_key(&parsed))
+}
+
+#[tauri::command]
+pub fn get_circuit_state(circuits: State<'_, CircuitBreakers>, base_url: String) -> Result<CircuitStatus, String> {
+    Ok(circuits.status(&key_for_base_url(&base_url)?))
+}
+
+/// Close a host's circuit immediately (e.g. from a "retry now" button)
+#[tauri::command]
+pub fn reset_circuit(circuits: State<'_, CircuitBreakers>, base_url: String) -> Result<(), String> {
+    circuits.reset(&key_for_base_url(&base_url)?);
+    Ok(())
+}
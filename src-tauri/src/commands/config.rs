@@ -0,0 +1,175 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::network::{parse_tls_version, NetworkConfig, NetworkSettings};
+
+// File in the app data directory holding the saved configuration
+const CONFIG_FILE: &str = "config.json";
+
+/// Every user-facing setting in one place: what `get_config` returns and what's saved to app data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppConfig {
+    #[serde(flatten)]
+    pub network: NetworkSettings,
+    pub max_concurrency: u32,
+}
+
+/// Changes for `update_config`. A field left out stays as it is; `null` on a nullable setting puts it
+/// back to its default.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AppConfigPatch {
+    #[serde(default, deserialize_with = "nullable")]
+    pub user_agent: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub accept_language: Option<Option<String>>,
+    pub http2_prior_knowledge: Option<bool>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub pool_max_idle_per_host: Option<Option<usize>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub pool_idle_timeout_secs: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub min_tls_version: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub timeout_seconds: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub connect_timeout_seconds: Option<Option<u64>>,
+    pub max_concurrency: Option<u32>,
+}
+
+/// Tells a field sent as `null` (`Some(None)`) apart from one left out (`None`, via `default`)
+fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join(CONFIG_FILE))
+}
+
+/// Write via a temporary file so a crash mid-write can't leave a truncated config
+fn write_config(path: &PathBuf, config: &AppConfig) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)
+        .map_err(|e| format!("Failed to write config: {}", e))?;
+    std::fs::rename(&tmp, path)
+        .map_err(|e| format!("Failed to save config: {}", e))
+}
+
+/// Trimmed, with blank meaning "not set"
+fn non_blank(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Reject settings that would make every later request fail
+fn validate(config: &AppConfig) -> Result<(), String> {
+    let settings = &config.network;
+    for (name, value) in [("user agent", &settings.user_agent), ("Accept-Language", &settings.accept_language)] {
+        if let Some(value) = value {
+            reqwest::header::HeaderValue::from_str(value)
+                .map_err(|e| format!("Invalid {} '{}': {}", name, value, e))?;
+        }
+    }
+    if let Some(ref version) = settings.min_tls_version {
+        parse_tls_version(version)?;
+    }
+    if settings.timeout_seconds == Some(0) || settings.connect_timeout_seconds == Some(0) {
+        return Err("Timeouts must be at least 1 second".to_string());
+    }
+    if config.max_concurrency == 0 {
+        return Err("max_concurrency must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+fn current_config(network: &NetworkConfig) -> AppConfig {
+    AppConfig {
+        network: network.settings(),
+        max_concurrency: network.scheduler.limit() as u32,
+    }
+}
+
+/// Make `config` the running configuration, dropping the cached clients only if a network setting changed
+fn apply_config(network: &NetworkConfig, config: &AppConfig) {
+    if network.settings() != config.network {
+        network.update(|settings| *settings = config.network.clone());
+    }
+    network.scheduler.set_limit(config.max_concurrency as usize);
+}
+
+/// Apply the configuration saved by `update_config`, if any. Called once at startup.
+pub(crate) fn load_saved_config(app: &AppHandle) -> Result<(), String> {
+    let path = config_path(app)?;
+    let json = match std::fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read config '{}': {}", path.display(), e)),
+    };
+    let config: AppConfig = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse config '{}': {}", path.display(), e))?;
+    validate(&config)?;
+    apply_config(&app.state::<NetworkConfig>(), &config);
+    Ok(())
+}
+
+/// The running configuration. The individual `set_*` commands change it for this session only;
+/// `update_config` also saves it.
+#[tauri::command]
+pub fn get_config(network: State<'_, NetworkConfig>) -> AppConfig {
+    current_config(&network)
+}
+
+/// Merge `partial` into the running configuration and save the result to app data, where it's
+/// reapplied at startup. Shared clients are rebuilt only when a network setting actually changed.
+#[tauri::command]
+pub fn update_config(
+    app: AppHandle,
+    network: State<'_, NetworkConfig>,
+    partial: AppConfigPatch,
+) -> Result<AppConfig, String> {
+    let mut config = current_config(&network);
+    let settings = &mut config.network;
+    if let Some(user_agent) = partial.user_agent {
+        settings.user_agent = non_blank(user_agent);
+    }
+    if let Some(accept_language) = partial.accept_language {
+        settings.accept_language = non_blank(accept_language);
+    }
+    if let Some(http2_prior_knowledge) = partial.http2_prior_knowledge {
+        settings.http2_prior_knowledge = http2_prior_knowledge;
+    }
+    if let Some(pool_max_idle_per_host) = partial.pool_max_idle_per_host {
+        settings.pool_max_idle_per_host = pool_max_idle_per_host;
+    }
+    if let Some(pool_idle_timeout_secs) = partial.pool_idle_timeout_secs {
+        settings.pool_idle_timeout_secs = pool_idle_timeout_secs;
+    }
+    if let Some(min_tls_version) = partial.min_tls_version {
+        settings.min_tls_version = non_blank(min_tls_version);
+    }
+    if let Some(timeout_seconds) = partial.timeout_seconds {
+        settings.timeout_seconds = timeout_seconds;
+    }
+    if let Some(connect_timeout_seconds) = partial.connect_timeout_seconds {
+        settings.connect_timeout_seconds = connect_timeout_seconds;
+    }
+    if let Some(max_concurrency) = partial.max_concurrency {
+        config.max_concurrency = max_concurrency;
+    }
+    validate(&config)?;
+
+    // Saved first, so a config that can't be persisted isn't left half-applied
+    write_config(&config_path(&app)?, &config)?;
+    apply_config(&network, &config);
+    Ok(config)
+}
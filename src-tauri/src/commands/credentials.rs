@@ -1,46 +1,113 @@
-use keyring::Entry;
+use serde::{Deserialize, Serialize};
 use serde_json;
+use tauri::State;
+
+use crate::commands::profiles::{
+    add_to_profile_index, has_profile_index, listed_profile_names, prune_profile_index, read_default_profile,
+    read_profile_index, write_profile_index, ProfileLocks,
+};
+use crate::keystore::KeyStore;
+use crate::types::{Credentials, LoginCredentials, AppTokenCredentials, CredentialStoreStatus, KeyringStatus};
+
+// Keyring entry recording which layout the stored credential entries use
+const SCHEMA_VERSION_KEY: &str = "schema-version";
+
+// Credential entry layouts, oldest first:
+//   1 - `profile:`/`login:`/`apptoken:` entries only, no profile index, sessions without issued_at
+//   2 - every profile listed in the profile index, sessions stamped with issued_at
+pub(crate) const CREDENTIAL_SCHEMA_VERSION: u32 = 2;
+
+/// What `migrate_credentials` changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+    // Profiles whose entries were rewritten
+    pub migrated: Vec<String>,
+}
+
+pub(crate) fn profile_key(profile_name: &str) -> String {
+    format!("profile:{}", profile_name)
+}
+
+pub(crate) fn login_key(profile_name: &str) -> String {
+    format!("login:{}", profile_name)
+}
+
+pub(crate) fn apptoken_key(profile_name: &str) -> String {
+    format!("apptoken:{}", profile_name)
+}
+
+/// Index the profile and write one of its entries, all under the profile lock, so a concurrent
+/// delete of its other entries can't prune the profile from the index in between. Indexing comes
+/// first so that a master key rotation (which locks every indexed profile) can't miss the entry.
+async fn save_profile_entry(store: &KeyStore, locks: &ProfileLocks, profile_name: &str, key: &str, json: &str, what: &str) -> Result<(), String> {
+    let _guard = locks.profile(profile_name).await;
+
+    add_to_profile_index(store, locks, profile_name).await?;
+    if let Err(e) = store.set(key, json) {
+        prune_profile_index(store, locks, profile_name).await?;
+        return Err(format!("Failed to save {} to keyring: {}", what, e));
+    }
+    Ok(())
+}
 
-use crate::types::{Credentials, LoginCredentials, AppTokenCredentials};
+/// Delete one of a profile's entries, dropping the profile from the index if it was the last one
+async fn delete_profile_entry(store: &KeyStore, locks: &ProfileLocks, profile_name: &str, key: &str, what: &str) -> Result<(), String> {
+    let _guard = locks.profile(profile_name).await;
 
-const SERVICE_NAME: &str = "monash-nimbus-reports";
+    store.delete(key)
+        .map_err(|e| format!("Failed to delete {} from keyring: {}", what, e))?;
 
-fn get_entry(profile_name: &str) -> Result<Entry, String> {
-    let key = format!("profile:{}", profile_name);
-    Entry::new(SERVICE_NAME, &key)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))
+    prune_profile_index(store, locks, profile_name).await
 }
 
-fn get_login_entry(profile_name: &str) -> Result<Entry, String> {
-    let key = format!("login:{}", profile_name);
-    Entry::new(SERVICE_NAME, &key)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))
+/// Report whether credentials are being held in memory because the OS keyring is unavailable
+#[tauri::command]
+pub fn get_credential_store_status(store: State<'_, KeyStore>) -> CredentialStoreStatus {
+    CredentialStoreStatus {
+        keyring_unavailable: store.keyring_unavailable(),
+        fallback_enabled: store.fallback_enabled(),
+        master_key_enabled: store.master_key_enabled().unwrap_or(false),
+        master_key_unlocked: store.master_key_unlocked(),
+    }
 }
 
-fn get_apptoken_entry(profile_name: &str) -> Result<Entry, String> {
-    let key = format!("apptoken:{}", profile_name);
-    Entry::new(SERVICE_NAME, &key)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))
+/// Check that the OS keyring actually works with a throwaway write/read/delete, and name its backend.
+/// Use this to diagnose "credentials don't persist" reports.
+#[tauri::command]
+pub async fn keyring_status(store: State<'_, KeyStore>) -> Result<KeyringStatus, String> {
+    let result = store.probe_keyring();
+    Ok(KeyringStatus {
+        available: result.is_ok(),
+        backend: KeyStore::backend_name().to_string(),
+        error: result.err(),
+    })
 }
 
 #[tauri::command]
-pub async fn save_credentials(profile_name: String, credentials: Credentials) -> Result<(), String> {
-    let entry = get_entry(&profile_name)?;
+pub async fn save_credentials(store: State<'_, KeyStore>, locks: State<'_, ProfileLocks>, profile_name: String, mut credentials: Credentials) -> Result<(), String> {
+    if credentials.issued_at.is_none() {
+        credentials.issued_at = Some(chrono::Utc::now().timestamp());
+    }
+
+    // Default headers go on every request for this profile, so reject bad ones now rather than on each call
+    for (name, value) in &credentials.default_headers {
+        reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid default header name '{}': {}", name, e))?;
+        reqwest::header::HeaderValue::from_str(value)
+            .map_err(|e| format!("Invalid default header value for '{}': {}", name, e))?;
+    }
 
     let credentials_json = serde_json::to_string(&credentials)
         .map_err(|e| format!("Failed to serialize credentials: {}", e))?;
 
-    entry.set_password(&credentials_json)
-        .map_err(|e| format!("Failed to save credentials to keyring: {}", e))?;
-
-    Ok(())
+    save_profile_entry(&store, &locks, &profile_name, &profile_key(&profile_name), &credentials_json, "credentials").await
 }
 
 #[tauri::command]
-pub async fn load_credentials(profile_name: String) -> Result<Credentials, String> {
-    let entry = get_entry(&profile_name)?;
-
-    let credentials_json = entry.get_password()
+pub async fn load_credentials(store: State<'_, KeyStore>, profile_name: String) -> Result<Credentials, String> {
+    let credentials_json = store.get(&profile_key(&profile_name))
         .map_err(|e| format!("Failed to load credentials from keyring: {}", e))?;
 
     let credentials: Credentials = serde_json::from_str(&credentials_json)
@@ -50,35 +117,46 @@ pub async fn load_credentials(profile_name: String) -> Result<Credentials, Strin
 }
 
 #[tauri::command]
-pub async fn delete_credentials(profile_name: String) -> Result<(), String> {
-    let entry = get_entry(&profile_name)?;
+pub async fn delete_credentials(store: State<'_, KeyStore>, locks: State<'_, ProfileLocks>, profile_name: String) -> Result<(), String> {
+    delete_profile_entry(&store, &locks, &profile_name, &profile_key(&profile_name), "credentials").await
+}
+
+/// Sign a profile out locally: drop the session token and user id (and the session's issued/expiry
+/// times) but keep the base URL, auth mode and default headers, plus any login or app token entries
+#[tauri::command]
+pub async fn clear_session_token(store: State<'_, KeyStore>, locks: State<'_, ProfileLocks>, profile_name: String) -> Result<(), String> {
+    let _guard = locks.profile(&profile_name).await;
 
-    entry.delete_credential()
-        .map_err(|e| format!("Failed to delete credentials from keyring: {}", e))?;
+    let credentials_json = store.try_get(&profile_key(&profile_name))
+        .map_err(|e| format!("Failed to load credentials from keyring: {}", e))?
+        .ok_or_else(|| format!("No stored credentials for profile '{}'", profile_name))?;
+    let mut credentials: Credentials = serde_json::from_str(&credentials_json)
+        .map_err(|e| format!("Failed to deserialize credentials: {}", e))?;
 
-    Ok(())
+    credentials.auth_token = None;
+    credentials.user_id = None;
+    credentials.issued_at = None;
+    credentials.expires_at = None;
+
+    let credentials_json = serde_json::to_string(&credentials)
+        .map_err(|e| format!("Failed to serialize credentials: {}", e))?;
+    store.set(&profile_key(&profile_name), &credentials_json)
+        .map_err(|e| format!("Failed to save credentials to keyring: {}", e))
 }
 
 // Login credentials (username/password) - separate from session tokens
 
 #[tauri::command]
-pub async fn save_login_credentials(profile_name: String, credentials: LoginCredentials) -> Result<(), String> {
-    let entry = get_login_entry(&profile_name)?;
-
+pub async fn save_login_credentials(store: State<'_, KeyStore>, locks: State<'_, ProfileLocks>, profile_name: String, credentials: LoginCredentials) -> Result<(), String> {
     let credentials_json = serde_json::to_string(&credentials)
         .map_err(|e| format!("Failed to serialize login credentials: {}", e))?;
 
-    entry.set_password(&credentials_json)
-        .map_err(|e| format!("Failed to save login credentials to keyring: {}", e))?;
-
-    Ok(())
+    save_profile_entry(&store, &locks, &profile_name, &login_key(&profile_name), &credentials_json, "login credentials").await
 }
 
 #[tauri::command]
-pub async fn load_login_credentials(profile_name: String) -> Result<LoginCredentials, String> {
-    let entry = get_login_entry(&profile_name)?;
-
-    let credentials_json = entry.get_password()
+pub async fn load_login_credentials(store: State<'_, KeyStore>, profile_name: String) -> Result<LoginCredentials, String> {
+    let credentials_json = store.get(&login_key(&profile_name))
         .map_err(|e| format!("Failed to load login credentials from keyring: {}", e))?;
 
     let credentials: LoginCredentials = serde_json::from_str(&credentials_json)
@@ -88,35 +166,43 @@ pub async fn load_login_credentials(profile_name: String) -> Result<LoginCredent
 }
 
 #[tauri::command]
-pub async fn delete_login_credentials(profile_name: String) -> Result<(), String> {
-    let entry = get_login_entry(&profile_name)?;
+pub async fn delete_login_credentials(store: State<'_, KeyStore>, locks: State<'_, ProfileLocks>, profile_name: String) -> Result<(), String> {
+    delete_profile_entry(&store, &locks, &profile_name, &login_key(&profile_name), "login credentials").await
+}
+
+/// Forget the stored password but keep the login entry (and its username) and the profile.
+/// The user is asked for the password again the next time the session needs renewing.
+#[tauri::command]
+pub async fn clear_login_password(store: State<'_, KeyStore>, locks: State<'_, ProfileLocks>, profile_name: String) -> Result<(), String> {
+    let _guard = locks.profile(&profile_name).await;
 
-    entry.delete_credential()
-        .map_err(|e| format!("Failed to delete login credentials from keyring: {}", e))?;
+    let credentials_json = store.try_get(&login_key(&profile_name))
+        .map_err(|e| format!("Failed to load login credentials from keyring: {}", e))?
+        .ok_or_else(|| format!("No stored login credentials for profile '{}'", profile_name))?;
+    let mut credentials: LoginCredentials = serde_json::from_str(&credentials_json)
+        .map_err(|e| format!("Failed to deserialize login credentials: {}", e))?;
 
-    Ok(())
+    // Overwriting the keyring entry replaces the only persisted copy of the password
+    credentials.password.clear();
+    let credentials_json = serde_json::to_string(&credentials)
+        .map_err(|e| format!("Failed to serialize login credentials: {}", e))?;
+    store.set(&login_key(&profile_name), &credentials_json)
+        .map_err(|e| format!("Failed to save login credentials to keyring: {}", e))
 }
 
 // App Token credentials (app_token/username) - for App Token auth mode
 
 #[tauri::command]
-pub async fn save_apptoken_credentials(profile_name: String, credentials: AppTokenCredentials) -> Result<(), String> {
-    let entry = get_apptoken_entry(&profile_name)?;
-
+pub async fn save_apptoken_credentials(store: State<'_, KeyStore>, locks: State<'_, ProfileLocks>, profile_name: String, credentials: AppTokenCredentials) -> Result<(), String> {
     let credentials_json = serde_json::to_string(&credentials)
         .map_err(|e| format!("Failed to serialize app token credentials: {}", e))?;
 
-    entry.set_password(&credentials_json)
-        .map_err(|e| format!("Failed to save app token credentials to keyring: {}", e))?;
-
-    Ok(())
+    save_profile_entry(&store, &locks, &profile_name, &apptoken_key(&profile_name), &credentials_json, "app token credentials").await
 }
 
 #[tauri::command]
-pub async fn load_apptoken_credentials(profile_name: String) -> Result<AppTokenCredentials, String> {
-    let entry = get_apptoken_entry(&profile_name)?;
-
-    let credentials_json = entry.get_password()
+pub async fn load_apptoken_credentials(store: State<'_, KeyStore>, profile_name: String) -> Result<AppTokenCredentials, String> {
+    let credentials_json = store.get(&apptoken_key(&profile_name))
         .map_err(|e| format!("Failed to load app token credentials from keyring: {}", e))?;
 
     let credentials: AppTokenCredentials = serde_json::from_str(&credentials_json)
@@ -126,11 +212,253 @@ pub async fn load_apptoken_credentials(profile_name: String) -> Result<AppTokenC
 }
 
 #[tauri::command]
-pub async fn delete_apptoken_credentials(profile_name: String) -> Result<(), String> {
-    let entry = get_apptoken_entry(&profile_name)?;
+pub async fn delete_apptoken_credentials(store: State<'_, KeyStore>, locks: State<'_, ProfileLocks>, profile_name: String) -> Result<(), String> {
+    delete_profile_entry(&store, &locks, &profile_name, &apptoken_key(&profile_name), "app token credentials").await
+}
+
+/// Session, login and app token entries of every indexed profile - the keyring can't be listed
+fn credential_keys(store: &KeyStore) -> Result<Vec<String>, String> {
+    Ok(read_profile_index(store)?
+        .iter()
+        .flat_map(|name| [profile_key(name), login_key(name), apptoken_key(name)])
+        .collect())
+}
 
-    entry.delete_credential()
-        .map_err(|e| format!("Failed to delete app token credentials from keyring: {}", e))?;
+/// Re-seal every indexed profile's entries while holding all the profile locks, so no save or
+/// session refresh lands between the rotation reading an entry and writing it back
+async fn reseal_credentials(
+    store: &KeyStore,
+    locks: &ProfileLocks,
+    old_passphrase: Option<&str>,
+    new_passphrase: &str,
+) -> Result<u32, String> {
+    let (_names, _profile_guards, _index_guard) = locks.all_profiles(store).await?;
+    store.rotate_master_key(&credential_keys(store)?, old_passphrase, new_passphrase)
+}
 
-    Ok(())
+/// Seal every stored credential under a new app-level master passphrase, on top of the OS keyring.
+/// From then on the store has to be unlocked each session with `unlock_master_key`. Returns how many entries were sealed.
+#[tauri::command]
+pub async fn enable_master_key(
+    store: State<'_, KeyStore>,
+    locks: State<'_, ProfileLocks>,
+    passphrase: String,
+) -> Result<u32, String> {
+    reseal_credentials(&store, &locks, None, &passphrase).await
+}
+
+/// Unlock the master-passphrase-sealed credentials for this session
+#[tauri::command]
+pub async fn unlock_master_key(store: State<'_, KeyStore>, passphrase: String) -> Result<(), String> {
+    store.unlock_master_key(&passphrase)
+}
+
+/// Re-encrypt every stored credential under a new master passphrase, returning how many were rotated.
+/// Nothing is written unless every entry decrypts with `old_passphrase`.
+#[tauri::command]
+pub async fn rotate_master_key(
+    store: State<'_, KeyStore>,
+    locks: State<'_, ProfileLocks>,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<u32, String> {
+    reseal_credentials(&store, &locks, Some(&old_passphrase), &new_passphrase).await
+}
+
+/// Layout of the stored entries; a store without the marker predates it (version 1)
+pub(crate) fn stored_schema_version(store: &KeyStore) -> Result<u32, String> {
+    match store.try_get(SCHEMA_VERSION_KEY)? {
+        Some(version) => version.trim().parse()
+            .map_err(|e| format!("Invalid credential schema version '{}': {}", version, e)),
+        None => Ok(1),
+    }
+}
+
+/// Re-read an entry in whatever older shape it was saved in and write it back in the current one
+fn rewrite_entry<T: Serialize + serde::de::DeserializeOwned>(
+    store: &KeyStore,
+    key: &str,
+    upgrade: impl FnOnce(&mut T),
+) -> Result<bool, String> {
+    let Some(json) = store.try_get(key)? else {
+        return Ok(false);
+    };
+    let mut value: T = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to deserialize '{}' during migration: {}", key, e))?;
+    upgrade(&mut value);
+    let json = serde_json::to_string(&value)
+        .map_err(|e| format!("Failed to serialize '{}' during migration: {}", key, e))?;
+    store.set(key, &json)?;
+    Ok(true)
+}
+
+/// Bring stored entries from `from_version` up to the current layout, then record the new version.
+/// Safe to run repeatedly: entries already in the current layout are rewritten unchanged.
+/// The keyring can't be listed, so profiles missing from the index are only found via `extra_profiles`,
+/// the default profile, or a listable (in-memory) store. A version 1 store with no index and no
+/// profile found that way is left at version 1, so a later call with `extra_profiles` still upgrades it.
+pub(crate) fn migrate_store(
+    store: &KeyStore,
+    from_version: u32,
+    extra_profiles: &[String],
+) -> Result<MigrationReport, String> {
+    let mut migrated = Vec::new();
+
+    if from_version < 2 {
+        let had_index = has_profile_index(store)?;
+        let mut names = read_profile_index(store)?;
+        let discovered = listed_profile_names(store).unwrap_or_default();
+        let default = read_default_profile(store)?;
+        for name in extra_profiles.iter().chain(&discovered).chain(&default) {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        if !had_index && names.is_empty() {
+            return Ok(MigrationReport { from_version, to_version: from_version, migrated });
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        let mut indexed = Vec::new();
+        for name in names {
+            let session = rewrite_entry::<Credentials>(store, &profile_key(&name), |credentials| {
+                credentials.issued_at.get_or_insert(now);
+            })?;
+            let login = rewrite_entry::<LoginCredentials>(store, &login_key(&name), |_| {})?;
+            let apptoken = rewrite_entry::<AppTokenCredentials>(store, &apptoken_key(&name), |_| {})?;
+            if session || login || apptoken {
+                indexed.push(name.clone());
+                migrated.push(name);
+            }
+        }
+        write_profile_index(store, &indexed)?;
+    }
+
+    let to_version = from_version.max(CREDENTIAL_SCHEMA_VERSION);
+    store.set(SCHEMA_VERSION_KEY, &to_version.to_string())
+        .map_err(|e| format!("Failed to save credential schema version: {}", e))?;
+
+    Ok(MigrationReport { from_version, to_version, migrated })
+}
+
+/// Migrate on startup when the stored layout is older than this build's
+pub(crate) fn migrate_on_startup(store: &KeyStore) -> Result<Option<MigrationReport>, String> {
+    let version = stored_schema_version(store)?;
+    if version >= CREDENTIAL_SCHEMA_VERSION {
+        return Ok(None);
+    }
+    let report = migrate_store(store, version, &[])?;
+    Ok((report.to_version > version).then_some(report))
+}
+
+/// Rewrite stored credentials from an older layout into the current one.
+/// `from_version` defaults to the stored marker; `profile_names` adds profiles the index doesn't know about yet.
+#[tauri::command]
+pub async fn migrate_credentials(
+    store: State<'_, KeyStore>,
+    locks: State<'_, ProfileLocks>,
+    from_version: Option<u32>,
+    profile_names: Option<Vec<String>>,
+) -> Result<MigrationReport, String> {
+    let _guard = locks.index().await;
+    let from_version = match from_version {
+        Some(version) => version,
+        None => stored_schema_version(&store)?,
+    };
+    migrate_store(&store, from_version, &profile_names.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    // A session as version 1 saved it: no issued_at, and no profile index alongside it
+    const V1_SESSION: &str = r#"{"base_url":"https://nimbus.example.edu","auth_mode":"credential","user_id":7,"auth_token":"t"}"#;
+
+    #[test]
+    fn upgrade_without_index_indexes_existing_profiles() {
+        let store = KeyStore::in_memory();
+        store.set(&profile_key("Prod"), V1_SESSION).unwrap();
+
+        let report = migrate_on_startup(&store).unwrap().expect("a version 1 store should migrate");
+        assert_eq!((report.from_version, report.to_version), (1, CREDENTIAL_SCHEMA_VERSION));
+        assert_eq!(report.migrated, vec!["Prod".to_string()]);
+        assert_eq!(read_profile_index(&store).unwrap(), vec!["Prod".to_string()]);
+        assert_eq!(stored_schema_version(&store).unwrap(), CREDENTIAL_SCHEMA_VERSION);
+
+        let credentials: Credentials = serde_json::from_str(&store.get(&profile_key("Prod")).unwrap()).unwrap();
+        assert!(credentials.issued_at.is_some());
+    }
+
+    #[test]
+    fn upgrade_without_index_or_profiles_stays_on_version_1() {
+        let store = KeyStore::in_memory();
+
+        assert!(migrate_on_startup(&store).unwrap().is_none());
+        assert_eq!(stored_schema_version(&store).unwrap(), 1);
+        assert!(!has_profile_index(&store).unwrap());
+    }
+
+    #[test]
+    fn upgrade_picks_up_profiles_named_by_the_caller() {
+        let store = KeyStore::in_memory();
+        store.set(&login_key("Test"), r#"{"username":"u","password":"p"}"#).unwrap();
+
+        let report = migrate_store(&store, 1, &["Test".to_string(), "Gone".to_string()]).unwrap();
+        assert_eq!(report.to_version, CREDENTIAL_SCHEMA_VERSION);
+        assert_eq!(report.migrated, vec!["Test".to_string()]);
+        assert_eq!(read_profile_index(&store).unwrap(), vec!["Test".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn parallel_saves_of_different_profiles_are_all_indexed() {
+        let (store, locks) = (Arc::new(KeyStore::in_memory()), Arc::new(ProfileLocks::default()));
+        let names: Vec<String> = (0..64).map(|i| format!("Profile{:02}", i)).collect();
+
+        let tasks: Vec<_> = names
+            .iter()
+            .map(|name| {
+                let (store, locks, name) = (store.clone(), locks.clone(), name.clone());
+                tokio::spawn(async move {
+                    let json = r#"{"username":"u","password":"p"}"#;
+                    save_profile_entry(&store, &locks, &name, &login_key(&name), json, "login credentials").await
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        let mut indexed = read_profile_index(&store).unwrap();
+        indexed.sort();
+        assert_eq!(indexed, names);
+        assert!(names.iter().all(|name| store.try_get(&login_key(name)).unwrap().is_some()));
+    }
+
+    #[tokio::test]
+    async fn a_master_key_rotation_waits_for_profile_writes() {
+        let (store, locks) = (Arc::new(KeyStore::in_memory()), Arc::new(ProfileLocks::default()));
+        let login = |password: &str| format!(r#"{{"username":"u","password":"{}"}}"#, password);
+        for name in ["Prod", "Test"] {
+            save_profile_entry(&store, &locks, name, &login_key(name), &login("before"), "login credentials").await.unwrap();
+        }
+        reseal_credentials(&store, &locks, None, "first").await.unwrap();
+
+        // A session refresh holds only the profile lock while it rewrites the entry
+        let guard = locks.profile("Prod").await;
+        let mut rotation = tokio::spawn({
+            let (store, locks) = (store.clone(), locks.clone());
+            async move { reseal_credentials(&store, &locks, Some("first"), "second").await }
+        });
+        assert!(tokio::time::timeout(Duration::from_secs(1), &mut rotation).await.is_err());
+        store.set(&login_key("Prod"), &login("after")).unwrap();
+        drop(guard);
+        assert_eq!(rotation.await.unwrap().unwrap(), 2);
+
+        store.unlock_master_key("second").unwrap();
+        let prod: LoginCredentials = serde_json::from_str(&store.get(&login_key("Prod")).unwrap()).unwrap();
+        assert_eq!(prod.password, "after");
+    }
 }
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+
+use crate::commands::odata::unwrap_odata_value;
+
+/// `value` with duplicate rows dropped, in the shape it was passed, and how many were dropped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupResult {
+    pub value: Value,
+    pub removed: u32,
+}
+
+/// Drop rows whose `key_field` repeats an earlier row's, keeping the first and the original order.
+/// Accepts `{ value: [...] }` (other top-level fields such as `@odata.count` are kept) or a bare array.
+/// Rows without the key (or with a null one) can't be matched, so they're all kept.
+#[tauri::command]
+pub fn dedup_odata(value: Value, key_field: String) -> Result<DedupResult, String> {
+    let mut seen = HashSet::new();
+    let mut removed = 0u32;
+    let rows: Vec<Value> = unwrap_odata_value(&value)?
+        .iter()
+        .filter(|row| match row.get(&key_field) {
+            Some(Value::Null) | None => true,
+            Some(key) => {
+                let first = seen.insert(key.to_string());
+                if !first {
+                    removed += 1;
+                }
+                first
+            }
+        })
+        .cloned()
+        .collect();
+
+    let value = match value {
+        Value::Object(mut object) => {
+            object.insert("value".to_string(), Value::Array(rows));
+            Value::Object(object)
+        }
+        _ => Value::Array(rows),
+    };
+
+    Ok(DedupResult { value, removed })
+}
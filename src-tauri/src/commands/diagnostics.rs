@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::commands::capture::CaptureBuffer;
+
+// How many recent requests the activity log keeps
+const REQUEST_LOG_CAPACITY: usize = 200;
+
+// Query parameters whose values are safe to show; everything else (filters, tokens, ...) is redacted
+const SAFE_QUERY_PARAMS: &[&str] = &["$top", "$skip", "$select", "$orderby", "$count", "$format"];
+
+/// One executed request, as shown in the "recent activity" view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestLogEntry {
+    // Unix time in milliseconds when the request was sent
+    pub timestamp: i64,
+    pub method: String,
+    pub url_redacted: String,
+    // None when no response came back (DNS, connection or timeout failure)
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+}
+
+/// Bounded in-memory log of recent requests (managed state, not persisted)
+#[derive(Default)]
+pub struct RequestLog {
+    entries: Mutex<VecDeque<RequestLogEntry>>,
+    // Full request/response capture for support bundles, off unless set_capture turns it on
+    pub(crate) capture: CaptureBuffer,
+}
+
+impl RequestLog {
+    pub(crate) fn record(&self, entry: RequestLogEntry) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= REQUEST_LOG_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    pub(crate) fn snapshot(&self) -> Vec<RequestLogEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.iter().cloned().collect()
+    }
+
+    fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+// Upper bounds (ms) of the latency histogram buckets; slower queries land in a final overflow bucket
+const LATENCY_BUCKETS_MS: &[u64] = &[10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000];
+
+/// Timing summary for one entity, for the performance view.
+/// Percentiles are bucket upper bounds (capped at `max_ms`), so they're approximate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityMetrics {
+    pub entity: String,
+    pub count: u64,
+    pub min_ms: u64,
+    pub max_ms: u64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+}
+
+#[derive(Debug)]
+struct EntityTimings {
+    count: u64,
+    min_ms: u64,
+    max_ms: u64,
+    buckets: [u64; LATENCY_BUCKETS_MS.len() + 1],
+}
+
+impl EntityTimings {
+    fn new() -> Self {
+        Self { count: 0, min_ms: u64::MAX, max_ms: 0, buckets: [0; LATENCY_BUCKETS_MS.len() + 1] }
+    }
+
+    fn record(&mut self, duration_ms: u64) {
+        self.count += 1;
+        self.min_ms = self.min_ms.min(duration_ms);
+        self.max_ms = self.max_ms.max(duration_ms);
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| duration_ms <= bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+    }
+
+    fn percentile(&self, p: f64) -> u64 {
+        let target = ((self.count as f64) * p).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= target {
+                return LATENCY_BUCKETS_MS.get(i).map_or(self.max_ms, |&bound| bound.min(self.max_ms));
+            }
+        }
+        self.max_ms
+    }
+}
+
+/// Per-entity OData query timings (managed state, not persisted)
+#[derive(Default)]
+pub struct QueryMetrics {
+    entities: Mutex<HashMap<String, EntityTimings>>,
+}
+
+impl QueryMetrics {
+    pub(crate) fn record(&self, entity: &str, duration_ms: u64) {
+        let mut entities = self.entities.lock().unwrap_or_else(|e| e.into_inner());
+        entities
+            .entry(entity.to_string())
+            .or_insert_with(EntityTimings::new)
+            .record(duration_ms);
+    }
+}
+
+/// Strip credentials and sensitive query values from a URL before it's logged
+pub(crate) fn redact_url(url: &url::Url) -> String {
+    let mut redacted = url.clone();
+    let _ = redacted.set_username("");
+    let _ = redacted.set_password(None);
+
+    if url.query().is_some() {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(name, value)| {
+                let value = if SAFE_QUERY_PARAMS.iter().any(|p| p.eq_ignore_ascii_case(&name)) {
+                    value.into_owned()
+                } else {
+                    "[REDACTED]".to_string()
+                };
+                (name.into_owned(), value)
+            })
+            .collect();
+        redacted.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+
+    redacted.to_string()
+}
+
+/// Recent requests, oldest first
+#[tauri::command]
+pub fn get_request_log(log: State<'_, RequestLog>) -> Vec<RequestLogEntry> {
+    log.snapshot()
+}
+
+#[tauri::command]
+pub fn clear_request_log(log: State<'_, RequestLog>) {
+    log.clear();
+}
+
+/// Timing stats per entity, slowest (p95) first
+#[tauri::command]
+pub fn get_query_metrics(metrics: State<'_, QueryMetrics>) -> Vec<EntityMetrics> {
+    let entities = metrics.entities.lock().unwrap_or_else(|e| e.into_inner());
+    let mut result: Vec<EntityMetrics> = entities
+        .iter()
+        .map(|(entity, timings)| EntityMetrics {
+            entity: entity.clone(),
+            count: timings.count,
+            min_ms: timings.min_ms,
+            max_ms: timings.max_ms,
+            p50_ms: timings.percentile(0.50),
+            p95_ms: timings.percentile(0.95),
+        })
+        .collect();
+    result.sort_by_key(|m| std::cmp::Reverse(m.p95_ms));
+    result
+}
+
+#[tauri::command]
+pub fn reset_query_metrics(metrics: State<'_, QueryMetrics>) {
+    metrics.entities.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::commands::odata::unwrap_odata_value;
+
+type Row = Map<String, Value>;
+
+/// A row present in both result sets whose fields differ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RowChange {
+    pub key: Value,
+    pub before: Value,
+    pub after: Value,
+    // Fields changed or removed, then fields added, each alphabetically (serde_json keeps keys sorted)
+    pub fields: Vec<String>,
+}
+
+/// Rows added, removed and changed between two runs of the same query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffResult {
+    pub added: Vec<Value>,
+    pub removed: Vec<Value>,
+    pub changed: Vec<RowChange>,
+}
+
+/// The rows of `{ value: [...] }` or a bare array
+fn diff_rows(result: &Value, which: &str) -> Result<Vec<Value>, String> {
+    unwrap_odata_value(result)
+        .cloned()
+        .map_err(|e| format!("In the {} result: {}", which, e))
+}
+
+/// Index rows by their key, in their original order. Every row must be an object with a unique, non-null key.
+fn keyed_rows(rows: Vec<Value>, key_field: &str, which: &str) -> Result<Vec<(String, Row)>, String> {
+    let mut seen = HashMap::new();
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let Value::Object(row) = row else {
+                return Err(format!("Row {} of the {} result is not an object", i, which));
+            };
+            let key = match row.get(key_field) {
+                Some(Value::Null) | None => {
+                    return Err(format!("Row {} of the {} result has no '{}'", i, which, key_field));
+                }
+                Some(key) => key.to_string(),
+            };
+            if let Some(first) = seen.insert(key.clone(), i) {
+                return Err(format!(
+                    "Rows {} and {} of the {} result share the {} {}",
+                    first, i, which, key_field, key
+                ));
+            }
+            Ok((key, row))
+        })
+        .collect()
+}
+
+/// Data fields (not `@odata` annotations) whose values differ between the two rows
+fn changed_fields(before: &Row, after: &Row) -> Vec<String> {
+    let mut fields: Vec<String> = before.iter()
+        .filter(|(name, value)| !name.contains('@') && after.get(*name) != Some(value))
+        .map(|(name, _)| name.clone())
+        .collect();
+    fields.extend(
+        after.keys()
+            .filter(|name| !name.contains('@') && !before.contains_key(*name))
+            .cloned(),
+    );
+    fields
+}
+
+/// Compare two OData results row by row, matching rows on `key_field` (e.g. an Id column).
+/// Accepts `{ value: [...] }` or bare arrays. `added` and `changed` follow the new result's order,
+/// `removed` the old one's. Annotations such as `@odata.etag` don't count as changes.
+#[tauri::command]
+pub fn diff_odata(old: Value, new: Value, key_field: String) -> Result<DiffResult, String> {
+    let old = keyed_rows(diff_rows(&old, "old")?, &key_field, "old")?;
+    let new = keyed_rows(diff_rows(&new, "new")?, &key_field, "new")?;
+
+    let mut old_by_key: HashMap<String, Row> = HashMap::with_capacity(old.len());
+    let mut old_order = Vec::with_capacity(old.len());
+    for (key, row) in old {
+        old_order.push(key.clone());
+        old_by_key.insert(key, row);
+    }
+
+    let mut added = Vec::new();
+    let mut changed = Vec::new();
+    for (key, after) in new {
+        let Some(before) = old_by_key.remove(&key) else {
+            added.push(Value::Object(after));
+            continue;
+        };
+        let fields = changed_fields(&before, &after);
+        if !fields.is_empty() {
+            changed.push(RowChange {
+                key: after[key_field.as_str()].clone(),
+                before: Value::Object(before),
+                after: Value::Object(after),
+                fields,
+            });
+        }
+    }
+
+    let removed = old_order.into_iter()
+        .filter_map(|key| old_by_key.remove(&key))
+        .map(Value::Object)
+        .collect();
+
+    Ok(DiffResult { added, removed, changed })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn rows_are_matched_on_the_key() {
+        let old = json!({ "value": [{ "Id": 1, "Name": "A" }, { "Id": 2, "Name": "B" }, { "Id": 3, "Name": "C" }] });
+        let new = json!([{ "Id": 4, "Name": "D" }, { "Id": 3, "Name": "C" }, { "Id": 1, "Name": "A2" }]);
+        let diff = diff_odata(old, new, "Id".to_string()).unwrap();
+
+        assert_eq!(diff.added, [json!({ "Id": 4, "Name": "D" })]);
+        assert_eq!(diff.removed, [json!({ "Id": 2, "Name": "B" })]);
+        assert_eq!(diff.changed.len(), 1);
+        let change = &diff.changed[0];
+        assert_eq!((&change.key, &change.fields), (&json!(1), &vec!["Name".to_string()]));
+        assert_eq!((&change.before["Name"], &change.after["Name"]), (&json!("A"), &json!("A2")));
+    }
+
+    #[test]
+    fn changed_fields_list_changes_and_removals_then_additions() {
+        let old = json!([{ "Id": 1, "Zone": 1, "Code": "x", "Shift": "AM" }]);
+        let new = json!([{ "Id": 1, "Zone": 2, "Code": "x", "Break": 30 }]);
+        let diff = diff_odata(old, new, "Id".to_string()).unwrap();
+        assert_eq!(diff.changed[0].fields, ["Shift", "Zone", "Break"]);
+    }
+
+    #[test]
+    fn annotations_are_not_changes() {
+        let old = json!([{ "Id": 1, "Name": "A", "@odata.etag": "W/1" }]);
+        let new = json!([{ "Id": 1, "Name": "A", "@odata.etag": "W/2", "Name@odata.type": "String" }]);
+        assert!(diff_odata(old, new, "Id".to_string()).unwrap().changed.is_empty());
+    }
+
+    #[test]
+    fn duplicate_and_missing_keys_are_rejected() {
+        let duplicated = json!([{ "Id": 1 }, { "Id": 1 }]);
+        let error = diff_odata(duplicated.clone(), json!([]), "Id".to_string()).unwrap_err();
+        assert!(error.contains("Rows 0 and 1 of the old result"), "{}", error);
+        assert!(diff_odata(json!([]), duplicated, "Id".to_string()).unwrap_err().contains("new result"));
+
+        assert!(diff_odata(json!([{ "Id": null }]), json!([]), "Id".to_string()).is_err());
+        assert!(diff_odata(json!([]), json!([{ "Name": "A" }]), "Id".to_string()).is_err());
+    }
+}
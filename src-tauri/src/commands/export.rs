@@ -0,0 +1,350 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::commands::circuit::CircuitBreakers;
+use crate::commands::diagnostics::{QueryMetrics, RequestLog};
+use crate::commands::http::{run_odata_query, AuthScheme, ODataQueryParams, RequestContext};
+use crate::commands::network::NetworkConfig;
+use crate::commands::odata::{next_link, unwrap_odata_value, EntityFieldCache};
+use crate::commands::profiles::ProfileLocks;
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+
+// Event sent after each page is written, and once more when the export finishes
+const EXPORT_PROGRESS_EVENT: &str = "export-progress";
+
+const DEFAULT_EXPORT_PAGE_SIZE: u32 = 500;
+
+#[derive(Debug, Clone, Serialize)]
+struct ExportProgress {
+    export_id: String,
+    rows_written: u64,
+    done: bool,
+}
+
+/// Look up a dotted path (`Location.Description`) in a row
+fn lookup<'a>(row: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(row, |value, segment| value.get(segment))
+}
+
+/// Render one cell: missing/null as empty, scalars as text, nested objects/arrays as JSON
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::Bool(b)) => b.to_string(),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// Default `$select` for a column list: each column's top-level property, once.
+/// Selecting explicitly also makes CoreApi return adhoc fields.
+fn default_select(columns: &[String]) -> String {
+    let mut fields: Vec<&str> = Vec::new();
+    for column in columns {
+        let top = column.split('.').next().unwrap_or(column);
+        if !fields.contains(&top) {
+            fields.push(top);
+        }
+    }
+    fields.join(",")
+}
+
+/// Page through `params` (its `top`, `skip`, `skiptoken` and `next_link` are managed here), handing each
+/// page's rows to `write_page` and reporting `(rows_written, done)` after each. A page with a nextLink
+/// is followed by requesting that link as sent; otherwise the next page skips the rows received so far.
+/// Returns the rows exported.
+pub(crate) async fn export_pages(
+    ctx: &RequestContext<'_>,
+    metrics: &QueryMetrics,
+    fields: &EntityFieldCache,
+    params: &ODataQueryParams,
+    page_size: u32,
+    mut write_page: impl FnMut(&[Value]) -> Result<(), String>,
+    mut progress: impl FnMut(u64, bool),
+) -> Result<u64, AppError> {
+    let mut rows_written: u64 = 0;
+    // Set once the server starts driving the paging with nextLinks
+    let mut link: Option<String> = None;
+    loop {
+        let page = ODataQueryParams {
+            top: Some(page_size as i32),
+            skip: match link {
+                Some(_) => None,
+                None => Some(i32::try_from(rows_written).map_err(|_| "Export is too large to page with $skip".to_string())?),
+            },
+            skiptoken: None,
+            next_link: link.clone(),
+            ..params.clone()
+        };
+        let json = run_odata_query(ctx, metrics, fields, page).await?;
+        // An unexpected shape fails the export rather than passing for an empty last page
+        let rows = unwrap_odata_value(&json)?;
+
+        write_page(rows)?;
+        rows_written += rows.len() as u64;
+
+        // A short page ends the export, unless the server capped the page size and sent a nextLink
+        link = next_link(&json).map(str::to_string);
+        let done = rows.is_empty() || ((rows.len() as u32) < page_size && link.is_none());
+        progress(rows_written, done);
+        if done {
+            return Ok(rows_written);
+        }
+    }
+}
+
+/// Page through an OData entity and write the requested `columns` to an RFC 4180 CSV file.
+/// Dotted column paths reach into expanded objects (pass `expand` for those). The file is written
+/// to `<output_path>.part` and renamed when complete. Returns the number of data rows written.
+#[tauri::command]
+pub async fn export_odata_to_csv(
+    app: AppHandle,
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    base_url: String,
+    entity: String,
+    columns: Vec<String>,
+    filter: Option<String>,
+    select: Option<String>,
+    expand: Option<String>,
+    orderby: Option<String>,
+    output_path: String,
+    page_size: Option<u32>,
+    export_id: Option<String>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    deadline_id: Option<String>,
+) -> Result<u64, AppError> {
+    if columns.is_empty() {
+        return Err("At least one column is required for a CSV export".to_string().into());
+    }
+
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let page_size = page_size.unwrap_or(DEFAULT_EXPORT_PAGE_SIZE).max(1);
+    let export_id = export_id.unwrap_or_else(|| output_path.clone());
+    let select = select.unwrap_or_else(|| default_select(&columns));
+
+    let partial_path = format!("{}.part", output_path);
+    let mut writer = csv::Writer::from_path(&partial_path)
+        .map_err(|e| format!("Failed to create '{}': {}", partial_path, e))?;
+
+    let params = ODataQueryParams {
+        base_url,
+        entity,
+        filter,
+        select: Some(select),
+        expand,
+        orderby,
+        user_id,
+        auth_token,
+        app_token,
+        username,
+        auth_scheme,
+        profile_name,
+        timeout_seconds,
+        ..Default::default()
+    };
+    let result = async {
+        writer.write_record(&columns)
+            .map_err(|e| format!("Failed to write CSV header: {}", e))?;
+
+        let write_page = |rows: &[Value]| {
+            for row in rows {
+                writer.write_record(columns.iter().map(|column| cell(lookup(row, column))))
+                    .map_err(|e| format!("Failed to write CSV row: {}", e))?;
+            }
+            writer.flush()
+                .map_err(|e| format!("Failed to write '{}': {}", partial_path, e))
+        };
+        let progress = |rows_written, done| {
+            let _ = app.emit(EXPORT_PROGRESS_EVENT, ExportProgress { export_id: export_id.clone(), rows_written, done });
+        };
+        export_pages(&ctx, &metrics, &fields, &params, page_size, write_page, progress).await
+    }.await;
+
+    drop(writer);
+    match result {
+        Ok(rows_written) => {
+            std::fs::rename(&partial_path, &output_path)
+                .map_err(|e| format!("Failed to move export into place: {}", e))?;
+            Ok(rows_written)
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial_path);
+            Err(e)
+        }
+    }
+}
+
+/// How rows are written into the zip entry
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZipEntryFormat {
+    // One JSON object per line
+    #[default]
+    Jsonl,
+    Csv,
+}
+
+/// Outcome of `export_odata_to_zip`; `compressed_bytes` is the size of the finished zip file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZipExportResult {
+    pub path: String,
+    pub uncompressed_bytes: u64,
+    pub compressed_bytes: u64,
+    pub rows: u64,
+}
+
+/// Records rendered as RFC 4180 CSV
+fn csv_bytes<R>(records: impl IntoIterator<Item = R>) -> Result<Vec<u8>, String>
+where
+    R: IntoIterator,
+    R::Item: AsRef<[u8]>,
+{
+    let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(Vec::new());
+    for record in records {
+        writer.write_record(record)
+            .map_err(|e| format!("Failed to write CSV row: {}", e))?;
+    }
+    writer.into_inner().map_err(|e| format!("Failed to write CSV row: {}", e))
+}
+
+/// Page through an OData query and write the rows straight into a single deflated zip entry,
+/// as JSON lines or (with `columns`) CSV, so no uncompressed copy ever touches the disk.
+/// `top`, `skip`, `skiptoken` and `next_link` in `params` are managed here: server nextLinks are
+/// followed as sent, as in `export_odata_to_csv`. The zip is written to
+/// `<output_zip_path>.part` and renamed when complete. Progress goes out as `export-progress` events.
+#[tauri::command]
+pub async fn export_odata_to_zip(
+    app: AppHandle,
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    mut params: ODataQueryParams,
+    entry_name: String,
+    output_zip_path: String,
+    format: Option<ZipEntryFormat>,
+    columns: Option<Vec<String>>,
+    page_size: Option<u32>,
+    export_id: Option<String>,
+) -> Result<ZipExportResult, AppError> {
+    let format = format.unwrap_or_default();
+    let columns = columns.unwrap_or_default();
+    if matches!(format, ZipEntryFormat::Csv) {
+        if columns.is_empty() {
+            return Err("At least one column is required for a CSV export".to_string().into());
+        }
+        if params.select.as_deref().is_none_or(str::is_empty) {
+            params.select = Some(default_select(&columns));
+        }
+    }
+    if entry_name.trim().is_empty() {
+        return Err("A zip entry name is required".to_string().into());
+    }
+
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(params.deadline_id.as_deref())?,
+    };
+    let page_size = page_size.unwrap_or(DEFAULT_EXPORT_PAGE_SIZE).max(1);
+    let export_id = export_id.unwrap_or_else(|| output_zip_path.clone());
+
+    let partial_path = format!("{}.part", output_zip_path);
+    let file = std::fs::File::create(&partial_path)
+        .map_err(|e| format!("Failed to create '{}': {}", partial_path, e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+
+    let mut uncompressed_bytes: u64 = 0;
+    let result = async {
+        zip.start_file(entry_name.as_str(), options)
+            .map_err(|e| format!("Failed to add '{}' to the zip: {}", entry_name, e))?;
+
+        let mut write = |bytes: Vec<u8>| {
+            uncompressed_bytes += bytes.len() as u64;
+            zip.write_all(&bytes)
+                .map_err(|e| format!("Failed to write '{}': {}", partial_path, e))
+        };
+        if matches!(format, ZipEntryFormat::Csv) {
+            write(csv_bytes([&columns])?)?;
+        }
+
+        let write_page = |rows: &[Value]| {
+            let bytes = match format {
+                ZipEntryFormat::Jsonl => {
+                    let mut bytes = Vec::new();
+                    for row in rows {
+                        serde_json::to_writer(&mut bytes, row)
+                            .map_err(|e| format!("Failed to serialize row: {}", e))?;
+                        bytes.push(b'\n');
+                    }
+                    bytes
+                }
+                ZipEntryFormat::Csv => csv_bytes(
+                    rows.iter().map(|row| columns.iter().map(|column| cell(lookup(row, column)))),
+                )?,
+            };
+            write(bytes)
+        };
+        let progress = |rows_written, done| {
+            let _ = app.emit(EXPORT_PROGRESS_EVENT, ExportProgress { export_id: export_id.clone(), rows_written, done });
+        };
+        export_pages(&ctx, &metrics, &fields, &params, page_size, write_page, progress).await
+    }.await;
+
+    // Finishing writes the central directory; on failure just close the file so it can be removed
+    let finished = match result {
+        Ok(rows) => zip.finish()
+            .map(|_| rows)
+            .map_err(|e| format!("Failed to finish '{}': {}", partial_path, e).into()),
+        Err(e) => {
+            drop(zip);
+            Err(e)
+        }
+    };
+    match finished {
+        Ok(rows) => {
+            std::fs::rename(&partial_path, &output_zip_path)
+                .map_err(|e| format!("Failed to move export into place: {}", e))?;
+            let compressed_bytes = std::fs::metadata(&output_zip_path)
+                .map(|metadata| metadata.len())
+                .map_err(|e| format!("Failed to read '{}': {}", output_zip_path, e))?;
+            Ok(ZipExportResult { path: output_zip_path, uncompressed_bytes, compressed_bytes, rows })
+        }
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial_path);
+            Err(e)
+        }
+    }
+}
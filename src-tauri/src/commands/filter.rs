@@ -0,0 +1,651 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::odata::is_guid;
+
+// Logical, comparison and arithmetic operators in OData v4 `$filter`
+const COMPARISON_OPS: &[&str] = &["eq", "ne", "gt", "ge", "lt", "le", "has"];
+const ARITHMETIC_OPS: &[&str] = &["add", "sub", "mul", "div", "divby", "mod"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Literal,
+    OpenParen,
+    CloseParen,
+    Comma,
+    Colon,
+}
+
+/// A token plus its 1-based column in the filter string, for error messages
+#[derive(Debug, Clone)]
+struct Spanned {
+    token: Token,
+    column: usize,
+}
+
+fn error_at(column: usize, message: impl std::fmt::Display) -> String {
+    format!("Invalid filter at position {}: {}", column, message)
+}
+
+/// Suggest the OData keyword for common C/SQL-style operators
+fn symbol_hint(symbol: &str) -> Option<&'static str> {
+    match symbol {
+        "=" | "==" => Some("eq"),
+        "!=" | "<>" => Some("ne"),
+        ">" => Some("gt"),
+        ">=" => Some("ge"),
+        "<" => Some("lt"),
+        "<=" => Some("le"),
+        "&&" | "&" => Some("and"),
+        "||" | "|" => Some("or"),
+        "!" => Some("not"),
+        _ => None,
+    }
+}
+
+fn tokenize(filter: &str) -> Result<Vec<Spanned>, String> {
+    let chars: Vec<char> = filter.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let token = match c {
+            '(' => { i += 1; Token::OpenParen }
+            ')' => { i += 1; Token::CloseParen }
+            ',' => { i += 1; Token::Comma }
+            ':' => { i += 1; Token::Colon }
+            '\'' => {
+                // Quoted string; a doubled '' is an escaped quote
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(error_at(column, "unterminated string literal (missing closing ')")),
+                        Some('\'') if chars.get(i + 1) == Some(&'\'') => i += 2,
+                        Some('\'') => { i += 1; break; }
+                        Some(_) => i += 1,
+                    }
+                }
+                Token::Literal
+            }
+            '"' => return Err(error_at(column, "string literals use single quotes, not double quotes")),
+            c if c.is_ascii_digit() || (c == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) => {
+                // Numbers, and unquoted date/time/GUID literals such as 2024-01-31T09:00:00Z
+                i += 1;
+                while chars.get(i).is_some_and(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '+')) {
+                    i += 1;
+                }
+                Token::Literal
+            }
+            c if c.is_alphabetic() || c == '_' || c == '$' || c == '@' => {
+                let start = i;
+                while chars.get(i).is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '/' | '.' | '$' | '@')) {
+                    i += 1;
+                }
+                Token::Word(chars[start..i].iter().collect())
+            }
+            _ => {
+                let start = i;
+                while chars.get(i).is_some_and(|c| matches!(c, '=' | '!' | '<' | '>' | '&' | '|')) {
+                    i += 1;
+                }
+                let symbol: String = if i > start { chars[start..i].iter().collect() } else { c.to_string() };
+                return Err(match symbol_hint(&symbol) {
+                    Some(keyword) => error_at(column, format!("'{}' is not an OData operator, use '{}'", symbol, keyword)),
+                    None => error_at(column, format!("unexpected character '{}'", symbol)),
+                });
+            }
+        };
+
+        tokens.push(Spanned { token, column });
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent checker over the token stream; it only validates structure, it builds nothing
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    end_column: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens.get(self.pos).map_or(self.end_column, |t| t.column)
+    }
+
+    fn peek_word(&self) -> Option<String> {
+        match self.peek() {
+            Some(Token::Word(w)) => Some(w.to_ascii_lowercase()),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<(), String> {
+        if self.peek() == Some(&expected) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(error_at(self.column(), format!("expected {}", what)))
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<(), String> {
+        self.parse_and()?;
+        while self.peek_word().as_deref() == Some("or") {
+            self.pos += 1;
+            self.parse_and()?;
+        }
+        Ok(())
+    }
+
+    fn parse_and(&mut self) -> Result<(), String> {
+        self.parse_unary()?;
+        while self.peek_word().as_deref() == Some("and") {
+            self.pos += 1;
+            self.parse_unary()?;
+        }
+        Ok(())
+    }
+
+    fn parse_unary(&mut self) -> Result<(), String> {
+        if self.peek_word().as_deref() == Some("not") {
+            self.pos += 1;
+            return self.parse_unary();
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<(), String> {
+        self.parse_additive()?;
+        match self.peek_word() {
+            Some(op) if COMPARISON_OPS.contains(&op.as_str()) => {
+                self.pos += 1;
+                self.parse_additive()
+            }
+            Some(op) if op == "in" => {
+                self.pos += 1;
+                self.expect(Token::OpenParen, "'(' to start the 'in' list")?;
+                self.parse_list()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<(), String> {
+        self.parse_primary()?;
+        while self.peek_word().is_some_and(|w| ARITHMETIC_OPS.contains(&w.as_str())) {
+            self.pos += 1;
+            self.parse_primary()?;
+        }
+        Ok(())
+    }
+
+    /// Comma-separated expressions up to and including the closing ')'
+    fn parse_list(&mut self) -> Result<(), String> {
+        if self.peek() == Some(&Token::CloseParen) {
+            self.pos += 1;
+            return Ok(());
+        }
+        loop {
+            // Lambda variable, e.g. `Items/any(i: i/Amount gt 0)`
+            if matches!(self.peek(), Some(Token::Word(_)))
+                && self.tokens.get(self.pos + 1).map(|t| &t.token) == Some(&Token::Colon)
+            {
+                self.pos += 2;
+            }
+            self.parse_or()?;
+            match self.peek() {
+                Some(Token::Comma) => self.pos += 1,
+                Some(Token::CloseParen) => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return Err(error_at(self.column(), "expected ',' or ')'")),
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<(), String> {
+        let column = self.column();
+        match self.peek().cloned() {
+            Some(Token::OpenParen) => {
+                self.pos += 1;
+                self.parse_or()?;
+                self.expect(Token::CloseParen, "')' to close the group")
+            }
+            Some(Token::Literal) => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(Token::Word(word)) => {
+                let lower = word.to_ascii_lowercase();
+                if COMPARISON_OPS.contains(&lower.as_str())
+                    || ARITHMETIC_OPS.contains(&lower.as_str())
+                    || lower == "and"
+                    || lower == "or"
+                {
+                    return Err(error_at(column, format!("operator '{}' is missing its left-hand operand", word)));
+                }
+                self.pos += 1;
+                // Function call such as contains(Name,'x') or lambda such as Items/any(...)
+                if self.peek() == Some(&Token::OpenParen) {
+                    self.pos += 1;
+                    self.parse_list()?;
+                }
+                Ok(())
+            }
+            Some(Token::CloseParen) => Err(error_at(column, "unexpected ')'")),
+            Some(Token::Comma) => Err(error_at(column, "unexpected ','")),
+            Some(Token::Colon) => Err(error_at(column, "unexpected ':'")),
+            None => Err(error_at(column, "expression ends early, an operand is missing")),
+        }
+    }
+}
+
+/// Best-effort local check of a `$filter` expression: balanced parentheses, quoted strings,
+/// OData keyword operators (eq/ne/gt/and/or/...) and operands in the right places.
+/// Not a full grammar - it catches the common mistakes before the server returns a 400.
+#[tauri::command]
+pub fn validate_odata_filter(filter: String) -> Result<(), String> {
+    let tokens = tokenize(&filter)?;
+    if tokens.is_empty() {
+        return Err("Filter is empty".to_string());
+    }
+
+    let mut parser = Parser { tokens, pos: 0, end_column: filter.chars().count() + 1 };
+    parser.parse_or()?;
+
+    match parser.peek() {
+        None => Ok(()),
+        Some(Token::CloseParen) => Err(error_at(parser.column(), "unmatched ')'")),
+        Some(Token::Word(word)) => Err(error_at(
+            parser.column(),
+            format!("unexpected '{}' - expected an operator such as eq, and, or", word),
+        )),
+        Some(_) => Err(error_at(parser.column(), "unexpected token, expected an operator such as eq, and, or")),
+    }
+}
+
+/// A typed value on the right-hand side of a filter comparison
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum FilterValue {
+    String(String),
+    Number(serde_json::Number),
+    Bool(bool),
+    Null,
+    /// RFC 3339 / ISO-8601 date-time; one without an offset is taken as UTC
+    DateTime(String),
+    /// Calendar date, `YYYY-MM-DD`
+    Date(String),
+    Guid(String),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StringFunction {
+    Contains,
+    StartsWith,
+    EndsWith,
+}
+
+/// A `$filter` expression tree, composed by `build_filter` into a correctly escaped filter string
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum FilterExpr {
+    Compare { field: String, operator: ComparisonOp, value: FilterValue },
+    In { field: String, values: Vec<FilterValue> },
+    Function { function: StringFunction, field: String, value: String },
+    And { exprs: Vec<FilterExpr> },
+    Or { exprs: Vec<FilterExpr> },
+    Not { expr: Box<FilterExpr> },
+}
+
+/// A property path such as `Name` or `Location/Description`
+fn filter_field(field: &str) -> Result<&str, String> {
+    let valid = !field.is_empty()
+        && field.split('/').all(|segment| {
+            segment.chars().next().is_some_and(|c| c.is_alphabetic() || c == '_')
+                && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+        });
+    if valid {
+        Ok(field)
+    } else {
+        Err(format!("Invalid filter field '{}'", field))
+    }
+}
+
+/// Single-quoted string literal with embedded quotes doubled, not URL-encoded - for a filter
+/// that is encoded as a whole before it goes into a URL
+pub(crate) fn quoted_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Single-quoted string literal with embedded quotes doubled, URL-encoded like entity keys
+/// so characters such as `&`, `#` and `+` can't break the query string
+pub(crate) fn string_literal(value: &str) -> String {
+    format!("'{}'", urlencoding::encode(&value.replace('\'', "''")))
+}
+
+fn filter_literal(value: &FilterValue) -> Result<String, String> {
+    match value {
+        FilterValue::String(s) => Ok(string_literal(s)),
+        FilterValue::Number(n) => Ok(n.to_string()),
+        FilterValue::Bool(b) => Ok(b.to_string()),
+        FilterValue::Null => Ok("null".to_string()),
+        FilterValue::DateTime(s) => {
+            let utc = match chrono::DateTime::parse_from_rfc3339(s) {
+                Ok(dt) => dt.with_timezone(&chrono::Utc),
+                Err(_) => chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+                    .map_err(|e| format!("Invalid date-time '{}': {}", s, e))?
+                    .and_utc(),
+            };
+            // `+` in an offset would decode as a space in the query string, so always send UTC with Z
+            Ok(utc.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true))
+        }
+        FilterValue::Date(s) => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .map_err(|e| format!("Invalid date '{}': {}", s, e)),
+        FilterValue::Guid(s) if is_guid(s) => Ok(s.to_ascii_lowercase()),
+        FilterValue::Guid(s) => Err(format!("Invalid GUID '{}'", s)),
+    }
+}
+
+fn compose_filter(expr: &FilterExpr) -> Result<String, String> {
+    match expr {
+        FilterExpr::Compare { field, operator, value } => {
+            let operator = match operator {
+                ComparisonOp::Eq => "eq",
+                ComparisonOp::Ne => "ne",
+                ComparisonOp::Gt => "gt",
+                ComparisonOp::Ge => "ge",
+                ComparisonOp::Lt => "lt",
+                ComparisonOp::Le => "le",
+            };
+            Ok(format!("{} {} {}", filter_field(field)?, operator, filter_literal(value)?))
+        }
+        FilterExpr::In { field, values } => {
+            if values.is_empty() {
+                return Err(format!("'in' filter on '{}' needs at least one value", field));
+            }
+            let values = values.iter().map(filter_literal).collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("{} in ({})", filter_field(field)?, values.join(",")))
+        }
+        FilterExpr::Function { function, field, value } => {
+            let function = match function {
+                StringFunction::Contains => "contains",
+                StringFunction::StartsWith => "startswith",
+                StringFunction::EndsWith => "endswith",
+            };
+            Ok(format!("{}({},{})", function, filter_field(field)?, string_literal(value)))
+        }
+        FilterExpr::And { exprs } | FilterExpr::Or { exprs } => {
+            let joiner = if matches!(expr, FilterExpr::And { .. }) { " and " } else { " or " };
+            match exprs.as_slice() {
+                [] => Err("'and'/'or' filter needs at least one expression".to_string()),
+                [only] => compose_filter(only),
+                // Parenthesize each operand so mixed and/or nesting keeps its meaning
+                _ => Ok(exprs.iter()
+                    .map(|e| compose_filter(e).map(|s| format!("({})", s)))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .join(joiner)),
+            }
+        }
+        FilterExpr::Not { expr } => Ok(format!("not ({})", compose_filter(expr)?)),
+    }
+}
+
+/// Compose a `$filter` string from a typed expression, ready to pass as `filter` to execute_odata_query.
+/// Strings are quoted and escaped, dates and date-times are normalized to ISO-8601.
+#[tauri::command]
+pub fn build_filter(expr: FilterExpr) -> Result<String, String> {
+    compose_filter(&expr)
+}
+
+/// Parse one end of a date range: RFC 3339, a date-time without offset (UTC), epoch seconds,
+/// or a bare `YYYY-MM-DD` date, which covers the whole day
+fn range_bound(value: &str, end_of_day: bool) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<i64>() {
+        return chrono::DateTime::from_timestamp(seconds, 0)
+            .ok_or_else(|| format!("Epoch seconds {} are out of range", seconds));
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let time = if end_of_day {
+            chrono::NaiveTime::from_hms_milli_opt(23, 59, 59, 999)
+        } else {
+            chrono::NaiveTime::from_hms_opt(0, 0, 0)
+        };
+        return Ok(date.and_time(time.unwrap_or_default()).and_utc());
+    }
+    match chrono::DateTime::parse_from_rfc3339(value) {
+        Ok(dt) => Ok(dt.with_timezone(&chrono::Utc)),
+        Err(_) => chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+            .map(|dt| dt.and_utc())
+            .map_err(|_| format!("Invalid date '{}': expected ISO-8601 or epoch seconds", value)),
+    }
+}
+
+/// `$filter` for `field` between `from` and `to` inclusive, e.g.
+/// `StartDate ge 2024-01-01T00:00:00Z and StartDate le 2024-01-31T23:59:59.999Z`.
+/// Bounds are ISO-8601 strings or epoch seconds, sent as UTC; a bare date covers that whole day.
+/// Either bound may be omitted, and with neither the result is an empty string.
+#[tauri::command]
+pub fn date_range_filter(field: String, from: Option<String>, to: Option<String>) -> Result<String, String> {
+    let field = filter_field(&field)?;
+    let from = from.as_deref().map(|v| range_bound(v, false)).transpose()?;
+    let to = to.as_deref().map(|v| range_bound(v, true)).transpose()?;
+
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return Err(format!("Date range starts after it ends ({} > {})", from, to));
+        }
+    }
+
+    let literal = |dt: chrono::DateTime<chrono::Utc>| dt.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true);
+    let clauses: Vec<String> = [("ge", from), ("le", to)]
+        .into_iter()
+        .filter_map(|(operator, bound)| bound.map(|dt| format!("{} {} {}", field, operator, literal(dt))))
+        .collect();
+    Ok(clauses.join(" and "))
+}
+
+/// Sort direction of one `$orderby` item
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDir {
+    Asc,
+    Desc,
+}
+
+/// Compose `$orderby` from `(field, direction)` pairs, e.g. `Amount desc,Name asc`
+#[tauri::command]
+pub fn build_orderby(fields: Vec<(String, SortDir)>) -> Result<String, String> {
+    if fields.is_empty() {
+        return Err("orderby needs at least one field".to_string());
+    }
+    let items = fields
+        .iter()
+        .map(|(field, dir)| {
+            let dir = match dir {
+                SortDir::Asc => "asc",
+                SortDir::Desc => "desc",
+            };
+            filter_field(field).map(|field| format!("{} {}", field, dir))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(items.join(","))
+}
+
+/// Split on `separator` outside parentheses and quoted strings, so `substring(Name,1,2) desc` stays whole
+pub(crate) fn split_top_level(value: &str, separator: impl Fn(char) -> bool) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut quoted, mut start) = (0usize, false, 0);
+    for (i, c) in value.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth = depth.saturating_sub(1),
+            c if !quoted && depth == 0 && separator(c) => {
+                parts.push(&value[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+/// Best-effort check of a raw `$orderby`: comma-separated `expression [asc|desc]` items.
+/// Only the direction keywords are checked strictly; expressions are left to the server.
+pub(crate) fn validate_orderby(orderby: &str) -> Result<(), String> {
+    for (i, item) in split_top_level(orderby, |c| c == ',').into_iter().enumerate() {
+        let words: Vec<&str> = split_top_level(item.trim(), char::is_whitespace)
+            .into_iter()
+            .filter(|w| !w.is_empty())
+            .collect();
+        match words.as_slice() {
+            [] => return Err(format!("orderby item {} is empty - check for a stray comma", i + 1)),
+            [only] if only.eq_ignore_ascii_case("asc") || only.eq_ignore_ascii_case("desc") => {
+                return Err(format!("orderby item {} is only a direction ('{}') with no field", i + 1, only));
+            }
+            [_] | [_, "asc"] | [_, "desc"] => {}
+            [field, dir] => {
+                let hint = match dir.to_ascii_lowercase().as_str() {
+                    "asc" | "ascending" | "up" => "use 'asc'",
+                    "desc" | "descending" | "dsc" | "down" => "use 'desc'",
+                    _ => "expected 'asc' or 'desc'",
+                };
+                return Err(format!("Unknown sort direction '{}' after '{}' - {}", dir, field, hint));
+            }
+            [field, ..] => {
+                return Err(format!(
+                    "orderby item '{}' has extra words after '{}' - separate fields with commas",
+                    item.trim(), field
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check a raw `$orderby` string before it's sent, e.g. to catch `Amount descending`
+#[tauri::command]
+pub fn validate_odata_orderby(orderby: String) -> Result<(), String> {
+    validate_orderby(&orderby)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(filter: &str) -> Result<(), String> {
+        validate_odata_filter(filter.to_string())
+    }
+
+    /// The error for an invalid filter, as `<column>: <message>`
+    fn error(filter: &str) -> String {
+        let error = validate(filter).unwrap_err();
+        error.strip_prefix("Invalid filter at position ").unwrap_or(&error).to_string()
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_column() {
+        assert_eq!(error("Name eq 'abc"), "9: unterminated string literal (missing closing ')");
+        assert_eq!(error("eq 1"), "1: operator 'eq' is missing its left-hand operand");
+        assert_eq!(error("(Id eq 1"), "9: expected ')' to close the group");
+        assert_eq!(error("Id eq 1)"), "8: unmatched ')'");
+        assert_eq!(error("Id eq 1 Name"), "9: unexpected 'Name' - expected an operator such as eq, and, or");
+    }
+
+    #[test]
+    fn symbol_operators_suggest_the_keyword() {
+        assert_eq!(error("Name == 'x'"), "6: '==' is not an OData operator, use 'eq'");
+        assert!(error("Id <> 1").contains("use 'ne'"));
+        assert!(error("A eq 1 && B eq 2").contains("use 'and'"));
+        assert!(error("Name eq \"x\"").contains("single quotes"));
+    }
+
+    #[test]
+    fn doubled_quotes_stay_inside_the_literal() {
+        assert!(validate("Name eq 'O''Brien'").is_ok());
+        assert!(validate("Name eq 'It''s' and Id eq 1").is_ok());
+        assert!(validate("Name eq 'O''Brien").is_err());
+    }
+
+    #[test]
+    fn lambdas_functions_and_in_lists_are_accepted() {
+        assert!(validate("Items/any(i: i/Amount gt 0)").is_ok());
+        assert!(validate("Items/all(i: i/Approved eq true) and not contains(Name,'x')").is_ok());
+        assert!(validate("Id in (1, 2, 3)").is_ok());
+        assert!(validate("Status in ()").is_ok());
+        assert_eq!(error("Id in 1"), "7: expected '(' to start the 'in' list");
+        assert_eq!(error("Id in (1 2)"), "10: expected ',' or ')'");
+    }
+
+    #[test]
+    fn built_filters_escape_strings_and_normalize_dates() {
+        let compare = |value| FilterExpr::Compare { field: "Start".to_string(), operator: ComparisonOp::Ge, value };
+        let naive = compare(FilterValue::DateTime("2024-01-31T09:00:00".to_string()));
+        assert_eq!(build_filter(naive).unwrap(), "Start ge 2024-01-31T09:00:00Z");
+        let offset = compare(FilterValue::DateTime("2024-01-31T09:00:00.5+10:00".to_string()));
+        assert_eq!(build_filter(offset).unwrap(), "Start ge 2024-01-30T23:00:00.500Z");
+        assert!(build_filter(compare(FilterValue::DateTime("31/01/2024".to_string()))).is_err());
+
+        let name = FilterExpr::Function {
+            function: StringFunction::StartsWith,
+            field: "Name".to_string(),
+            value: "O'Brien & Co".to_string(),
+        };
+        let ids = FilterExpr::In {
+            field: "Id".to_string(),
+            values: vec![FilterValue::Number(1.into()), FilterValue::Number(2.into())],
+        };
+        assert_eq!(
+            build_filter(FilterExpr::Or { exprs: vec![name, ids] }).unwrap(),
+            "(startswith(Name,'O%27%27Brien%20%26%20Co')) or (Id in (1,2))"
+        );
+        assert!(build_filter(FilterExpr::In { field: "Id".to_string(), values: Vec::new() }).is_err());
+    }
+
+    #[test]
+    fn date_ranges_cover_whole_days_and_reject_reversed_bounds() {
+        let range = |from: Option<&str>, to: Option<&str>| {
+            date_range_filter("StartDate".to_string(), from.map(str::to_string), to.map(str::to_string))
+        };
+        assert_eq!(
+            range(Some("2024-01-01"), Some("2024-01-31")).unwrap(),
+            "StartDate ge 2024-01-01T00:00:00Z and StartDate le 2024-01-31T23:59:59.999Z"
+        );
+        assert_eq!(range(None, Some("1706659200")).unwrap(), "StartDate le 2024-01-31T00:00:00Z");
+        assert_eq!(range(None, None).unwrap(), "");
+        assert!(range(Some("2024-02-01"), Some("2024-01-31")).unwrap_err().contains("starts after it ends"));
+        // The same day is a valid range, from its start to its end
+        assert!(range(Some("2024-01-31"), Some("2024-01-31")).is_ok());
+    }
+}
@@ -0,0 +1,84 @@
+use serde_json::{Map, Value};
+
+// Joins nested keys unless the caller picks another separator
+const DEFAULT_SEPARATOR: &str = ".";
+
+fn join_key(prefix: &str, key: &str, separator: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}{}{}", prefix, separator, key)
+    }
+}
+
+/// Flatten `value` into every row of `rows` under `prefix`. Nested objects become prefixed keys;
+/// arrays become JSON strings, or with `explode` one copy of the rows per element.
+fn flatten_into(
+    value: &Value,
+    prefix: &str,
+    separator: &str,
+    explode: bool,
+    mut rows: Vec<Map<String, Value>>,
+) -> Vec<Map<String, Value>> {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, child) in object {
+                // Annotations such as @odata.etag or Name@odata.type aren't data
+                if key.contains('@') {
+                    continue;
+                }
+                rows = flatten_into(child, &join_key(prefix, key, separator), separator, explode, rows);
+            }
+            rows
+        }
+        Value::Array(items) if explode && !items.is_empty() => items
+            .iter()
+            .flat_map(|item| flatten_into(item, prefix, separator, explode, rows.clone()))
+            .collect(),
+        // An empty top-level row has no columns to add
+        _ if prefix.is_empty() => rows,
+        other => {
+            let cell = match other {
+                Value::Array(items) if !items.is_empty() => Value::String(other.to_string()),
+                Value::Array(_) | Value::Object(_) => Value::Null,
+                scalar => scalar.clone(),
+            };
+            for row in &mut rows {
+                row.insert(prefix.to_string(), cell.clone());
+            }
+            rows
+        }
+    }
+}
+
+/// Flatten an OData result into grid rows: nested objects become `Customer.Name` style keys.
+/// Arrays are kept as JSON strings, or with `explode_arrays` each element gets its own row
+/// (the parent's fields repeated). Accepts `{ value: [...] }`, a bare array, or a single object.
+#[tauri::command]
+pub fn flatten_odata(
+    value: Value,
+    separator: Option<String>,
+    explode_arrays: Option<bool>,
+) -> Result<Vec<Map<String, Value>>, String> {
+    let separator = separator.unwrap_or_else(|| DEFAULT_SEPARATOR.to_string());
+    let explode = explode_arrays.unwrap_or(false);
+
+    let rows = match value {
+        Value::Array(rows) => rows,
+        Value::Object(mut object) => match object.remove("value") {
+            Some(Value::Array(rows)) => rows,
+            Some(other) => return Err(format!("Expected `value` to be an array, got {}", other)),
+            None => vec![Value::Object(object)],
+        },
+        other => return Err(format!("Expected an OData result object or array, got {}", other)),
+    };
+
+    Ok(rows
+        .iter()
+        .flat_map(|row| match row {
+            Value::Object(_) => flatten_into(row, "", &separator, explode, vec![Map::new()]),
+            // A bare scalar row still gets a column so nothing is silently dropped
+            scalar => flatten_into(scalar, "value", &separator, explode, vec![Map::new()]),
+        })
+        .collect())
+}
@@ -1,28 +1,229 @@
-use reqwest::{Client, ClientBuilder};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use flate2::{write::GzEncoder, Compression};
+use reqwest::{Client, ClientBuilder, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use tauri::State;
 
-use crate::types::HttpResponse;
+use crate::commands::circuit::{circuit_key, CircuitBreakers};
+use crate::commands::diagnostics::{redact_url, QueryMetrics, RequestLog, RequestLogEntry};
+use crate::commands::filter::{split_top_level, validate_orderby};
+use crate::commands::idempotency::{idempotency_scope, IdempotencyCache};
+use crate::commands::network::{parse_tls_version, Deadline, NetworkConfig};
+use crate::commands::odata::{
+    entity_fields, expand_to_odata, extract_odata_count, odata_service_root, resolve_next_link, result_rows,
+    unwrap_odata_value, EntityFieldCache, ExpandSpec,
+};
+use crate::commands::profiles::ProfileLocks;
+use crate::commands::session;
+use crate::commands::xml::{looks_like_xml, xml_to_json};
+use crate::error::{AppError, ErrorKind, MissingRowFields};
+use crate::keystore::KeyStore;
+use crate::types::{Credentials, HttpResponse, JsonResponse};
 
-fn build_client(timeout_seconds: Option<u64>) -> Result<Client, String> {
-    let timeout = Duration::from_secs(timeout_seconds.unwrap_or(30));
+/// How `auth_token` is presented in credential (non app token) mode
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub enum AuthScheme {
+    /// Nimbus core API: `Authorization: Bearer` plus `AuthenticationToken` (and `UserID`)
+    #[default]
+    NimbusDual,
+    /// Standard OAuth-style `Authorization: Bearer` only
+    BearerOnly,
+    /// The token sent as-is in a named header, for API-key-protected add-ons
+    ApiKey { header_name: String },
+    /// `Authorization: Basic` with `username` and the token as the password
+    Basic,
+}
 
-    ClientBuilder::new()
-        .timeout(timeout)
-        .cookie_store(true)
-        .user_agent("MonashNimbusReports/1.0 (Tauri; Rust)")
+/// Auth header inputs, either passed by the caller or loaded from a stored profile
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AuthFields {
+    pub user_id: Option<i32>,
+    pub auth_token: Option<String>,
+    pub app_token: Option<String>,
+    pub username: Option<String>,
+    pub scheme: AuthScheme,
+    // A stored profile's default headers; empty for caller-supplied auth
+    pub default_headers: HashMap<String, String>,
+}
+
+impl From<&Credentials> for AuthFields {
+    fn from(credentials: &Credentials) -> Self {
+        Self {
+            user_id: credentials.user_id,
+            auth_token: credentials.auth_token.clone(),
+            app_token: credentials.app_token.clone(),
+            username: credentials.username.clone(),
+            scheme: AuthScheme::default(),
+            default_headers: credentials.default_headers.clone(),
+        }
+    }
+}
+
+// How much of a non-JSON body execute_rest_get_json quotes back in its error
+const RAW_BODY_PREVIEW_CHARS: usize = 4096;
+
+// Default cap on response bodies so an accidental whole-table select can't exhaust memory
+const DEFAULT_MAX_RESPONSE_BYTES: u64 = 100 * 1024 * 1024;
+
+// Cap on JSON request bodies sent by execute_rest_post
+const MAX_REQUEST_BODY_BYTES: u64 = 10 * 1024 * 1024;
+
+// Header carrying execute_rest_post's idempotency key
+const IDEMPOTENCY_HEADER: &str = "Idempotency-Key";
+
+// With compress_body, POST bodies smaller than this go uncompressed - gzip barely pays off below it
+const GZIP_BODY_THRESHOLD_BYTES: usize = 64 * 1024;
+
+// Total time allowed for a request when the caller doesn't say
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+// Time allowed to establish a connection (DNS + TCP + TLS), separate from the total request timeout
+const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 10;
+
+// Oldest TLS version negotiated unless NetworkSettings says otherwise (institutional baseline)
+const DEFAULT_MIN_TLS_VERSION: reqwest::tls::Version = reqwest::tls::Version::TLS_1_2;
+
+// Same-origin redirects followed by default (and the cap for RedirectPolicy::Follow)
+const DEFAULT_MAX_REDIRECTS: usize = 5;
+const FOLLOW_MAX_REDIRECTS: usize = 10;
+
+/// How the client treats redirects.
+/// Cross-origin redirects are never followed: Nimbus auth travels in custom headers
+/// (AuthenticationToken, AppToken, ...) that reqwest would otherwise forward to the new host.
+/// The 3xx is returned instead, with `final_url` and the Location header showing where it pointed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RedirectPolicy {
+    None,
+    Limited(usize),
+    Follow,
+}
+
+impl RedirectPolicy {
+    fn to_reqwest(self) -> reqwest::redirect::Policy {
+        let max = match self {
+            RedirectPolicy::None => return reqwest::redirect::Policy::none(),
+            RedirectPolicy::Limited(n) => n,
+            RedirectPolicy::Follow => FOLLOW_MAX_REDIRECTS,
+        };
+        reqwest::redirect::Policy::custom(move |attempt| {
+            let same_origin = attempt.previous()
+                .first()
+                .is_some_and(|first| first.origin() == attempt.url().origin());
+            if !same_origin {
+                attempt.stop()
+            } else if attempt.previous().len() > max {
+                attempt.error(format!("too many redirects (limit {})", max))
+            } else {
+                attempt.follow()
+            }
+        })
+    }
+}
+
+/// Client-level settings that distinguish one cached client from another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct ClientKey {
+    pub timeout_seconds: u64,
+    pub connect_timeout_seconds: u64,
+    pub redirect_policy: RedirectPolicy,
+}
+
+/// Get the shared client for these settings. `timeout_seconds` bounds the whole request (default 30s,
+/// or the configured default); `connect_timeout_seconds` only bounds establishing the connection
+/// (default 10s, or the configured default), so an unreachable host fails fast while a slow query
+/// can still run for the full total timeout.
+/// Clients are cached per settings combination so connections (and cookies) are reused across
+/// commands; `reset_http_client` drops them after network settings change.
+pub(crate) fn build_client(
+    network: &NetworkConfig,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    redirect_policy: Option<RedirectPolicy>,
+) -> Result<Client, String> {
+    let settings = network.settings();
+    let key = ClientKey {
+        timeout_seconds: timeout_seconds.or(settings.timeout_seconds).unwrap_or(DEFAULT_TIMEOUT_SECS),
+        connect_timeout_seconds: connect_timeout_seconds
+            .or(settings.connect_timeout_seconds)
+            .unwrap_or(DEFAULT_CONNECT_TIMEOUT_SECS),
+        redirect_policy: redirect_policy.unwrap_or(RedirectPolicy::Limited(DEFAULT_MAX_REDIRECTS)),
+    };
+    network.cached_client(key, || new_client(network, key))
+}
+
+/// Build an uncached client - for probes that must not reuse a pooled connection
+pub(crate) fn new_client(network: &NetworkConfig, key: ClientKey) -> Result<Client, String> {
+    client_builder(network, key)?
         .build()
         .map_err(|e| format!("Failed to build HTTP client: {}", e))
 }
 
-fn build_headers(
+/// Builder with every current network setting applied, for callers that need to add to it
+pub(crate) fn client_builder(network: &NetworkConfig, key: ClientKey) -> Result<ClientBuilder, String> {
+    let settings = network.settings();
+    let min_tls_version = match settings.min_tls_version {
+        Some(ref version) => parse_tls_version(version)?,
+        None => DEFAULT_MIN_TLS_VERSION,
+    };
+    let mut builder = ClientBuilder::new()
+        .timeout(Duration::from_secs(key.timeout_seconds))
+        .connect_timeout(Duration::from_secs(key.connect_timeout_seconds))
+        .cookie_store(true)
+        .redirect(key.redirect_policy.to_reqwest())
+        .min_tls_version(min_tls_version)
+        .user_agent(network.user_agent());
+
+    // A default header, so a request that sets its own Accept-Language keeps it
+    if let Some(accept_language) = network.accept_language() {
+        let value = reqwest::header::HeaderValue::from_str(&accept_language)
+            .map_err(|e| format!("Invalid Accept-Language '{}': {}", accept_language, e))?;
+        builder = builder.default_headers(
+            std::iter::once((reqwest::header::ACCEPT_LANGUAGE, value)).collect(),
+        );
+    }
+
+    // Connection tuning stays at reqwest's defaults unless set in NetworkSettings
+    if settings.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    if let Some(max_idle) = settings.pool_max_idle_per_host {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+    if let Some(idle_secs) = settings.pool_idle_timeout_secs {
+        builder = builder.pool_idle_timeout(Duration::from_secs(idle_secs));
+    }
+
+    Ok(builder)
+}
+
+/// Build the headers shared by every request, in three layers:
+/// 1. managed defaults - `Accept: application/json` and the auth headers for the active mode
+///    (AppToken + Username, or UserID + Authorization + AuthenticationToken);
+/// 2. the stored profile's `default_headers`, when the auth came from a profile;
+/// 3. caller-supplied `custom_headers`, applied last.
+///
+/// Caller-supplied headers always win: any of them replaces the managed header of the same name
+/// (names compare case-insensitively), so a caller can deliberately override Accept, Authorization or UserID.
+/// Content-Type is deliberately left out: bodyless GETs shouldn't carry one, and the
+/// body-sending paths only fill it in when the caller didn't supply one.
+pub(crate) fn build_headers(
     custom_headers: Option<HashMap<String, String>>,
-    user_id: Option<i32>,
-    auth_token: Option<String>,
-    app_token: Option<String>,
-    username: Option<String>,
+    auth: &AuthFields,
 ) -> Result<reqwest::header::HeaderMap, String> {
+    let mut headers = managed_headers(auth)?;
+    apply_custom_headers(&mut headers, auth.default_headers.clone())?;
+    if let Some(custom) = custom_headers {
+        apply_custom_headers(&mut headers, custom)?;
+    }
+    Ok(headers)
+}
+
+/// Layer 1 of `build_headers`: Accept plus the auth headers
+fn managed_headers(auth: &AuthFields) -> Result<reqwest::header::HeaderMap, String> {
     let mut headers = reqwest::header::HeaderMap::new();
 
     // CRITICAL: Nimbus REST API returns XML by default - we MUST request JSON
@@ -31,21 +232,16 @@ fn build_headers(
         "application/json".parse()
             .map_err(|e| format!("Invalid Accept header: {}", e))?,
     );
-    headers.insert(
-        reqwest::header::CONTENT_TYPE,
-        "application/json".parse()
-            .map_err(|e| format!("Invalid Content-Type header: {}", e))?,
-    );
 
     // App Token auth mode - uses AppToken + Username headers
-    if let Some(ref token) = app_token {
+    if let Some(ref token) = auth.app_token {
         headers.insert(
             "AppToken",
             token.parse()
                 .map_err(|e| format!("Invalid AppToken header: {}", e))?,
         );
 
-        if let Some(ref user) = username {
+        if let Some(ref user) = auth.username {
             headers.insert(
                 "Username",
                 user.parse()
@@ -53,9 +249,9 @@ fn build_headers(
             );
         }
     }
-    // Credential-based auth mode - uses UserID + AuthenticationToken headers
+    // Credential-based auth mode - header layout depends on the auth scheme
     else {
-        if let Some(user_id) = user_id {
+        if let (Some(user_id), AuthScheme::NimbusDual) = (auth.user_id, &auth.scheme) {
             headers.insert(
                 "UserID",
                 user_id.to_string().parse()
@@ -63,38 +259,158 @@ fn build_headers(
             );
         }
 
-        if let Some(ref token) = auth_token {
-            // Nimbus requires both Authorization Bearer AND AuthenticationToken headers
-            let auth_value = format!("Bearer {}", token);
-            headers.insert(
-                reqwest::header::AUTHORIZATION,
-                auth_value.parse()
-                    .map_err(|e| format!("Invalid authorization header: {}", e))?,
-            );
+        if let Some(ref token) = auth.auth_token {
+            match auth.scheme {
+                AuthScheme::NimbusDual => {
+                    // Nimbus requires both Authorization Bearer AND AuthenticationToken headers
+                    let auth_value = format!("Bearer {}", token);
+                    headers.insert(
+                        reqwest::header::AUTHORIZATION,
+                        auth_value.parse()
+                            .map_err(|e| format!("Invalid authorization header: {}", e))?,
+                    );
 
-            headers.insert(
-                "AuthenticationToken",
-                token.parse()
-                    .map_err(|e| format!("Invalid AuthenticationToken header: {}", e))?,
-            );
+                    headers.insert(
+                        "AuthenticationToken",
+                        token.parse()
+                            .map_err(|e| format!("Invalid AuthenticationToken header: {}", e))?,
+                    );
+                }
+                AuthScheme::BearerOnly => {
+                    headers.insert(
+                        reqwest::header::AUTHORIZATION,
+                        format!("Bearer {}", token).parse()
+                            .map_err(|e| format!("Invalid authorization header: {}", e))?,
+                    );
+                }
+                AuthScheme::ApiKey { ref header_name } => {
+                    let name = reqwest::header::HeaderName::from_bytes(header_name.as_bytes())
+                        .map_err(|e| format!("Invalid API key header name '{}': {}", header_name, e))?;
+                    headers.insert(
+                        name,
+                        token.parse()
+                            .map_err(|e| format!("Invalid API key header: {}", e))?,
+                    );
+                }
+                AuthScheme::Basic => {
+                    let user = auth.username.as_deref().unwrap_or_default();
+                    let encoded = BASE64.encode(format!("{}:{}", user, token));
+                    headers.insert(
+                        reqwest::header::AUTHORIZATION,
+                        format!("Basic {}", encoded).parse()
+                            .map_err(|e| format!("Invalid authorization header: {}", e))?,
+                    );
+                }
+            }
         }
     }
 
-    if let Some(custom) = custom_headers {
-        for (key, value) in custom {
-            let header_name = reqwest::header::HeaderName::from_bytes(key.as_bytes())
-                .map_err(|e| format!("Invalid header key '{}': {}", key, e))?;
-            let header_value = reqwest::header::HeaderValue::from_str(&value)
-                .map_err(|e| format!("Invalid header value for '{}': {}", key, e))?;
-            headers.insert(header_name, header_value);
+    Ok(headers)
+}
+
+/// Layers 2 and 3 of `build_headers`: `insert` replaces every existing value for the name, so the caller's value wins
+fn apply_custom_headers(
+    headers: &mut reqwest::header::HeaderMap,
+    custom: HashMap<String, String>,
+) -> Result<(), String> {
+    for (key, value) in custom {
+        let header_name = reqwest::header::HeaderName::from_bytes(key.as_bytes())
+            .map_err(|e| format!("Invalid header key '{}': {}", key, e))?;
+        let header_value = reqwest::header::HeaderValue::from_str(&value)
+            .map_err(|e| format!("Invalid header value for '{}': {}", key, e))?;
+        headers.insert(header_name, header_value);
+    }
+    Ok(())
+}
+
+/// Validate a Nimbus base URL and put it in canonical form: `https://` added when no scheme
+/// is given, only http/https accepted, trailing slashes removed
+pub(crate) fn normalize_base_url(input: &str) -> Result<String, String> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err("Invalid base URL: it is empty".to_string());
+    }
+
+    let with_scheme = if trimmed.contains("://") {
+        trimmed.to_string()
+    } else {
+        format!("https://{}", trimmed)
+    };
+
+    let parsed = url::Url::parse(&with_scheme)
+        .map_err(|e| format!("Invalid base URL '{}': {}", trimmed, e))?;
+
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!(
+            "Invalid base URL '{}': scheme must be http or https, not '{}'",
+            trimmed,
+            parsed.scheme()
+        ));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("Invalid base URL '{}': no host name", trimmed));
+    }
+
+    Ok(parsed.as_str().trim_end_matches('/').to_string())
+}
+
+/// Resolve the request URL from either a full `url` or `base_url` + `endpoint`
+pub(crate) fn resolve_url(
+    url: Option<String>,
+    base_url: Option<String>,
+    endpoint: Option<String>,
+) -> Result<String, String> {
+    if let Some(u) = url {
+        Ok(u)
+    } else if let Some(base) = base_url {
+        let base = normalize_base_url(&base)?;
+        if let Some(ep) = endpoint {
+            Ok(format!("{}{}", base, ep))
+        } else {
+            Ok(base)
         }
+    } else if let Some(ep) = endpoint {
+        Ok(ep)
+    } else {
+        Err("No URL provided. Pass 'url' or 'baseUrl' (optionally with 'endpoint')".to_string())
     }
+}
 
-    Ok(headers)
+/// Read the response body, giving up as soon as it grows past `max_bytes`
+/// (checked against Content-Length up front, then counted while streaming)
+pub(crate) async fn read_body_limited(
+    mut response: reqwest::Response,
+    max_bytes: Option<u64>,
+) -> Result<String, AppError> {
+    let limit = max_bytes.unwrap_or(DEFAULT_MAX_RESPONSE_BYTES);
+    let too_large = || AppError::typed(ErrorKind::ResponseTooLarge { limit_bytes: limit });
+
+    if response.content_length().is_some_and(|len| len > limit) {
+        return Err(too_large());
+    }
+
+    let mut body: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read response body: {}", e))?
+    {
+        if body.len() as u64 + chunk.len() as u64 > limit {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(String::from_utf8(body)
+        .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned()))
 }
 
-async fn response_to_http_response(response: reqwest::Response) -> Result<HttpResponse, String> {
+async fn response_to_http_response(
+    response: reqwest::Response,
+    max_response_bytes: Option<u64>,
+) -> Result<HttpResponse, AppError> {
     let status = response.status().as_u16();
+    let final_url = response.url().to_string();
 
     let mut headers = HashMap::new();
     for (key, value) in response.headers() {
@@ -103,21 +419,307 @@ async fn response_to_http_response(response: reqwest::Response) -> Result<HttpRe
         }
     }
 
-    let body = response.text()
-        .await
-        .map_err(|e| format!("Failed to read response body: {}", e))?;
+    let body = read_body_limited(response, max_response_bytes).await?;
 
     Ok(HttpResponse {
         status,
         body,
         headers,
+        final_url,
     })
 }
 
+/// Headers whose values are replaced with "[REDACTED]" anywhere a request is shown or recorded
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "authenticationtoken",
+    "apptoken",
+    "cookie",
+    "proxy-authorization",
+    // Usual names for AuthScheme::ApiKey headers
+    "x-api-key",
+    "api-key",
+    "apikey",
+];
+
+pub(crate) fn is_sensitive_header(name: &str) -> bool {
+    SENSITIVE_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name))
+}
+
+/// Describe a request exactly as it would be sent - `{ method, url, headers }` with secrets redacted.
+/// Built from the finished reqwest request so it reflects the same URL encoding as a real send.
+pub(crate) fn describe_request(builder: RequestBuilder) -> Result<Value, String> {
+    let request = builder
+        .build()
+        .map_err(|e| format!("Failed to build request: {}", e))?;
+
+    let mut headers = serde_json::Map::new();
+    for (name, value) in request.headers() {
+        let value = if is_sensitive_header(name.as_str()) {
+            "[REDACTED]".to_string()
+        } else {
+            value.to_str().unwrap_or("[binary]").to_string()
+        };
+        headers.insert(name.to_string(), Value::String(value));
+    }
+
+    Ok(serde_json::json!({
+        "method": request.method().as_str(),
+        "url": request.url().as_str(),
+        "headers": headers,
+    }))
+}
+
+/// Turn a 401/403 into an `Unauthorized` error so every command reports auth failures the same way.
+/// `token_was_stale` is false when the token was just refreshed, since a new token can't have expired.
+fn check_unauthorized(response: &reqwest::Response, token_was_stale: bool) -> Result<(), AppError> {
+    let status = response.status();
+    if status != StatusCode::UNAUTHORIZED && status != StatusCode::FORBIDDEN {
+        return Ok(());
+    }
+
+    let www_authenticate = response.headers()
+        .get(reqwest::header::WWW_AUTHENTICATE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    Err(AppError::typed(ErrorKind::Unauthorized {
+        status: status.as_u16(),
+        // 403 means the user is known but lacks permission - signing in again won't help
+        token_likely_expired: status == StatusCode::UNAUTHORIZED && token_was_stale,
+        www_authenticate,
+    }))
+}
+
+/// Turn a 429 into a `RateLimited` error carrying the server's throttling hints
+/// (`Retry-After` as seconds or an HTTP date, plus any `X-RateLimit-*` headers)
+fn check_rate_limited(response: &reqwest::Response) -> Result<(), AppError> {
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return Ok(());
+    }
+
+    let headers = response.headers();
+    let header_str = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).map(str::trim);
+    let header_u64 = |name: &str| header_str(name).and_then(|v| v.parse::<u64>().ok());
+
+    let retry_after_secs = header_str("retry-after").and_then(|v| {
+        v.parse::<u64>().ok().or_else(|| {
+            chrono::DateTime::parse_from_rfc2822(v)
+                .ok()
+                .map(|at| (at.timestamp() - chrono::Utc::now().timestamp()).max(0) as u64)
+        })
+    });
+
+    Err(AppError::typed(ErrorKind::RateLimited {
+        retry_after_secs,
+        limit: header_u64("x-ratelimit-limit"),
+        remaining: header_u64("x-ratelimit-remaining"),
+    }))
+}
+
+/// Managed state an outgoing request passes through, borrowed from the command's `State` parameters
+#[derive(Clone)]
+pub(crate) struct RequestContext<'a> {
+    pub store: &'a KeyStore,
+    pub log: &'a RequestLog,
+    pub circuits: &'a CircuitBreakers,
+    pub network: &'a NetworkConfig,
+    // Held while a 401 or near-expiry refresh rewrites the profile's session
+    pub locks: &'a ProfileLocks,
+    // Overall budget from begin_deadline shared with the command's other requests
+    pub deadline: Option<Deadline>,
+}
+
+/// Send a request through the host's circuit breaker and record it (redacted) in the request log,
+/// whether or not a response came back
+pub(crate) async fn send_logged(
+    ctx: &RequestContext<'_>,
+    builder: RequestBuilder,
+    error_label: &str,
+) -> Result<reqwest::Response, AppError> {
+    let (client, request) = builder.build_split();
+    let request = request.map_err(|e| format!("{} failed: {}", error_label, e))?;
+
+    let deadline_exceeded = |deadline: &Deadline| {
+        AppError::typed(ErrorKind::DeadlineExceeded { deadline_id: deadline.id.clone() })
+    };
+    // A spent budget fails before anything is sent or queued
+    if let Some(deadline) = ctx.deadline.as_ref().filter(|d| d.remaining().is_zero()) {
+        return Err(deadline_exceeded(deadline));
+    }
+
+    let (log, circuits) = (ctx.log, ctx.circuits);
+    let host = circuit_key(request.url());
+    circuits.before_request(&host)?;
+
+    let method = request.method().to_string();
+    let url_redacted = redact_url(request.url());
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let started = Instant::now();
+    let pending_capture = log.capture.claim(&request);
+
+    let in_flight = ctx.network.in_flight.register();
+    // Queued requests can be cancelled too, before they ever reach the server
+    let send = async {
+        let _permit = ctx.network.scheduler.acquire().await;
+        client.execute(request).await
+    };
+    // The client's own timeout still applies, so each request gets whichever runs out first
+    let budget_spent = async {
+        match &ctx.deadline {
+            Some(deadline) => {
+                tokio::time::sleep(deadline.remaining()).await;
+                deadline_exceeded(deadline)
+            }
+            None => std::future::pending().await,
+        }
+    };
+    let outcome = tokio::select! {
+        result = send => Ok(result),
+        _ = in_flight.cancelled() => Err(AppError::typed(ErrorKind::Cancelled)),
+        error = budget_spent => Err(error),
+    };
+    let result = match outcome {
+        Ok(result) => result,
+        Err(error) => {
+            // Not the host's fault, so the circuit breaker doesn't count it
+            circuits.release_trial(&host);
+            log.record(RequestLogEntry {
+                timestamp,
+                method,
+                url_redacted,
+                status: None,
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
+            return Err(error);
+        }
+    };
+    drop(in_flight);
+
+    log.record(RequestLogEntry {
+        timestamp,
+        method,
+        url_redacted,
+        status: result.as_ref().ok().map(|r| r.status().as_u16()),
+        duration_ms: started.elapsed().as_millis() as u64,
+    });
+
+    match result {
+        Ok(ref response) if !response.status().is_server_error() => circuits.record_success(&host),
+        _ => circuits.record_failure(&host),
+    }
+
+    let response = result.map_err(|e| format!("{} failed: {}", error_label, e))?;
+    match pending_capture {
+        Some(pending) => Ok(log.capture.record(pending, response).await?),
+        None => Ok(response),
+    }
+}
+
+/// Send a request, and if it comes back 401 for a stored profile, re-authenticate once and retry.
+/// `build` is called again for the retry, so it must not consume anything it can't recreate.
+pub(crate) async fn send_with_refresh<F>(
+    ctx: &RequestContext<'_>,
+    profile_name: Option<&str>,
+    auth: AuthFields,
+    custom_headers: Option<HashMap<String, String>>,
+    error_label: &str,
+    build: F,
+) -> Result<reqwest::Response, AppError>
+where
+    F: Fn(reqwest::header::HeaderMap) -> RequestBuilder,
+{
+    let sent_token = auth.auth_token.is_some() || auth.app_token.is_some();
+    let headers = build_headers(custom_headers.clone(), &auth)?;
+    let mut response = send_logged(ctx, build(headers), error_label).await?;
+    let mut refreshed = false;
+
+    if let Some(profile_name) = profile_name {
+        if response.status() == StatusCode::UNAUTHORIZED {
+            // Only one refresh per request - a second 401 goes straight back to the caller
+            // Skipped when another request already replaced the rejected token while this one waited
+            let rejected = auth.auth_token.as_deref().or(auth.app_token.as_deref());
+            let refresh = session::reauthenticate(ctx, profile_name, |stored| {
+                rejected.is_none() || session::session_token(stored).as_deref() == rejected
+            }).await;
+            // A failed refresh still reports the original 401, so the caller knows to sign in again
+            let credentials = match refresh {
+                Ok(credentials) => credentials,
+                Err(refresh_error) => {
+                    check_unauthorized(&response, true)
+                        .map_err(|e| e.with_context(&format!("session refresh failed: {}", refresh_error)))?;
+                    return Err(refresh_error);
+                }
+            };
+            let refreshed_auth = AuthFields { scheme: auth.scheme.clone(), ..AuthFields::from(&credentials) };
+            let headers = build_headers(custom_headers, &refreshed_auth)?;
+            response = send_logged(ctx, build(headers), error_label).await?;
+            refreshed = true;
+        }
+    }
+
+    check_unauthorized(&response, sent_token && !refreshed)?;
+    check_rate_limited(&response)?;
+    Ok(response)
+}
+
+/// Everything `execute_odata_query` accepts, as one object: the `$` query options plus auth and
+/// transport settings. New query options only need a field here, not another positional argument.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ODataQueryParams {
+    pub base_url: String,
+    pub entity: String,
+    // OData query options
+    pub top: Option<i32>,
+    pub skip: Option<i32>,
+    pub filter: Option<String>,
+    pub select: Option<String>,
+    // Without an explicit select, build one covering every field (adhoc fields are only returned when selected)
+    pub select_all: Option<bool>,
+    pub expand: Option<String>,
+    pub expand_specs: Option<Vec<ExpandSpec>>,
+    pub orderby: Option<String>,
+    pub count: Option<bool>,
+    // `$compute` expressions, e.g. `Price mul Quantity as Total`; `select` can name their aliases.
+    // Only newer Nimbus versions accept it - see detect_capabilities
+    pub compute: Option<String>,
+    // Server continuation token from a previous page's nextLink (see PageCursor)
+    pub skiptoken: Option<String>,
+    // A previous page's nextLink (absolute or relative to the service root), requested exactly as the
+    // server sent it; the query options above are then ignored since the link already carries them
+    pub next_link: Option<String>,
+    pub format: Option<String>,
+    // Auth (ignored when profile_name is set)
+    pub user_id: Option<i32>,
+    pub auth_token: Option<String>,
+    pub app_token: Option<String>,
+    pub username: Option<String>,
+    pub auth_scheme: Option<AuthScheme>,
+    pub profile_name: Option<String>,
+    // Transport
+    pub timeout_seconds: Option<u64>,
+    pub connect_timeout_seconds: Option<u64>,
+    pub max_response_bytes: Option<u64>,
+    pub redirect_policy: Option<RedirectPolicy>,
+    pub dry_run: Option<bool>,
+    // Handle from begin_deadline; the query gets at most the budget that's left
+    pub deadline_id: Option<String>,
+    // Accept-Language for this query only, instead of the app-wide one (see set_accept_language)
+    pub accept_language: Option<String>,
+}
+
 /// Execute OData query and return parsed JSON
-/// Handles both array [...] and object { value: [...] } response formats from Nimbus
+/// Kept for existing callers - new code should use `execute_odata_query_v2`. The arguments are
+/// frozen; newer options are only available through `ODataQueryParams`.
 #[tauri::command]
 pub async fn execute_odata_query(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
     base_url: String,
     entity: String,
     top: Option<i32>,
@@ -132,96 +734,453 @@ pub async fn execute_odata_query(
     app_token: Option<String>,
     username: Option<String>,
     timeout_seconds: Option<u64>,
-) -> Result<Value, String> {
-    let client = build_client(timeout_seconds)?;
-
-    // Build OData URL - Use /CoreApi/OData/ which returns adhoc fields with $select
-    // Legacy /ODataApi/ does NOT return adhoc fields even with $select
-    let odata_base = if base_url.ends_with("/CoreApi/OData") || base_url.ends_with("/CoreApi/OData/") {
-        base_url.trim_end_matches('/').to_string()
-    } else if base_url.ends_with("/ODataApi") || base_url.ends_with("/ODataApi/") {
-        // Convert legacy endpoint to CoreApi
-        base_url.replace("/ODataApi", "/CoreApi/OData").trim_end_matches('/').to_string()
-    } else if base_url.ends_with("/odata") || base_url.ends_with("/odata/") {
-        base_url.trim_end_matches('/').to_string()
+    profile_name: Option<String>,
+    max_response_bytes: Option<u64>,
+    redirect_policy: Option<RedirectPolicy>,
+    expand_specs: Option<Vec<ExpandSpec>>,
+    dry_run: Option<bool>,
+    format: Option<String>,
+) -> Result<Value, AppError> {
+    let params = ODataQueryParams {
+        base_url,
+        entity,
+        top,
+        skip,
+        filter,
+        select,
+        expand,
+        expand_specs,
+        orderby,
+        count,
+        format,
+        user_id,
+        auth_token,
+        app_token,
+        username,
+        profile_name,
+        timeout_seconds,
+        max_response_bytes,
+        redirect_policy,
+        dry_run,
+        ..Default::default()
+    };
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: None,
+    };
+    run_odata_query(&ctx, &metrics, &fields, params).await
+}
+
+/// Execute OData query from a single params object and return parsed JSON
+/// Handles both array [...] and object { value: [...] } response formats from Nimbus
+/// An XML response is converted and returned as `{ value, "@converted_from_xml": true }`
+/// When `profile_name` is set, auth comes from that stored profile and is refreshed as needed
+/// With `dry_run`, nothing is sent and `{ method, url, headers }` (secrets redacted) is returned instead
+#[tauri::command]
+pub async fn execute_odata_query_v2(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    params: ODataQueryParams,
+) -> Result<Value, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(params.deadline_id.as_deref())?,
+    };
+    run_odata_query(&ctx, &metrics, &fields, params).await
+}
+
+/// Like `execute_odata_query_v2`, but every row must carry all of `required_fields`.
+/// Rows missing any come back as a `MissingFields` error naming each row and field, which catches
+/// adhoc fields silently dropped by the endpoint before they turn into nulls downstream.
+#[tauri::command]
+pub async fn execute_odata_typed(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    params: ODataQueryParams,
+    required_fields: Vec<String>,
+) -> Result<Value, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(params.deadline_id.as_deref())?,
+    };
+    let dry_run = params.dry_run.unwrap_or(false);
+    let json = run_odata_query(&ctx, &metrics, &fields, params).await?;
+    if dry_run {
+        return Ok(json);
+    }
+
+    let missing: Vec<MissingRowFields> = result_rows(&json)
+        .iter()
+        .enumerate()
+        .filter_map(|(row, value)| {
+            let fields: Vec<String> = required_fields
+                .iter()
+                .filter(|field| value.get(field.as_str()).is_none())
+                .cloned()
+                .collect();
+            (!fields.is_empty()).then_some(MissingRowFields { row, fields })
+        })
+        .collect();
+
+    if missing.is_empty() {
+        Ok(json)
+    } else {
+        Err(AppError::typed(ErrorKind::MissingFields { rows: missing }))
+    }
+}
+
+/// Timing and size of one OData query, without its rows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub status: u16,
+    pub rows: usize,
+    // Body size as received (after any transfer decompression)
+    pub bytes: usize,
+    // From sending the request to the end of the body, including any session refresh
+    pub duration_ms: u64,
+    // `@odata.count`, when the query asked for it with `count`
+    pub count: Option<i64>,
+}
+
+/// Run a query for a query-tuning view: report its status, row count, size and time, and discard
+/// the body so large results never reach the frontend. A failing status (e.g. 400 for a bad
+/// filter) is reported in `status` with zero rows rather than as an error.
+#[tauri::command]
+pub async fn probe_odata(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    mut params: ODataQueryParams,
+) -> Result<ProbeResult, AppError> {
+    if params.dry_run.unwrap_or(false) {
+        return Err("dry_run isn't supported when probing a query".to_string().into());
+    }
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(params.deadline_id.as_deref())?,
+    };
+
+    let started = Instant::now();
+    let response = match send_odata_query(&ctx, &metrics, &fields, &mut params).await? {
+        ODataSend::Sent(response) => response,
+        ODataSend::DryRun(_) => return Err("dry_run isn't supported when probing a query".to_string().into()),
+    };
+    let status = response.status();
+    let content_type = content_type(&response);
+    let body = read_body_limited(response, params.max_response_bytes).await?;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let (rows, count) = if status.is_success() {
+        let json = parse_odata_body(status, content_type.as_deref(), &body)?;
+        (unwrap_odata_value(&json)?.len(), extract_odata_count(&json))
     } else {
-        format!("{}/CoreApi/OData", base_url.trim_end_matches('/'))
+        (0, None)
     };
 
+    Ok(ProbeResult { status: status.as_u16(), rows, bytes: body.len(), duration_ms, count })
+}
+
+/// The aliases a `$compute` option defines: the name after the last `as` of each comma-separated item
+fn compute_aliases(compute: &str) -> Vec<&str> {
+    split_top_level(compute, |c| c == ',')
+        .into_iter()
+        .filter_map(|item| {
+            let words: Vec<&str> = item.split_whitespace().collect();
+            match words.as_slice() {
+                [.., "as", alias] => Some(*alias),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Build the full URL for an OData query: the service root (see `odata_service_root`), the entity, then
+/// the query options in a fixed order - `$top`, `$skip`, `$filter`, `$compute`, `$select`, `$expand`,
+/// `$orderby`, `$count`, `$skiptoken`, `$format`. Only the query options are read from `params`.
+pub(crate) fn odata_query_url(odata_base: &str, entity: &str, params: &ODataQueryParams) -> Result<String, String> {
+    let non_empty = |value: &Option<String>| value.clone().filter(|v| !v.is_empty());
+
     let mut url = format!("{}/{}", odata_base, entity);
     let mut query_params: Vec<String> = Vec::new();
 
-    if let Some(top) = top {
+    if let Some(top) = params.top {
         query_params.push(format!("$top={}", top));
     }
 
-    if let Some(skip) = skip {
+    if let Some(skip) = params.skip {
         query_params.push(format!("$skip={}", skip));
     }
 
-    if let Some(ref f) = filter {
-        if !f.is_empty() {
-            // Don't URL encode - OData handles this
-            query_params.push(format!("$filter={}", f));
-        }
+    // Don't URL encode - OData handles this
+    if let Some(f) = non_empty(&params.filter) {
+        query_params.push(format!("$filter={}", f));
     }
 
-    if let Some(ref s) = select {
-        if !s.is_empty() {
-            query_params.push(format!("$select={}", s));
-        }
+    // Passed through raw like $filter; computed aliases can then appear in $select and $orderby
+    if let Some(c) = non_empty(&params.compute) {
+        query_params.push(format!("$compute={}", c));
     }
 
-    if let Some(ref e) = expand {
-        if !e.is_empty() {
-            query_params.push(format!("$expand={}", e));
-        }
+    if let Some(s) = non_empty(&params.select) {
+        query_params.push(format!("$select={}", s));
     }
 
-    if let Some(ref ob) = orderby {
-        if !ob.is_empty() {
-            query_params.push(format!("$orderby={}", ob));
-        }
+    // Raw expand is passed through untouched, so nested options such as
+    // `Transactions($select=Amount;$filter=Amount gt 0)` keep their parentheses and ';' separators
+    // (reqwest only percent-encodes the spaces). Structured `expand_specs` take precedence.
+    let expand = match params.expand_specs {
+        Some(ref specs) if !specs.is_empty() => Some(expand_to_odata(specs)),
+        _ => non_empty(&params.expand),
+    };
+    if let Some(e) = expand.filter(|e| !e.is_empty()) {
+        query_params.push(format!("$expand={}", e));
+    }
+
+    // A typo such as `Amount descending` is caught here instead of coming back as a 400
+    if let Some(ob) = non_empty(&params.orderby) {
+        validate_orderby(&ob)?;
+        query_params.push(format!("$orderby={}", ob));
     }
 
-    if count.unwrap_or(false) {
+    if params.count.unwrap_or(false) {
         query_params.push("$count=true".to_string());
     }
 
+    // Opaque server token - encoded so it survives exactly as the server issued it
+    if let Some(token) = non_empty(&params.skiptoken) {
+        query_params.push(format!("$skiptoken={}", urlencoding::encode(&token)));
+    }
+
+    // Explicit $format for deployments that ignore Accept, e.g. `json` or
+    // `application/json;odata.metadata=minimal` (encoded so its ';' and '=' stay part of the value)
+    if let Some(f) = non_empty(&params.format) {
+        query_params.push(format!("$format={}", urlencoding::encode(&f)));
+    }
+
     if !query_params.is_empty() {
         url = format!("{}?{}", url, query_params.join("&"));
     }
+    Ok(url)
+}
 
-    let headers = build_headers(None, user_id, auth_token, app_token, username)?;
+/// The exact URL `execute_odata_query_v2` would request for `params`, for showing or copying.
+/// `select_all` isn't expanded here, since resolving the field list needs a call to the server.
+#[tauri::command]
+pub fn build_odata_url(network: State<'_, NetworkConfig>, params: ODataQueryParams) -> Result<String, String> {
+    odata_query_url(&odata_service_root(&network, &params.base_url)?, &params.entity, &params)
+}
 
-    // Log the URL for debugging
-    println!("OData query URL: {}", url);
+/// What `send_odata_query` did: described the request (dry run) or sent it
+enum ODataSend {
+    DryRun(Value),
+    Sent(reqwest::Response),
+}
 
-    let response = client
-        .get(&url)
-        .headers(headers)
-        .send()
-        .await
-        .map_err(|e| format!("OData request failed: {}", e))?;
+/// Resolve auth and `select_all`, then send the query (or describe it, for a dry run).
+/// The response is returned as-is, whatever its status.
+async fn send_odata_query(
+    ctx: &RequestContext<'_>,
+    metrics: &QueryMetrics,
+    fields: &EntityFieldCache,
+    params: &mut ODataQueryParams,
+) -> Result<ODataSend, AppError> {
+    let client = build_client(
+        ctx.network,
+        params.timeout_seconds,
+        params.connect_timeout_seconds,
+        params.redirect_policy,
+    )?;
+
+    let odata_base = odata_service_root(ctx.network, &params.base_url)?;
+    let profile_name = params.profile_name.take();
+
+    let auth = session::resolve_auth(
+        ctx,
+        profile_name.as_deref(),
+        AuthFields {
+            user_id: params.user_id,
+            auth_token: params.auth_token.take(),
+            app_token: params.app_token.take(),
+            username: params.username.take(),
+            scheme: params.auth_scheme.take().unwrap_or_default(),
+            ..Default::default()
+        },
+    ).await?;
+
+    let next_link = params.next_link.clone().filter(|link| !link.is_empty());
+    if next_link.is_none() && params.select.as_deref().is_none_or(str::is_empty) && params.select_all.unwrap_or(false) {
+        let all = entity_fields(ctx, fields, &client, &odata_base, &params.entity, profile_name.as_deref(), auth.clone()).await?;
+        // Computed fields aren't in the entity's field list, so select their aliases as well
+        let aliases = params.compute.as_deref().map(compute_aliases).unwrap_or_default();
+        params.select = Some(all.iter().map(String::as_str).chain(aliases).collect::<Vec<_>>().join(","));
+    }
+
+    let url = match next_link {
+        Some(ref link) => resolve_next_link(&odata_base, link)?,
+        None => odata_query_url(&odata_base, &params.entity, params)?,
+    };
+    let format = params.format.as_deref().is_some_and(|f| !f.is_empty());
+
+    let accept_language = params.accept_language.as_deref()
+        .map(str::trim)
+        .filter(|al| !al.is_empty())
+        .map(|al| reqwest::header::HeaderValue::from_str(al)
+            .map_err(|e| format!("Invalid Accept-Language '{}': {}", al, e)))
+        .transpose()?;
+
+    // $format and the forced `Accept: application/json` can disagree; drop Accept so $format wins
+    let prepare = |mut headers: reqwest::header::HeaderMap| {
+        if format {
+            headers.remove(reqwest::header::ACCEPT);
+        }
+        if let Some(ref accept_language) = accept_language {
+            headers.insert(reqwest::header::ACCEPT_LANGUAGE, accept_language.clone());
+        }
+        client.get(&url).headers(headers)
+    };
+
+    if params.dry_run.unwrap_or(false) {
+        let headers = build_headers(None, &auth)?;
+        return Ok(ODataSend::DryRun(describe_request(prepare(headers))?));
+    }
+
+    let started = Instant::now();
+    let response = send_with_refresh(ctx, profile_name.as_deref(), auth, None, "OData request", prepare).await?;
+    metrics.record(&params.entity, started.elapsed().as_millis() as u64);
+    Ok(ODataSend::Sent(response))
+}
+
+/// Parse an OData body, converting the XML some legacy endpoints still send despite
+/// `Accept: application/json` into `{ value, "@converted_from_xml": true }`.
+/// A 204 or blank body is an empty result (`{ value: [] }`), not a parse error.
+fn parse_odata_body(status: StatusCode, content_type: Option<&str>, body: &str) -> Result<Value, String> {
+    if status == StatusCode::NO_CONTENT || body.trim().is_empty() {
+        return Ok(serde_json::json!({ "value": [] }));
+    }
+    if looks_like_xml(content_type, body) {
+        let converted = xml_to_json(body)
+            .map_err(|e| format!("Server returned XML, not JSON, and it could not be converted: {}", e))?;
+        return Ok(serde_json::json!({
+            "value": converted,
+            "@converted_from_xml": true,
+        }));
+    }
+
+    serde_json::from_str(body)
+        .map_err(|e| format!("Failed to parse OData response as JSON: {}", e))
+}
+
+fn content_type(response: &reqwest::Response) -> Option<String> {
+    response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+pub(crate) async fn run_odata_query(
+    ctx: &RequestContext<'_>,
+    metrics: &QueryMetrics,
+    fields: &EntityFieldCache,
+    mut params: ODataQueryParams,
+) -> Result<Value, AppError> {
+    let response = match send_odata_query(ctx, metrics, fields, &mut params).await? {
+        ODataSend::DryRun(description) => return Ok(description),
+        ODataSend::Sent(response) => response,
+    };
 
     let status = response.status();
     if !status.is_success() {
-        let body = response.text().await.unwrap_or_default();
-        return Err(format!("OData query failed with status {}: {}", status.as_u16(), body));
+        let body = read_body_limited(response, params.max_response_bytes).await.unwrap_or_default();
+        return Err(format!("OData query failed with status {}: {}", status.as_u16(), body).into());
     }
 
-    let body = response.text().await
-        .map_err(|e| format!("Failed to read response body: {}", e))?;
+    let content_type = content_type(&response);
+    let body = read_body_limited(response, params.max_response_bytes).await?;
+    Ok(parse_odata_body(status, content_type.as_deref(), &body)?)
+}
+
+/// HTTP Basic credentials for endpoints outside the Nimbus token scheme: `(username, password)`
+pub(crate) type BasicAuth = (String, String);
 
-    let json: Value = serde_json::from_str(&body)
-        .map_err(|e| format!("Failed to parse OData response as JSON: {}", e))?;
+/// Basic auth replaces the token headers rather than adding to them, so refuse a request that
+/// asks for both (or that already carries its own Authorization header)
+fn check_basic_auth_exclusive(
+    basic_auth: &Option<BasicAuth>,
+    passed: &AuthFields,
+    profile_name: Option<&str>,
+    headers: &Option<HashMap<String, String>>,
+) -> Result<(), String> {
+    if basic_auth.is_none() {
+        return Ok(());
+    }
+    if profile_name.is_some() || passed.auth_token.is_some() || passed.app_token.is_some() || passed.user_id.is_some() {
+        return Err("basic_auth can't be combined with token auth or a profile".to_string());
+    }
+    let has_authorization = headers.as_ref()
+        .is_some_and(|h| h.keys().any(|name| name.eq_ignore_ascii_case("authorization")));
+    if has_authorization {
+        return Err("basic_auth can't be combined with an Authorization header".to_string());
+    }
+    Ok(())
+}
 
-    Ok(json)
+/// Add Basic credentials to a request; reqwest marks the header sensitive so it's never logged
+fn with_basic_auth(builder: RequestBuilder, basic_auth: &Option<BasicAuth>) -> RequestBuilder {
+    match basic_auth {
+        Some((username, password)) => builder.basic_auth(username, Some(password)),
+        None => builder,
+    }
+}
+
+/// The request `execute_rest_get` sends: a plain GET with no body, so no Content-Type
+fn rest_get_request(
+    client: &Client,
+    url: &str,
+    headers: reqwest::header::HeaderMap,
+    basic_auth: &Option<BasicAuth>,
+) -> RequestBuilder {
+    with_basic_auth(client.get(url).headers(headers), basic_auth)
 }
 
 /// Execute REST GET and return HttpResponse
+/// With `basic_auth` (`[username, password]`), HTTP Basic is sent instead of the token headers
 #[tauri::command]
 pub async fn execute_rest_get(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
     url: Option<String>,
     base_url: Option<String>,
     endpoint: Option<String>,
@@ -230,39 +1189,343 @@ pub async fn execute_rest_get(
     auth_token: Option<String>,
     app_token: Option<String>,
     username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
     timeout_seconds: Option<u64>,
-) -> Result<HttpResponse, String> {
-    let client = build_client(timeout_seconds)?;
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    max_response_bytes: Option<u64>,
+    redirect_policy: Option<RedirectPolicy>,
+    deadline_id: Option<String>,
+    basic_auth: Option<BasicAuth>,
+) -> Result<HttpResponse, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let client = build_client(ctx.network, timeout_seconds, connect_timeout_seconds, redirect_policy)?;
 
-    let full_url = if let Some(u) = url {
-        u
-    } else if let Some(base) = base_url {
-        if let Some(ep) = endpoint {
-            format!("{}{}", base.trim_end_matches('/'), ep)
-        } else {
-            base
-        }
-    } else if let Some(ep) = endpoint {
-        ep
-    } else {
-        return Err("No URL provided. Pass 'url' or 'baseUrl' (optionally with 'endpoint')".to_string());
+    let full_url = resolve_url(url, base_url, endpoint)?;
+
+    let passed = AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() };
+    check_basic_auth_exclusive(&basic_auth, &passed, profile_name.as_deref(), &headers)?;
+    let auth = session::resolve_auth(&ctx, profile_name.as_deref(), passed).await?;
+
+    let response = send_with_refresh(&ctx, profile_name.as_deref(), auth, headers, "GET request", |req_headers| {
+        rest_get_request(&client, &full_url, req_headers, &basic_auth)
+    }).await?;
+
+    response_to_http_response(response, max_response_bytes).await
+}
+
+/// Like `execute_rest_get`, but parses the body as JSON and returns `{ status, json, headers, final_url }`.
+/// A body that isn't JSON fails with the parse error and the start of the raw body; keep using
+/// `execute_rest_get` for text or binary endpoints.
+#[tauri::command]
+pub async fn execute_rest_get_json(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    url: Option<String>,
+    base_url: Option<String>,
+    endpoint: Option<String>,
+    headers: Option<HashMap<String, String>>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    max_response_bytes: Option<u64>,
+    redirect_policy: Option<RedirectPolicy>,
+    deadline_id: Option<String>,
+    basic_auth: Option<BasicAuth>,
+) -> Result<JsonResponse, AppError> {
+    let response = execute_rest_get(
+        store, log, circuits, network, locks, url, base_url, endpoint, headers, user_id, auth_token, app_token,
+        username, auth_scheme, timeout_seconds, connect_timeout_seconds, profile_name, max_response_bytes,
+        redirect_policy, deadline_id, basic_auth,
+    ).await?;
+
+    let json: Value = serde_json::from_str(response.body.trim_start_matches('\u{feff}')).map_err(|e| {
+        let preview: String = response.body.chars().take(RAW_BODY_PREVIEW_CHARS).collect();
+        format!("Response (status {}) is not valid JSON: {}. Body: {}", response.status, e, preview)
+    })?;
+
+    Ok(JsonResponse {
+        status: response.status,
+        json,
+        headers: response.headers,
+        final_url: response.final_url,
+    })
+}
+
+// REST paging defaults: the page size asked for, the first page number and the most pages followed
+const DEFAULT_REST_PAGE_SIZE: u32 = 100;
+const DEFAULT_REST_START_PAGE: u32 = 1;
+const DEFAULT_REST_MAX_PAGES: u32 = 100;
+
+// Where the items usually sit in a paged REST body that isn't itself an array
+const REST_ITEM_KEYS: &[&str] = &["value", "items", "data", "results", "records"];
+
+/// Items gathered by `execute_rest_get_paged`. `next_url` is where paging stopped - the page that
+/// failed, or the next one once `max_pages` was reached - and can be passed back as `url` to resume.
+#[derive(Debug, Clone, Serialize)]
+pub struct PagedRestResult {
+    pub items: Vec<Value>,
+    pub pages: u32,
+    pub next_url: Option<String>,
+    pub error: Option<AppError>,
+}
+
+/// Look up a dotted path such as `paging.next` or `$.links.0.href` in a JSON body
+fn json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.trim().trim_start_matches('$').trim_start_matches('.');
+    if path.is_empty() {
+        return Some(value);
+    }
+    let pointer: String = path.split('.')
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect();
+    value.pointer(&pointer)
+}
+
+/// The `rel="next"` target of RFC 8288 `Link` headers
+fn link_next(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers.get_all(reqwest::header::LINK)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|link| {
+            let (target, params) = link.split_once(';')?;
+            let is_next = params.split(';').any(|param| {
+                param.trim().strip_prefix("rel=")
+                    .is_some_and(|rel| rel.trim_matches('"').split_whitespace().any(|r| r.eq_ignore_ascii_case("next")))
+            });
+            let target = target.trim().strip_prefix('<')?.strip_suffix('>')?;
+            is_next.then(|| target.to_string())
+        })
+}
+
+/// `url` with `page_param`/`size_param` set, replacing any values already in the query
+fn with_page(url: &url::Url, page_param: &str, page: u64, size_param: &str, page_size: u32) -> String {
+    let mut url = url.clone();
+    let kept: Vec<(String, String)> = url.query_pairs()
+        .filter(|(name, _)| name != page_param && name != size_param)
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(kept)
+        .append_pair(page_param, &page.to_string())
+        .append_pair(size_param, &page_size.to_string());
+    url.to_string()
+}
+
+/// Follow a paged (non-OData) REST endpoint and concatenate its items into one array.
+/// The first request carries `page_param`/`size_param` (default `page`/`pageSize`, starting at page 1
+/// with 100 items). After that, the next page comes from a `Link: <...>; rel="next"` header if there is
+/// one, otherwise from `next_selector` - a dotted path into the body holding a URL, a page number or
+/// `true` - and without either, the page number goes up until a short or empty page.
+/// Items are the body itself when it's an array, else the array at `items_path` or under a usual key
+/// (`value`, `items`, `data`, ...). At most `max_pages` (100) pages are read; with `partial_ok`, a
+/// failure returns the items read so far plus the error.
+#[tauri::command]
+pub async fn execute_rest_get_paged(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    url: Option<String>,
+    base_url: Option<String>,
+    endpoint: Option<String>,
+    page_param: Option<String>,
+    size_param: Option<String>,
+    page_size: Option<u32>,
+    start_page: Option<u32>,
+    next_selector: Option<String>,
+    items_path: Option<String>,
+    max_pages: Option<u32>,
+    partial_ok: Option<bool>,
+    headers: Option<HashMap<String, String>>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    max_response_bytes: Option<u64>,
+    redirect_policy: Option<RedirectPolicy>,
+    deadline_id: Option<String>,
+) -> Result<PagedRestResult, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
     };
+    let client = build_client(ctx.network, timeout_seconds, connect_timeout_seconds, redirect_policy)?;
 
-    let req_headers = build_headers(headers, user_id, auth_token, app_token, username)?;
+    let page_param = page_param.unwrap_or_else(|| "page".to_string());
+    let size_param = size_param.unwrap_or_else(|| "pageSize".to_string());
+    let page_size = page_size.unwrap_or(DEFAULT_REST_PAGE_SIZE).max(1);
+    let max_pages = max_pages.unwrap_or(DEFAULT_REST_MAX_PAGES);
 
-    let response = client
-        .get(&full_url)
-        .headers(req_headers)
-        .send()
-        .await
-        .map_err(|e| format!("GET request failed: {}", e))?;
+    let first_url = url::Url::parse(&resolve_url(url, base_url, endpoint)?)
+        .map_err(|e| format!("Invalid URL: {}", e))?;
+    // A URL that already names its page (e.g. a `next_url` passed back to resume) is used as-is
+    let named_page = first_url.query_pairs()
+        .find(|(name, _)| *name == page_param)
+        .and_then(|(_, value)| value.parse::<u64>().ok());
+    let mut page = named_page.unwrap_or(start_page.unwrap_or(DEFAULT_REST_START_PAGE) as u64);
+    let mut next_url = Some(match named_page {
+        Some(_) => first_url.to_string(),
+        None => with_page(&first_url, &page_param, page, &size_param, page_size),
+    });
+
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    let mut items: Vec<Value> = Vec::new();
+    let mut pages = 0;
 
-    response_to_http_response(response).await
+    while let Some(current) = next_url.clone() {
+        if pages >= max_pages {
+            break;
+        }
+
+        let fetched = async {
+            let response = send_with_refresh(
+                &ctx,
+                profile_name.as_deref(),
+                auth.clone(),
+                headers.clone(),
+                "GET request",
+                |req_headers| client.get(&current).headers(req_headers),
+            ).await?;
+            let link = link_next(response.headers());
+            let response = response_to_http_response(response, max_response_bytes).await?;
+            if !(200..300).contains(&response.status) {
+                return Err(AppError::from(format!("Page {} returned status {}: {}", pages + 1, response.status, response.body)));
+            }
+            let body: Value = serde_json::from_str(response.body.trim_start_matches('\u{feff}'))
+                .map_err(|e| format!("Page {} is not valid JSON: {}", pages + 1, e))?;
+            Ok((link, response.final_url, body))
+        };
+        let (link, final_url, body) = match fetched.await {
+            Ok(fetched) => fetched,
+            Err(error) if partial_ok.unwrap_or(false) => {
+                return Ok(PagedRestResult { items, pages, next_url, error: Some(error) });
+            }
+            Err(error) => return Err(error),
+        };
+
+        let page_items = match (&body, items_path.as_deref()) {
+            (Value::Array(rows), None) => rows.clone(),
+            (_, Some(path)) => json_path(&body, path)
+                .and_then(Value::as_array)
+                .cloned()
+                .ok_or_else(|| format!("Page {} has no array at '{}'", pages + 1, path))?,
+            (_, None) => REST_ITEM_KEYS.iter()
+                .find_map(|key| body.get(key).and_then(Value::as_array))
+                .cloned()
+                .ok_or_else(|| format!("Page {} has no item array; set items_path", pages + 1))?,
+        };
+        let page_len = page_items.len();
+        items.extend(page_items);
+        pages += 1;
+
+        let base = url::Url::parse(&final_url).unwrap_or_else(|_| first_url.clone());
+        let resolve = |target: &str| base.join(target).map(|u| u.to_string()).ok();
+        next_url = if let Some(link) = link {
+            resolve(&link)
+        } else if let Some(selector) = next_selector.as_deref() {
+            match json_path(&body, selector) {
+                Some(Value::String(target)) if !target.is_empty() => resolve(target),
+                Some(Value::Number(n)) => n.as_u64().map(|next| {
+                    page = next;
+                    with_page(&first_url, &page_param, page, &size_param, page_size)
+                }),
+                Some(Value::Bool(true)) => {
+                    page += 1;
+                    Some(with_page(&first_url, &page_param, page, &size_param, page_size))
+                }
+                _ => None,
+            }
+        } else if page_len == 0 || (page_len as u32) < page_size {
+            None
+        } else {
+            page += 1;
+            Some(with_page(&first_url, &page_param, page, &size_param, page_size))
+        };
+    }
+
+    Ok(PagedRestResult { items, pages, next_url, error: None })
+}
+
+/// Check a POST body has every `required_fields` entry as a non-null top-level field, naming each
+/// one that's missing or null. Presence only - values aren't type-checked.
+#[tauri::command]
+pub fn validate_post_body(body: Value, required_fields: Vec<String>) -> Result<(), String> {
+    check_required_fields(&body, &required_fields)
+}
+
+fn check_required_fields(body: &Value, required_fields: &[String]) -> Result<(), String> {
+    let Value::Object(object) = body else {
+        return Err(format!("Request body must be a JSON object, got {}", body));
+    };
+
+    let (mut missing, mut null) = (Vec::new(), Vec::new());
+    for field in required_fields {
+        match object.get(field) {
+            None => missing.push(field.as_str()),
+            Some(Value::Null) => null.push(field.as_str()),
+            Some(_) => {}
+        }
+    }
+
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!("missing {}", missing.join(", ")));
+    }
+    if !null.is_empty() {
+        problems.push(format!("null {}", null.join(", ")));
+    }
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Request body has required fields {}", problems.join("; ")))
+    }
 }
 
 /// Execute REST POST and return HttpResponse (used for authentication)
+/// With `required_fields`, the body is checked by `validate_post_body` before anything is sent
+/// With `idempotency_key`, the key is sent as `Idempotency-Key` and a repeat call with the same key
+/// returns the first call's response instead of sending again (for a limited time)
+/// With `basic_auth` (`[username, password]`), HTTP Basic is sent instead of the token headers
 #[tauri::command]
 pub async fn execute_rest_post(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    idempotency: State<'_, IdempotencyCache>,
     url: Option<String>,
     base_url: Option<String>,
     endpoint: Option<String>,
@@ -272,33 +1535,505 @@ pub async fn execute_rest_post(
     auth_token: Option<String>,
     app_token: Option<String>,
     username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
     timeout_seconds: Option<u64>,
-) -> Result<HttpResponse, String> {
-    let client = build_client(timeout_seconds)?;
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    max_response_bytes: Option<u64>,
+    redirect_policy: Option<RedirectPolicy>,
+    idempotency_key: Option<String>,
+    deadline_id: Option<String>,
+    basic_auth: Option<BasicAuth>,
+    required_fields: Option<Vec<String>>,
+    compress_body: Option<bool>,
+) -> Result<HttpResponse, AppError> {
+    if let Some(ref required_fields) = required_fields {
+        check_required_fields(&body, required_fields)?;
+    }
 
-    let full_url = if let Some(u) = url {
-        u
-    } else if let Some(base) = base_url {
-        if let Some(ep) = endpoint {
-            format!("{}{}", base.trim_end_matches('/'), ep)
-        } else {
-            base
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let client = build_client(ctx.network, timeout_seconds, connect_timeout_seconds, redirect_policy)?;
+
+    let full_url = resolve_url(url, base_url, endpoint)?;
+
+    let passed = AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() };
+    check_basic_auth_exclusive(&basic_auth, &passed, profile_name.as_deref(), &headers)?;
+    let auth = session::resolve_auth(&ctx, profile_name.as_deref(), passed).await?;
+
+    let body = serde_json::to_vec(&body)
+        .map_err(|e| format!("Failed to serialize request body: {}", e))?;
+    if body.len() as u64 > MAX_REQUEST_BODY_BYTES {
+        return Err(AppError::typed(ErrorKind::RequestTooLarge {
+            limit_bytes: MAX_REQUEST_BODY_BYTES,
+            size_bytes: body.len() as u64,
+        }));
+    }
+
+    let idempotency_key = idempotency_key.filter(|k| !k.trim().is_empty());
+    let mut headers = headers;
+    if let Some(ref key) = idempotency_key {
+        // A caller-supplied header wins, whatever its case - as in build_headers
+        let headers = headers.get_or_insert_with(HashMap::new);
+        if !headers.keys().any(|name| name.eq_ignore_ascii_case(IDEMPOTENCY_HEADER)) {
+            headers.insert(IDEMPOTENCY_HEADER.to_string(), key.clone());
         }
-    } else if let Some(ep) = endpoint {
-        ep
-    } else {
-        return Err("No URL provided. Pass 'url' or 'baseUrl' (optionally with 'endpoint')".to_string());
+    }
+
+    // Off unless asked for: not every Nimbus endpoint accepts a compressed request body
+    let compressed = match compress_body {
+        Some(true) if body.len() > GZIP_BODY_THRESHOLD_BYTES => Some(gzip_bytes(&body)?),
+        _ => None,
     };
 
-    let req_headers = build_headers(headers, user_id, auth_token, app_token, username)?;
+    let (client, full_url, basic_auth) = (&client, &full_url, &basic_auth);
+    let post = |body: Vec<u8>, gzip: bool| {
+        send_with_refresh(&ctx, profile_name.as_deref(), auth.clone(), headers.clone(), "POST request", move |mut req_headers| {
+            // Same as .json(): only fill in Content-Type if the caller didn't supply one
+            req_headers
+                .entry(reqwest::header::CONTENT_TYPE)
+                .or_insert(reqwest::header::HeaderValue::from_static("application/json"));
+            if gzip {
+                req_headers.insert(
+                    reqwest::header::CONTENT_ENCODING,
+                    reqwest::header::HeaderValue::from_static("gzip"),
+                );
+            }
+            with_basic_auth(client.post(full_url).headers(req_headers).body(body.clone()), basic_auth)
+        })
+    };
 
-    let response = client
-        .post(&full_url)
-        .headers(req_headers)
-        .json(&body)
-        .send()
-        .await
-        .map_err(|e| format!("POST request failed: {}", e))?;
+    let send = || async {
+        let response = match compressed.clone() {
+            Some(gzipped) => {
+                let response = post(gzipped, true).await?;
+                // A server that can't decode the body says so with 415; send it again as plain JSON
+                if response.status() == StatusCode::UNSUPPORTED_MEDIA_TYPE {
+                    post(body.clone(), false).await?
+                } else {
+                    response
+                }
+            }
+            None => post(body.clone(), false).await?,
+        };
+
+        response_to_http_response(response, max_response_bytes).await
+    };
+
+    match idempotency_key {
+        Some(key) => {
+            let scope = idempotency_scope(profile_name.as_deref(), full_url, &body, &key);
+            idempotency.run(&scope, send).await
+        }
+        None => send().await,
+    }
+}
+
+/// Gzip a request body for `Content-Encoding: gzip`
+fn gzip_bytes(body: &[u8]) -> Result<Vec<u8>, String> {
+    let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::default());
+    encoder.write_all(body)
+        .and_then(|_| encoder.finish())
+        .map_err(|e| format!("Failed to compress request body: {}", e))
+}
+
+/// Execute REST POST with an `application/x-www-form-urlencoded` body (OAuth/SSO style token exchanges)
+#[tauri::command]
+pub async fn execute_rest_post_form(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    url: Option<String>,
+    base_url: Option<String>,
+    endpoint: Option<String>,
+    form: HashMap<String, String>,
+    headers: Option<HashMap<String, String>>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    max_response_bytes: Option<u64>,
+    redirect_policy: Option<RedirectPolicy>,
+    deadline_id: Option<String>,
+) -> Result<HttpResponse, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let client = build_client(ctx.network, timeout_seconds, connect_timeout_seconds, redirect_policy)?;
+
+    let full_url = resolve_url(url, base_url, endpoint)?;
+
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    let response = send_with_refresh(&ctx, profile_name.as_deref(), auth, headers, "POST request", |req_headers| {
+        client.post(&full_url).headers(req_headers).form(&form)
+    }).await?;
+
+    response_to_http_response(response, max_response_bytes).await
+}
+
+/// Check a Content-Type such as `application/xml` or `text/csv; charset=utf-8`
+fn parse_content_type(content_type: &str) -> Result<reqwest::header::HeaderValue, String> {
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    let valid_essence = essence.split_once('/').is_some_and(|(kind, subtype)| {
+        let token = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c));
+        token(kind) && token(subtype)
+    });
+    if !valid_essence {
+        return Err(format!("Invalid content type '{}': expected type/subtype, e.g. application/xml", content_type));
+    }
+    reqwest::header::HeaderValue::from_str(content_type.trim())
+        .map_err(|e| format!("Invalid content type '{}': {}", content_type, e))
+}
+
+/// Execute REST POST with a raw body (base64-encoded by the caller) sent as `content_type`,
+/// e.g. a file or XML payload for a Nimbus import endpoint. `content_type` replaces any
+/// Content-Type in `headers`; the body is sent as-is, with no JSON handling.
+#[tauri::command]
+pub async fn execute_rest_post_raw(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    url: Option<String>,
+    base_url: Option<String>,
+    endpoint: Option<String>,
+    body_base64: String,
+    content_type: String,
+    headers: Option<HashMap<String, String>>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    max_response_bytes: Option<u64>,
+    redirect_policy: Option<RedirectPolicy>,
+    deadline_id: Option<String>,
+) -> Result<HttpResponse, AppError> {
+    let content_type = parse_content_type(&content_type)?;
+    let body = BASE64.decode(body_base64.trim())
+        .map_err(|e| format!("Request body is not valid base64: {}", e))?;
+    if body.len() as u64 > MAX_REQUEST_BODY_BYTES {
+        return Err(AppError::typed(ErrorKind::RequestTooLarge {
+            limit_bytes: MAX_REQUEST_BODY_BYTES,
+            size_bytes: body.len() as u64,
+        }));
+    }
+
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let client = build_client(ctx.network, timeout_seconds, connect_timeout_seconds, redirect_policy)?;
+
+    let full_url = resolve_url(url, base_url, endpoint)?;
+
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    let response = send_with_refresh(&ctx, profile_name.as_deref(), auth, headers, "POST request", |mut req_headers| {
+        req_headers.insert(reqwest::header::CONTENT_TYPE, content_type.clone());
+        client.post(&full_url).headers(req_headers).body(body.clone())
+    }).await?;
 
-    response_to_http_response(response).await
+    response_to_http_response(response, max_response_bytes).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answer one request on a local port with `status` and an empty body; the handle yields the request head as received
+    async fn serve_once(status: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/RESTApi/Location", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut head = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !head.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                head.extend_from_slice(&buf[..n]);
+            }
+            let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&head).to_ascii_lowercase()
+        });
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn rest_get_sends_no_content_type() {
+        let (store, log, circuits, network, locks) = (
+            KeyStore::in_memory(),
+            RequestLog::default(),
+            CircuitBreakers::default(),
+            NetworkConfig::default(),
+            ProfileLocks::default(),
+        );
+        let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+        let client = build_client(&network, Some(5), None, None).unwrap();
+        let auth = AuthFields {
+            app_token: Some("token".to_string()),
+            username: Some("reports".to_string()),
+            default_headers: HashMap::from([("X-Tenant".to_string(), "monash".to_string())]),
+            ..Default::default()
+        };
+
+        let (url, server) = serve_once("200 OK").await;
+        let response = send_with_refresh(&ctx, None, auth, None, "GET request", |headers| {
+            rest_get_request(&client, &url, headers, &None)
+        }).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let head = server.await.unwrap();
+        assert!(head.starts_with("get /restapi/location "), "{}", head);
+        assert!(head.contains("\r\naccept: application/json\r\n"), "{}", head);
+        assert!(head.contains("\r\nx-tenant: monash\r\n"), "{}", head);
+        assert!(!head.contains("\r\ncontent-type:"), "{}", head);
+    }
+
+    #[tokio::test]
+    async fn a_failed_refresh_still_reports_the_401() {
+        let (store, log, circuits, network, locks) = (
+            KeyStore::in_memory(),
+            RequestLog::default(),
+            CircuitBreakers::default(),
+            NetworkConfig::default(),
+            ProfileLocks::default(),
+        );
+        let (url, server) = serve_once("401 Unauthorized").await;
+        let base_url = url.trim_end_matches("/RESTApi/Location");
+        store.set("profile:Prod", &format!(
+            r#"{{"base_url":"{}","auth_mode":"credential","user_id":7,"auth_token":"stale"}}"#, base_url
+        )).unwrap();
+        // No password kept, so the refresh can't sign back in
+        store.set("login:Prod", r#"{"username":"u","password":""}"#).unwrap();
+
+        let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+        let client = build_client(&network, Some(5), None, None).unwrap();
+        let auth = session::resolve_auth(&ctx, Some("Prod"), AuthFields::default()).await.unwrap();
+        let error = send_with_refresh(&ctx, Some("Prod"), auth, None, "GET request", |headers| {
+            rest_get_request(&client, &url, headers, &None)
+        }).await.unwrap_err();
+        server.await.unwrap();
+
+        match error {
+            AppError::Typed { kind: ErrorKind::Unauthorized { status, token_likely_expired, .. }, message } => {
+                assert_eq!((status, token_likely_expired), (401, true));
+                assert!(message.contains("No stored password"), "{}", message);
+            }
+            other => panic!("expected Unauthorized, got {:?}", other),
+        }
+    }
+
+    fn header<'h>(headers: &'h reqwest::header::HeaderMap, name: &str) -> Vec<&'h str> {
+        headers.get_all(name).iter().map(|v| v.to_str().unwrap()).collect()
+    }
+
+    fn credential_auth() -> AuthFields {
+        AuthFields { user_id: Some(7), auth_token: Some("session".to_string()), ..Default::default() }
+    }
+
+    #[test]
+    fn headers_are_managed_then_profile_defaults_then_caller() {
+        let auth = AuthFields {
+            default_headers: HashMap::from([
+                ("X-Tenant".to_string(), "monash".to_string()),
+                ("X-Region".to_string(), "au".to_string()),
+            ]),
+            ..credential_auth()
+        };
+        let custom = HashMap::from([("X-Region".to_string(), "nz".to_string())]);
+        let headers = build_headers(Some(custom), &auth).unwrap();
+
+        assert_eq!(header(&headers, "Accept"), ["application/json"]);
+        assert_eq!(header(&headers, "UserID"), ["7"]);
+        assert_eq!(header(&headers, "Authorization"), ["Bearer session"]);
+        assert_eq!(header(&headers, "AuthenticationToken"), ["session"]);
+        assert_eq!(header(&headers, "X-Tenant"), ["monash"]);
+        assert_eq!(header(&headers, "X-Region"), ["nz"]);
+    }
+
+    #[test]
+    fn profile_defaults_override_managed_headers() {
+        let auth = AuthFields {
+            default_headers: HashMap::from([("accept".to_string(), "application/json;odata.metadata=none".to_string())]),
+            ..credential_auth()
+        };
+        let headers = build_headers(None, &auth).unwrap();
+        assert_eq!(header(&headers, "Accept"), ["application/json;odata.metadata=none"]);
+    }
+
+    #[test]
+    fn caller_overrides_match_names_case_insensitively() {
+        let auth = AuthFields {
+            default_headers: HashMap::from([("X-TENANT".to_string(), "monash".to_string())]),
+            ..credential_auth()
+        };
+        let custom = HashMap::from([
+            ("authorization".to_string(), "Bearer override".to_string()),
+            ("USERID".to_string(), "9".to_string()),
+            ("x-tenant".to_string(), "caller".to_string()),
+        ]);
+        let headers = build_headers(Some(custom), &auth).unwrap();
+
+        // Replaced, not appended alongside the managed or profile value
+        assert_eq!(header(&headers, "Authorization"), ["Bearer override"]);
+        assert_eq!(header(&headers, "UserID"), ["9"]);
+        assert_eq!(header(&headers, "X-Tenant"), ["caller"]);
+        assert_eq!(header(&headers, "AuthenticationToken"), ["session"]);
+    }
+
+    #[test]
+    fn base_urls_are_normalized() {
+        assert_eq!(normalize_base_url(" nimbus.example.edu/ ").unwrap(), "https://nimbus.example.edu");
+        assert_eq!(normalize_base_url("http://nimbus.example.edu:8080//").unwrap(), "http://nimbus.example.edu:8080");
+        assert_eq!(normalize_base_url("https://nimbus.example.edu/ODataApi/").unwrap(), "https://nimbus.example.edu/ODataApi");
+        assert!(normalize_base_url("").is_err());
+        assert!(normalize_base_url("ftp://nimbus.example.edu").is_err());
+        assert!(normalize_base_url("https://").is_err());
+    }
+
+    #[test]
+    fn odata_endpoints_are_normalized_to_the_service_root() {
+        let network = NetworkConfig::default();
+        let root = |base_url: &str| odata_service_root(&network, base_url).unwrap();
+        assert_eq!(root("nimbus.example.edu"), "https://nimbus.example.edu/CoreApi/OData");
+        assert_eq!(root("https://nimbus.example.edu/CoreApi/OData/"), "https://nimbus.example.edu/CoreApi/OData");
+        // Legacy /ODataApi doesn't return adhoc fields, so it's moved to /CoreApi/OData unless detected
+        assert_eq!(root("https://nimbus.example.edu/ODataApi"), "https://nimbus.example.edu/CoreApi/OData");
+        assert_eq!(root("https://nimbus.example.edu/odata/"), "https://nimbus.example.edu/odata");
+    }
+
+    const SERVICE_ROOT: &str = "https://nimbus.example.edu/CoreApi/OData";
+
+    #[test]
+    fn query_options_come_in_a_fixed_order() {
+        let params = ODataQueryParams {
+            format: Some("json".to_string()),
+            skiptoken: Some("a b".to_string()),
+            count: Some(true),
+            orderby: Some("Name desc".to_string()),
+            expand: Some("Department".to_string()),
+            select: Some("Id,Name".to_string()),
+            filter: Some("Active eq true".to_string()),
+            skip: Some(200),
+            top: Some(100),
+            ..Default::default()
+        };
+        assert_eq!(
+            odata_query_url(SERVICE_ROOT, "User", &params).unwrap(),
+            "https://nimbus.example.edu/CoreApi/OData/User?$top=100&$skip=200&$filter=Active eq true\
+             &$select=Id,Name&$expand=Department&$orderby=Name desc&$count=true&$skiptoken=a%20b&$format=json"
+        );
+    }
+
+    #[test]
+    fn empty_query_options_are_left_out() {
+        let params = ODataQueryParams {
+            filter: Some(String::new()),
+            select: Some(String::new()),
+            count: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(odata_query_url(SERVICE_ROOT, "User", &params).unwrap(), "https://nimbus.example.edu/CoreApi/OData/User");
+    }
+
+    #[test]
+    fn expand_specs_replace_a_raw_expand() {
+        let params = ODataQueryParams {
+            expand: Some("Roles".to_string()),
+            expand_specs: Some(vec![ExpandSpec {
+                path: "Department".to_string(),
+                select: Some("Id".to_string()),
+                filter: None,
+                top: None,
+                expand: Vec::new(),
+            }]),
+            ..Default::default()
+        };
+        assert_eq!(
+            odata_query_url(SERVICE_ROOT, "User", &params).unwrap(),
+            "https://nimbus.example.edu/CoreApi/OData/User?$expand=Department($select=Id)"
+        );
+    }
+
+    #[test]
+    fn compute_comes_before_the_select_that_names_its_alias() {
+        let params = ODataQueryParams {
+            compute: Some("Hours mul Rate as Cost".to_string()),
+            select: Some("Id,Cost".to_string()),
+            orderby: Some("Cost desc".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            odata_query_url(SERVICE_ROOT, "Shift", &params).unwrap(),
+            "https://nimbus.example.edu/CoreApi/OData/Shift?$compute=Hours mul Rate as Cost&$select=Id,Cost&$orderby=Cost desc"
+        );
+    }
+
+    #[test]
+    fn malformed_orderby_is_rejected() {
+        let params = ODataQueryParams { orderby: Some("Name descending".to_string()), ..Default::default() };
+        assert!(odata_query_url(SERVICE_ROOT, "User", &params).is_err());
+    }
+
+    #[test]
+    fn no_content_is_an_empty_result() {
+        let empty = serde_json::json!({ "value": [] });
+        assert_eq!(parse_odata_body(StatusCode::NO_CONTENT, None, "").unwrap(), empty);
+        // A 204 is empty whatever the server put in the body
+        assert_eq!(parse_odata_body(StatusCode::NO_CONTENT, Some("application/json"), "garbage").unwrap(), empty);
+    }
+
+    #[test]
+    fn empty_and_whitespace_bodies_are_an_empty_result() {
+        let empty = serde_json::json!({ "value": [] });
+        assert_eq!(parse_odata_body(StatusCode::OK, Some("application/json"), "").unwrap(), empty);
+        assert_eq!(parse_odata_body(StatusCode::OK, Some("application/json"), " \r\n\t ").unwrap(), empty);
+    }
+
+    #[test]
+    fn json_bodies_are_parsed_and_bad_ones_rejected() {
+        let body = r#"{"value":[{"Id":1}]}"#;
+        assert_eq!(parse_odata_body(StatusCode::OK, Some("application/json"), body).unwrap()["value"][0]["Id"], 1);
+        assert!(parse_odata_body(StatusCode::OK, Some("application/json"), "{not json").is_err());
+    }
 }
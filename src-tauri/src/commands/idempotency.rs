@@ -0,0 +1,135 @@
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as AsyncMutex;
+
+use crate::commands::retry::{classify_status, RetryClass};
+use crate::error::AppError;
+use crate::types::HttpResponse;
+
+// How long a completed POST's response is replayed for a repeated idempotency key
+const COMPLETED_TTL: Duration = Duration::from_secs(10 * 60);
+
+struct Entry {
+    // Holds the remembered response; locked while a request with this key is in flight,
+    // so concurrent duplicates wait for it
+    response: Arc<AsyncMutex<Option<HttpResponse>>>,
+    created: Instant,
+}
+
+/// Idempotency keys seen by `execute_rest_post` (managed state).
+/// Entries are scoped by profile, URL and body as well as the key, so reusing a key for a
+/// different request sends it rather than replaying an unrelated response.
+/// Only final responses are remembered: after a failure with no response (e.g. a timeout) or a
+/// retryable status (408, 429, 5xx - see `classify_status`), the next call with the same key
+/// sends again, which is safe because the server sees the same key.
+#[derive(Default)]
+pub struct IdempotencyCache {
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+/// Cache key for one POST: the idempotency key plus a hash of who sent what where
+pub(crate) fn idempotency_scope(profile_name: Option<&str>, url: &str, body: &[u8], key: &str) -> String {
+    let mut hasher = Sha256::new();
+    for part in [profile_name.unwrap_or_default().as_bytes(), url.as_bytes(), body] {
+        // Length-prefixed so the parts can't run into each other
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    let digest: String = hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}:{}", key, digest)
+}
+
+impl IdempotencyCache {
+    fn slot(&self, scope: &str) -> Arc<AsyncMutex<Option<HttpResponse>>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        // Drop expired keys, but never one that a request is still waiting on
+        entries.retain(|_, entry| {
+            entry.created.elapsed() < COMPLETED_TTL || Arc::strong_count(&entry.response) > 1
+        });
+
+        entries
+            .entry(scope.to_string())
+            .or_insert_with(|| Entry { response: Arc::new(AsyncMutex::new(None)), created: Instant::now() })
+            .response
+            .clone()
+    }
+
+    /// Run `send` unless a request with this scope (see `idempotency_scope`) already produced a
+    /// final response, in which case that response is returned (waiting for it if the first
+    /// request is still in flight)
+    pub(crate) async fn run<F, Fut>(&self, scope: &str, send: F) -> Result<HttpResponse, AppError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<HttpResponse, AppError>>,
+    {
+        let slot = self.slot(scope);
+        let mut remembered = slot.lock().await;
+        if let Some(ref response) = *remembered {
+            return Ok(response.clone());
+        }
+        let response = send().await?;
+        let final_response = StatusCode::from_u16(response.status)
+            .is_ok_and(|status| classify_status(status) == RetryClass::Terminal);
+        if final_response {
+            *remembered = Some(response.clone());
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn response(status: u16) -> HttpResponse {
+        HttpResponse { status, body: format!("status {}", status), headers: HashMap::new(), final_url: String::new() }
+    }
+
+    #[tokio::test]
+    async fn final_responses_are_replayed() {
+        let (cache, sent) = (IdempotencyCache::default(), AtomicU32::new(0));
+        let scope = idempotency_scope(Some("Prod"), "https://nimbus.example.edu/RESTApi/Shift", b"{}", "k1");
+        for _ in 0..3 {
+            let replayed = cache.run(&scope, || async {
+                sent.fetch_add(1, Ordering::SeqCst);
+                Ok(response(201))
+            }).await.unwrap();
+            assert_eq!(replayed.status, 201);
+        }
+        assert_eq!(sent.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retryable_responses_are_sent_again() {
+        let (cache, sent) = (IdempotencyCache::default(), AtomicU32::new(0));
+        let scope = idempotency_scope(None, "https://nimbus.example.edu/RESTApi/Shift", b"{}", "k1");
+        for status in [503, 429, 408, 201] {
+            let result = cache.run(&scope, || async {
+                sent.fetch_add(1, Ordering::SeqCst);
+                Ok(response(status))
+            }).await.unwrap();
+            assert_eq!(result.status, status);
+        }
+        assert_eq!(sent.load(Ordering::SeqCst), 4);
+        // Now final, so replayed
+        let replayed = cache.run(&scope, || async { Ok(response(500)) }).await.unwrap();
+        assert_eq!(replayed.status, 201);
+    }
+
+    #[test]
+    fn scope_covers_profile_url_body_and_key() {
+        let url = "https://nimbus.example.edu/RESTApi/Shift";
+        let base = idempotency_scope(Some("Prod"), url, b"{\"a\":1}", "k1");
+        assert_eq!(base, idempotency_scope(Some("Prod"), url, b"{\"a\":1}", "k1"));
+        assert_ne!(base, idempotency_scope(Some("Test"), url, b"{\"a\":1}", "k1"));
+        assert_ne!(base, idempotency_scope(Some("Prod"), "https://nimbus.example.edu/RESTApi/Leave", b"{\"a\":1}", "k1"));
+        assert_ne!(base, idempotency_scope(Some("Prod"), url, b"{\"a\":2}", "k1"));
+        assert_ne!(base, idempotency_scope(Some("Prod"), url, b"{\"a\":1}", "k2"));
+    }
+}
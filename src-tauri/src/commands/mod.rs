@@ -1,3 +1,25 @@
+pub mod capture;
+pub mod circuit;
+pub mod config;
 pub mod credentials;
+pub mod dedup;
+pub mod diagnostics;
+pub mod diff;
+pub mod export;
+pub mod filter;
+pub mod flatten;
 pub mod http;
+pub mod idempotency;
+pub mod network;
+pub mod odata;
+pub mod polling;
+pub mod profiles;
+pub mod queries;
+pub mod retry;
+pub mod schema;
+pub mod session;
+pub mod snapshots;
+pub mod stream;
+pub mod sync;
 pub mod version;
+pub mod xml;
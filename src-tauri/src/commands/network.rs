@@ -0,0 +1,849 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::State;
+use tokio::sync::{Notify, Semaphore};
+
+use crate::commands::circuit::CircuitBreakers;
+use crate::commands::diagnostics::RequestLog;
+use crate::commands::http::{
+    build_client, build_headers, client_builder, new_client, normalize_base_url, send_logged, send_with_refresh,
+    AuthFields, AuthScheme, ClientKey, RedirectPolicy, RequestContext,
+};
+use crate::commands::odata::{odata_service_root, result_rows, ODataEndpointCache};
+use crate::commands::profiles::ProfileLocks;
+use crate::commands::session;
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+
+/// Client settings that apply to every request, changeable at runtime
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkSettings {
+    // Replaces the default `MonashNimbusReports/<version> (<os>; <arch>)` user agent
+    pub user_agent: Option<String>,
+    // Speak HTTP/2 from the first byte, without negotiating. Off by default: some Nimbus
+    // front-ends only speak HTTP/1.1 and fail every request when this is on
+    #[serde(default)]
+    pub http2_prior_knowledge: bool,
+    // Idle pooled connections kept per host; None keeps reqwest's default (unbounded)
+    #[serde(default)]
+    pub pool_max_idle_per_host: Option<usize>,
+    // How long an idle pooled connection is kept; None keeps reqwest's default (90s)
+    #[serde(default)]
+    pub pool_idle_timeout_secs: Option<u64>,
+    // Oldest TLS version accepted ("1.0" to "1.3"); None means TLS 1.2
+    #[serde(default)]
+    pub min_tls_version: Option<String>,
+    // Accept-Language sent with every request (e.g. "fr-FR, en;q=0.8"); None uses the system locale.
+    // Only servers that honor the header localize anything
+    #[serde(default)]
+    pub accept_language: Option<String>,
+    // Total and connect timeouts for requests that don't pass their own; None means 30s and 10s
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub connect_timeout_seconds: Option<u64>,
+}
+
+/// Cancellation tokens for every request currently waiting on the server
+#[derive(Default)]
+pub(crate) struct InFlightRequests {
+    next_id: AtomicU64,
+    tokens: Mutex<HashMap<u64, Arc<Notify>>>,
+}
+
+impl InFlightRequests {
+    /// Register a request; it stays cancellable until the returned guard is dropped
+    pub(crate) fn register(&self) -> InFlightGuard<'_> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = Arc::new(Notify::new());
+        self.tokens.lock().unwrap_or_else(|e| e.into_inner()).insert(id, token.clone());
+        InFlightGuard { registry: self, id, token }
+    }
+
+    fn cancel_all(&self) -> u32 {
+        let tokens: Vec<Arc<Notify>> = self.tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain()
+            .map(|(_, token)| token)
+            .collect();
+        // notify_one stores a permit, so a request that hasn't started waiting yet still sees it
+        tokens.iter().for_each(|token| token.notify_one());
+        tokens.len() as u32
+    }
+}
+
+pub(crate) struct InFlightGuard<'a> {
+    registry: &'a InFlightRequests,
+    id: u64,
+    token: Arc<Notify>,
+}
+
+impl InFlightGuard<'_> {
+    /// Resolves once `cancel_all_requests` has been called for this request
+    pub(crate) async fn cancelled(&self) {
+        self.token.notified().await
+    }
+}
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.registry.tokens.lock().unwrap_or_else(|e| e.into_inner()).remove(&self.id);
+    }
+}
+
+// Requests allowed on the wire at once until set_max_concurrency says otherwise
+const DEFAULT_MAX_CONCURRENCY: usize = 8;
+
+/// Limits how many requests are sent to Nimbus at once; the rest queue for a permit
+pub(crate) struct RequestScheduler {
+    permits: Arc<Semaphore>,
+    limit: Mutex<usize>,
+    active: AtomicUsize,
+}
+
+impl Default for RequestScheduler {
+    fn default() -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENCY)),
+            limit: Mutex::new(DEFAULT_MAX_CONCURRENCY),
+            active: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl RequestScheduler {
+    /// Wait for a free slot; the request counts as in flight until the permit is dropped
+    pub(crate) async fn acquire(&self) -> SchedulerPermit<'_> {
+        // The semaphore is never closed, so acquire can't fail
+        let permit = self.permits.acquire().await.expect("request scheduler semaphore closed");
+        self.active.fetch_add(1, Ordering::Relaxed);
+        SchedulerPermit { scheduler: self, _permit: permit }
+    }
+
+    pub(crate) fn set_limit(&self, limit: usize) {
+        let mut current = self.limit.lock().unwrap_or_else(|e| e.into_inner());
+        if limit > *current {
+            self.permits.add_permits(limit - *current);
+        } else if limit < *current {
+            // Take the surplus permits out of circulation; any held by running requests are
+            // retired as those requests finish rather than cutting them off
+            let surplus = (*current - limit) as u32;
+            let permits = self.permits.clone();
+            // Through Tauri's runtime handle, so this also works when called outside a command
+            tauri::async_runtime::spawn(async move {
+                if let Ok(permit) = permits.acquire_many_owned(surplus).await {
+                    permit.forget();
+                }
+            });
+        }
+        *current = limit;
+    }
+
+    pub(crate) fn limit(&self) -> usize {
+        *self.limit.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn active(&self) -> usize {
+        self.active.load(Ordering::Relaxed)
+    }
+}
+
+pub(crate) struct SchedulerPermit<'a> {
+    scheduler: &'a RequestScheduler,
+    _permit: tokio::sync::SemaphorePermit<'a>,
+}
+
+impl Drop for SchedulerPermit<'_> {
+    fn drop(&mut self) {
+        self.scheduler.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// Expired deadlines are forgotten this long after they run out, so late lookups still get a clear error
+const EXPIRED_DEADLINE_RETENTION: Duration = Duration::from_secs(10 * 60);
+
+/// Overall time budgets shared by several requests, keyed by the handle `begin_deadline` returns
+#[derive(Default)]
+pub(crate) struct Deadlines {
+    next_id: AtomicU64,
+    expiries: Mutex<HashMap<String, Instant>>,
+}
+
+/// A resolved deadline: when it runs out, and its handle for error messages
+#[derive(Debug, Clone)]
+pub(crate) struct Deadline {
+    pub id: String,
+    pub expires_at: Instant,
+}
+
+impl Deadline {
+    pub(crate) fn remaining(&self) -> Duration {
+        self.expires_at.saturating_duration_since(Instant::now())
+    }
+}
+
+impl Deadlines {
+    fn begin(&self, budget: Duration) -> String {
+        let now = Instant::now();
+        let id = format!("deadline-{}", self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+        let mut expiries = self.expiries.lock().unwrap_or_else(|e| e.into_inner());
+        expiries.retain(|_, expires_at| now.saturating_duration_since(*expires_at) < EXPIRED_DEADLINE_RETENTION);
+        expiries.insert(id.clone(), now + budget);
+        id
+    }
+
+    /// Look up the deadline a command was given, if any; an unknown handle is an error
+    pub(crate) fn resolve(&self, deadline_id: Option<&str>) -> Result<Option<Deadline>, String> {
+        let Some(id) = deadline_id else {
+            return Ok(None);
+        };
+        match self.expiries.lock().unwrap_or_else(|e| e.into_inner()).get(id) {
+            Some(expires_at) => Ok(Some(Deadline { id: id.to_string(), expires_at: *expires_at })),
+            None => Err(format!("Unknown or expired deadline '{}'", id)),
+        }
+    }
+}
+
+/// The OS locale as a BCP 47 tag (e.g. `en-AU`), detected once when NetworkConfig is created
+pub(crate) struct SystemLocale(Option<String>);
+
+impl Default for SystemLocale {
+    fn default() -> Self {
+        Self(sys_locale::get_locale().and_then(|locale| language_tag(&locale)))
+    }
+}
+
+/// `en_AU.UTF-8` style POSIX locales become `en-AU`; `C`/`POSIX` have no language to send
+fn language_tag(locale: &str) -> Option<String> {
+    let tag = locale.split(['.', '@']).next().unwrap_or_default().trim().replace('_', "-");
+    match tag.as_str() {
+        "" | "C" | "POSIX" => None,
+        _ => Some(tag),
+    }
+}
+
+/// Requests currently on the wire against the configured limit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InflightCount {
+    pub active: u32,
+    pub max_concurrency: u32,
+}
+
+/// Managed holder for the current `NetworkSettings`, the clients built from them, the in-flight
+/// requests, the scheduler that caps how many run at once, any open deadlines and the detected OData endpoints
+#[derive(Default)]
+pub struct NetworkConfig {
+    settings: Mutex<NetworkSettings>,
+    clients: Mutex<HashMap<ClientKey, Client>>,
+    pub(crate) in_flight: InFlightRequests,
+    pub(crate) scheduler: RequestScheduler,
+    pub(crate) deadlines: Deadlines,
+    pub(crate) odata_endpoints: ODataEndpointCache,
+    system_locale: SystemLocale,
+}
+
+impl NetworkConfig {
+    pub(crate) fn settings(&self) -> NetworkSettings {
+        self.settings.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Apply a settings change and drop the cached clients so the next request picks it up
+    pub(crate) fn update(&self, apply: impl FnOnce(&mut NetworkSettings)) -> NetworkSettings {
+        let updated = {
+            let mut settings = self.settings.lock().unwrap_or_else(|e| e.into_inner());
+            apply(&mut settings);
+            settings.clone()
+        };
+        self.reset_clients();
+        updated
+    }
+
+    /// Reuse the client for `key`, building it on first use
+    pub(crate) fn cached_client(
+        &self,
+        key: ClientKey,
+        build: impl FnOnce() -> Result<Client, String>,
+    ) -> Result<Client, String> {
+        if let Some(client) = self.clients.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(client.clone());
+        }
+        // Built outside the lock; if two commands race, the later insert simply wins
+        let client = build()?;
+        self.clients.lock().unwrap_or_else(|e| e.into_inner()).insert(key, client.clone());
+        Ok(client)
+    }
+
+    fn reset_clients(&self) {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// User agent sent to Nimbus: the override if set, otherwise the app version plus OS/arch
+    /// so admins can tell app versions apart in their access logs
+    pub(crate) fn user_agent(&self) -> String {
+        self.settings().user_agent.unwrap_or_else(|| {
+            format!(
+                "MonashNimbusReports/{} ({}; {})",
+                env!("CARGO_PKG_VERSION"),
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            )
+        })
+    }
+
+    /// Accept-Language sent by default: the override if set, otherwise the system locale (if any)
+    pub(crate) fn accept_language(&self) -> Option<String> {
+        self.settings().accept_language.or_else(|| self.system_locale.0.clone())
+    }
+}
+
+#[tauri::command]
+pub fn get_network_settings(network: State<'_, NetworkConfig>) -> NetworkSettings {
+    network.settings()
+}
+
+/// Drop every shared client (closing pooled connections and clearing cookies) so the next
+/// request builds a fresh one from the current settings. Returns the effective settings.
+#[tauri::command]
+pub fn reset_http_client(network: State<'_, NetworkConfig>) -> NetworkSettings {
+    network.reset_clients();
+    network.settings()
+}
+
+/// Abort every request still waiting on Nimbus (e.g. on logout or a profile switch) and return
+/// how many were cancelled. Each fails with a `Cancelled` error; with nothing in flight this returns 0.
+#[tauri::command]
+pub fn cancel_all_requests(network: State<'_, NetworkConfig>) -> Result<u32, String> {
+    Ok(network.in_flight.cancel_all())
+}
+
+/// Cap how many requests go to Nimbus at once (8 by default); beyond that they queue rather than fail.
+/// Lowering the cap doesn't interrupt running requests - it takes effect as they finish.
+#[tauri::command]
+pub async fn set_max_concurrency(network: State<'_, NetworkConfig>, n: u32) -> Result<InflightCount, String> {
+    if n == 0 {
+        return Err("max_concurrency must be at least 1".to_string());
+    }
+    network.scheduler.set_limit(n as usize);
+    Ok(get_inflight_count(network))
+}
+
+#[tauri::command]
+pub fn get_inflight_count(network: State<'_, NetworkConfig>) -> InflightCount {
+    InflightCount {
+        active: network.scheduler.active() as u32,
+        max_concurrency: network.scheduler.limit() as u32,
+    }
+}
+
+/// Start an overall time budget for a multi-step workflow and return its handle. Pass the handle as
+/// `deadline_id` to the HTTP commands: each request then gets at most the budget that's left, and
+/// once it's spent they fail straight away with `DeadlineExceeded`.
+#[tauri::command]
+pub fn begin_deadline(network: State<'_, NetworkConfig>, total_seconds: f64) -> Result<String, String> {
+    let budget = Duration::try_from_secs_f64(total_seconds)
+        .ok()
+        .filter(|budget| !budget.is_zero())
+        .ok_or_else(|| format!("total_seconds must be a positive number, got {}", total_seconds))?;
+    Ok(network.deadlines.begin(budget))
+}
+
+/// Seconds left on a deadline from `begin_deadline` (0 once it has run out)
+#[tauri::command]
+pub fn remaining_budget(network: State<'_, NetworkConfig>, deadline_id: String) -> Result<f64, String> {
+    let deadline = network.deadlines.resolve(Some(&deadline_id))?;
+    Ok(deadline.map_or(0.0, |d| d.remaining().as_secs_f64()))
+}
+
+/// Tune connection reuse for dashboards that issue many parallel requests. Arguments left as `None`
+/// go back to reqwest's defaults. HTTP/2 prior knowledge only helps servers that speak HTTP/2
+/// without negotiation; leave it off for HTTP/1.1-only Nimbus front-ends.
+#[tauri::command]
+pub fn set_connection_tuning(
+    network: State<'_, NetworkConfig>,
+    http2_prior_knowledge: Option<bool>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout_secs: Option<u64>,
+) -> NetworkSettings {
+    network.update(|settings| {
+        settings.http2_prior_knowledge = http2_prior_knowledge.unwrap_or(false);
+        settings.pool_max_idle_per_host = pool_max_idle_per_host;
+        settings.pool_idle_timeout_secs = pool_idle_timeout_secs;
+    })
+}
+
+/// Parse a TLS version as "1.2", "TLS1.2" or "TLSv1.2"
+pub(crate) fn parse_tls_version(version: &str) -> Result<reqwest::tls::Version, String> {
+    let normalized = version.trim().to_ascii_lowercase();
+    let number = normalized
+        .strip_prefix("tlsv")
+        .or_else(|| normalized.strip_prefix("tls"))
+        .unwrap_or(&normalized)
+        .trim();
+    match number {
+        "1.0" | "1" => Ok(reqwest::tls::Version::TLS_1_0),
+        "1.1" => Ok(reqwest::tls::Version::TLS_1_1),
+        "1.2" => Ok(reqwest::tls::Version::TLS_1_2),
+        "1.3" => Ok(reqwest::tls::Version::TLS_1_3),
+        _ => Err(format!("Unknown TLS version '{}' (expected 1.0, 1.1, 1.2 or 1.3)", version)),
+    }
+}
+
+/// Set the oldest TLS version any connection may negotiate; `None` restores the TLS 1.2 default.
+/// A server that can't meet it fails the handshake instead of silently downgrading.
+#[tauri::command]
+pub fn set_min_tls_version(
+    network: State<'_, NetworkConfig>,
+    version: Option<String>,
+) -> Result<NetworkSettings, String> {
+    let version = version.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    if let Some(ref v) = version {
+        parse_tls_version(v)?;
+    }
+    Ok(network.update(|settings| settings.min_tls_version = version))
+}
+
+/// Override the user agent for all later requests; `None` (or blank) restores the default
+#[tauri::command]
+pub fn set_user_agent(network: State<'_, NetworkConfig>, user_agent: Option<String>) -> Result<NetworkSettings, String> {
+    let user_agent = user_agent.map(|ua| ua.trim().to_string()).filter(|ua| !ua.is_empty());
+    if let Some(ref ua) = user_agent {
+        reqwest::header::HeaderValue::from_str(ua)
+            .map_err(|e| format!("Invalid user agent '{}': {}", ua, e))?;
+    }
+    Ok(network.update(|settings| settings.user_agent = user_agent))
+}
+
+/// Override the Accept-Language sent with every request; `None` (or blank) goes back to the
+/// system locale. Individual requests can still send their own (an `Accept-Language` custom header,
+/// or `accept_language` on OData queries). This only changes anything on
+/// servers that honor the header - elsewhere responses stay as they are.
+#[tauri::command]
+pub fn set_accept_language(
+    network: State<'_, NetworkConfig>,
+    accept_language: Option<String>,
+) -> Result<NetworkSettings, String> {
+    let accept_language = accept_language.map(|al| al.trim().to_string()).filter(|al| !al.is_empty());
+    if let Some(ref al) = accept_language {
+        reqwest::header::HeaderValue::from_str(al)
+            .map_err(|e| format!("Invalid Accept-Language '{}': {}", al, e))?;
+    }
+    Ok(network.update(|settings| settings.accept_language = accept_language))
+}
+
+// Rows fetched by benchmark_download unless asked otherwise, and the most it will fetch
+const DEFAULT_BENCHMARK_ROWS: u32 = 500;
+const MAX_BENCHMARK_ROWS: u32 = 10_000;
+
+/// Why a connectivity check couldn't get an HTTP response
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityFailure {
+    Dns,
+    ConnectionRefused,
+    Tls,
+    Timeout,
+    Other,
+}
+
+/// Result of a pre-auth reachability check against a Nimbus host
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectivityResult {
+    pub reachable: bool,
+    // HTTP status of the probe, when the server answered at all
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    // True when an https handshake completed (always false for plain http)
+    pub tls_ok: bool,
+    pub failure: Option<ConnectivityFailure>,
+    pub message: Option<String>,
+}
+
+/// Classify a transport error by walking its source chain (reqwest wraps hyper/io/TLS errors)
+pub(crate) fn classify_error(error: &reqwest::Error) -> ConnectivityFailure {
+    if error.is_timeout() {
+        return ConnectivityFailure::Timeout;
+    }
+
+    let mut chain = String::new();
+    let mut source: Option<&dyn std::error::Error> = Some(error);
+    while let Some(e) = source {
+        chain.push_str(&e.to_string().to_ascii_lowercase());
+        chain.push(' ');
+        source = e.source();
+    }
+
+    if chain.contains("dns error") || chain.contains("failed to lookup address") || chain.contains("no such host") {
+        ConnectivityFailure::Dns
+    } else if chain.contains("connection refused") || chain.contains("actively refused") {
+        ConnectivityFailure::ConnectionRefused
+    } else if chain.contains("certificate") || chain.contains("tls") || chain.contains("ssl") || chain.contains("handshake") {
+        ConnectivityFailure::Tls
+    } else {
+        ConnectivityFailure::Other
+    }
+}
+
+/// Check whether a Nimbus host is reachable before any auth, using a lightweight HEAD request.
+/// Any HTTP response (even 404/405) counts as reachable.
+#[tauri::command]
+pub async fn check_connectivity(
+    network: State<'_, NetworkConfig>,
+    base_url: String,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+) -> Result<ConnectivityResult, String> {
+    probe_connectivity(&network, &base_url, timeout_seconds, connect_timeout_seconds).await
+}
+
+/// The HEAD probe behind `check_connectivity`
+pub(crate) async fn probe_connectivity(
+    network: &NetworkConfig,
+    base_url: &str,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+) -> Result<ConnectivityResult, String> {
+    let base_url = normalize_base_url(base_url)?;
+    // A fresh client, so a pooled connection can't hide DNS or TLS problems
+    let client = new_client(network, ClientKey {
+        timeout_seconds: timeout_seconds.unwrap_or(10),
+        connect_timeout_seconds: connect_timeout_seconds.unwrap_or(10),
+        redirect_policy: RedirectPolicy::None,
+    })?;
+    let is_https = base_url.starts_with("https://");
+
+    let started = Instant::now();
+    let outcome = client.head(&base_url).send().await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    Ok(match outcome {
+        Ok(response) => ConnectivityResult {
+            reachable: true,
+            status: Some(response.status().as_u16()),
+            latency_ms,
+            tls_ok: is_https,
+            failure: None,
+            message: None,
+        },
+        Err(e) => ConnectivityResult {
+            reachable: false,
+            status: None,
+            latency_ms,
+            tls_ok: false,
+            failure: Some(classify_error(&e)),
+            message: Some(e.to_string()),
+        },
+    })
+}
+
+/// Warm up DNS, TCP and TLS to a Nimbus host with a HEAD request through the shared client, so the
+/// first real request reuses the pooled connection. Returns straight away; failures are only logged.
+#[tauri::command]
+pub fn prewarm(network: State<'_, NetworkConfig>, base_url: String) -> Result<(), String> {
+    // Default timeouts, so this warms the same pooled client the query commands use by default
+    let target = normalize_base_url(&base_url)
+        .and_then(|url| build_client(&network, None, None, None).map(|client| (client, url)));
+    let (client, url) = match target {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("Prewarm of {} skipped: {}", base_url, e);
+            return Ok(());
+        }
+    };
+
+    // Sync commands run off the async runtime, so spawn through Tauri's handle to it
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = client.head(&url).send().await {
+            eprintln!("Prewarm of {} failed: {}", url, e);
+        }
+    });
+    Ok(())
+}
+
+/// The certificate a Nimbus host presented, for a "is this the real server" check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsInfo {
+    pub subject: String,
+    pub issuer: String,
+    // RFC 3339, UTC
+    pub not_before: String,
+    pub not_after: String,
+    // DNS names and IP addresses from the subjectAltName extension
+    pub san: Vec<String>,
+    // SHA-256 of the DER certificate, as colon-separated uppercase hex (how browsers show it)
+    pub fingerprint_sha256: String,
+}
+
+fn rfc3339_utc(time: x509_parser::time::ASN1Time) -> String {
+    chrono::DateTime::from_timestamp(time.timestamp(), 0)
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_else(|| time.to_string())
+}
+
+fn certificate_info(der: &[u8]) -> Result<TlsInfo, String> {
+    use x509_parser::extensions::GeneralName;
+
+    let (_, cert) = x509_parser::parse_x509_certificate(der)
+        .map_err(|e| format!("Failed to parse the server certificate: {}", e))?;
+
+    let san = match cert.subject_alternative_name() {
+        Ok(Some(extension)) => extension.value.general_names.iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(dns) => Some(dns.to_string()),
+                GeneralName::IPAddress(bytes) => match bytes.len() {
+                    4 => Some(std::net::Ipv4Addr::from(<[u8; 4]>::try_from(*bytes).ok()?).to_string()),
+                    16 => Some(std::net::Ipv6Addr::from(<[u8; 16]>::try_from(*bytes).ok()?).to_string()),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        Ok(None) => Vec::new(),
+        Err(e) => return Err(format!("Failed to read the certificate's subjectAltName: {}", e)),
+    };
+
+    let fingerprint_sha256 = Sha256::digest(der)
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    Ok(TlsInfo {
+        subject: cert.subject().to_string(),
+        issuer: cert.issuer().to_string(),
+        not_before: rfc3339_utc(cert.validity().not_before),
+        not_after: rfc3339_utc(cert.validity().not_after),
+        san,
+        fingerprint_sha256,
+    })
+}
+
+/// Connect to `base_url` over TLS and return the certificate the server presented (subject, issuer,
+/// validity, SANs, SHA-256 fingerprint). A handshake that fails - untrusted, expired or wrong-host
+/// certificate, or a TLS version below the configured minimum - is an error saying why.
+#[tauri::command]
+pub async fn inspect_tls(network: State<'_, NetworkConfig>, base_url: String) -> Result<TlsInfo, String> {
+    let base_url = normalize_base_url(&base_url)?;
+    if !base_url.starts_with("https://") {
+        return Err(format!("{} doesn't use HTTPS, so there's no certificate to inspect", base_url));
+    }
+
+    // A fresh client: a pooled connection wouldn't hand back the peer certificate again
+    let client = client_builder(&network, ClientKey {
+        timeout_seconds: 15,
+        connect_timeout_seconds: 10,
+        redirect_policy: RedirectPolicy::None,
+    })?
+        .tls_info(true)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let response = client.head(&base_url).send().await.map_err(|e| {
+        let mut chain = e.to_string();
+        let mut source = std::error::Error::source(&e);
+        while let Some(cause) = source {
+            chain.push_str(": ");
+            chain.push_str(&cause.to_string());
+            source = cause.source();
+        }
+        match classify_error(&e) {
+            ConnectivityFailure::Tls => format!("TLS handshake with {} failed: {}", base_url, chain),
+            _ => format!("Could not connect to {}: {}", base_url, chain),
+        }
+    })?;
+
+    let der = response.extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .ok_or_else(|| format!("{} did not present a certificate", base_url))?;
+    certificate_info(der)
+}
+
+/// Where the time went in one request. Each phase is timed separately, so the numbers are estimates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimingBreakdown {
+    pub dns_ms: u64,
+    pub connect_ms: u64,
+    // TLS handshake; None for plain HTTP
+    pub tls_ms: Option<u64>,
+    // From sending the request on an open connection to the response headers: mostly server time
+    pub ttfb_ms: u64,
+    // DNS plus the whole first request, body included
+    pub total_ms: u64,
+    pub status: u16,
+}
+
+fn elapsed_ms(started: Instant) -> u64 {
+    started.elapsed().as_millis() as u64
+}
+
+/// Break one GET of `url` down into DNS, TCP connect, TLS and time to first byte, to tell a slow
+/// network from a slow server. reqwest doesn't expose its phases, so this resolves the host and
+/// opens a TCP connection itself, then sends the request twice on a fresh client pinned to that
+/// address: the first pays connect + TLS + server time, the second reuses the connection and pays
+/// only server time; TLS is what's left. If the server won't keep the connection open, the second
+/// request reconnects and `ttfb_ms` comes out high. With `profile_name` the profile's auth is sent,
+/// so `url` must then be on the profile's own server. Both requests are logged like any other.
+#[tauri::command]
+pub async fn timed_request(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    url: String,
+    profile_name: Option<String>,
+    timeout_seconds: Option<u64>,
+) -> Result<TimingBreakdown, AppError> {
+    let parsed = url::Url::parse(url.trim()).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+    let host = parsed.host_str()
+        .ok_or_else(|| format!("URL '{}' has no host", url))?
+        .trim_matches(|c| c == '[' || c == ']')
+        .to_string();
+    let port = parsed.port_or_known_default()
+        .ok_or_else(|| format!("URL '{}' has no port", url))?;
+    let is_https = parsed.scheme() == "https";
+    let timeout_seconds = timeout_seconds.unwrap_or(30);
+    let timeout = Duration::from_secs(timeout_seconds);
+
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    let headers = match profile_name {
+        Some(ref name) => {
+            // Never hand the profile's session to another server
+            let base_url = normalize_base_url(&session::load_credentials(&store, name)?.base_url)?;
+            let profile_origin = url::Url::parse(&base_url)
+                .map_err(|e| format!("Invalid base URL '{}': {}", base_url, e))?
+                .origin();
+            if parsed.origin() != profile_origin {
+                return Err(format!("'{}' is not on profile '{}' ({}), so its auth can't be sent there", url, name, base_url).into());
+            }
+            let auth = session::resolve_auth(&ctx, Some(name), AuthFields::default()).await?;
+            build_headers(None, &auth)?
+        }
+        None => reqwest::header::HeaderMap::new(),
+    };
+
+    let started = Instant::now();
+    let addr = tokio::time::timeout(timeout, tokio::net::lookup_host((host.as_str(), port)))
+        .await
+        .map_err(|_| format!("DNS lookup of {} timed out", host))?
+        .map_err(|e| format!("DNS lookup of {} failed: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("{} did not resolve to any address", host))?;
+    let dns_ms = elapsed_ms(started);
+
+    let started = Instant::now();
+    tokio::time::timeout(timeout, tokio::net::TcpStream::connect(addr))
+        .await
+        .map_err(|_| format!("Connecting to {} timed out", addr))?
+        .map_err(|e| format!("Connecting to {} failed: {}", addr, e))?;
+    let connect_ms = elapsed_ms(started);
+
+    let client = client_builder(&network, ClientKey {
+        timeout_seconds,
+        connect_timeout_seconds: timeout_seconds,
+        redirect_policy: RedirectPolicy::None,
+    })?
+        .resolve(&host, addr)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let started = Instant::now();
+    let response = send_logged(&ctx, client.get(parsed.clone()).headers(headers.clone()), "Timed request").await?;
+    let cold_ms = elapsed_ms(started);
+    let status = response.status().as_u16();
+    // Read to the end so the connection goes back to the pool for the second request
+    response.bytes().await.map_err(|e| format!("Failed to read response body: {}", e))?;
+    let total_ms = dns_ms + elapsed_ms(started);
+
+    let started = Instant::now();
+    send_logged(&ctx, client.get(parsed).headers(headers), "Second timed request").await?;
+    let ttfb_ms = elapsed_ms(started);
+
+    let handshake_ms = cold_ms.saturating_sub(ttfb_ms);
+    let tls_ms = is_https.then(|| handshake_ms.saturating_sub(connect_ms));
+
+    Ok(TimingBreakdown { dns_ms, connect_ms, tls_ms, ttfb_ms, total_ms, status })
+}
+
+/// Measured throughput of one OData read
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchResult {
+    // Raw body bytes received (after any transfer decompression)
+    pub bytes: u64,
+    pub duration_ms: u64,
+    pub mbps: f64,
+    // Rows actually returned, which can be fewer than requested
+    pub rows: u64,
+}
+
+/// Time a read of `rows` rows from `entity` (500 by default, at most 10,000) and report the effective
+/// download rate, so a slow VPN link shows up as a number. Read-only: a single `$top` GET.
+#[tauri::command]
+pub async fn benchmark_download(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    base_url: String,
+    entity: String,
+    rows: Option<u32>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+) -> Result<BenchResult, AppError> {
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    let rows = rows.unwrap_or(DEFAULT_BENCHMARK_ROWS).clamp(1, MAX_BENCHMARK_ROWS);
+    let client = build_client(ctx.network, timeout_seconds.or(Some(120)), None, None)?;
+    let url = format!("{}/{}?$top={}", odata_service_root(ctx.network, &base_url)?, entity, rows);
+
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    let started = Instant::now();
+    let mut response = send_with_refresh(&ctx, profile_name.as_deref(), auth, None, "Download benchmark", |headers| {
+        client.get(&url).headers(headers)
+    }).await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Download benchmark failed with status {}", status.as_u16()).into());
+    }
+
+    let mut body: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read response body: {}", e))?
+    {
+        body.extend_from_slice(&chunk);
+    }
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let bytes = body.len() as u64;
+    // Guard against a sub-millisecond read on localhost
+    let seconds = duration_ms.max(1) as f64 / 1000.0;
+    let rows = serde_json::from_slice(&body)
+        .map(|json| result_rows(&json).len() as u64)
+        .unwrap_or(0);
+
+    Ok(BenchResult {
+        bytes,
+        duration_ms,
+        mbps: (bytes as f64 * 8.0) / seconds / 1_000_000.0,
+        rows,
+    })
+}
@@ -0,0 +1,1354 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::State;
+
+use crate::commands::circuit::CircuitBreakers;
+use crate::commands::diagnostics::{QueryMetrics, RequestLog};
+use crate::commands::http::{
+    build_client, normalize_base_url, read_body_limited, run_odata_query, send_with_refresh, AuthFields,
+    AuthScheme, ODataQueryParams, RequestContext,
+};
+use crate::commands::network::{probe_connectivity, NetworkConfig};
+use crate::commands::profiles::ProfileLocks;
+use crate::commands::session;
+use crate::commands::xml::{
+    csdl_entity_properties, csdl_service_info, looks_like_xml, service_document_sets, CsdlServiceInfo,
+};
+use crate::error::{AppError, ErrorKind};
+use crate::keystore::KeyStore;
+
+/// Resolve the OData service root for a Nimbus base URL
+pub(crate) fn odata_base_url(base_url: &str) -> String {
+    // Use /CoreApi/OData/ which returns adhoc fields with $select
+    // Legacy /ODataApi/ does NOT return adhoc fields even with $select
+    if base_url.ends_with("/CoreApi/OData") || base_url.ends_with("/CoreApi/OData/") {
+        base_url.trim_end_matches('/').to_string()
+    } else if base_url.ends_with("/ODataApi") || base_url.ends_with("/ODataApi/") {
+        // Convert legacy endpoint to CoreApi
+        base_url.replace("/ODataApi", "/CoreApi/OData").trim_end_matches('/').to_string()
+    } else if base_url.ends_with("/odata") || base_url.ends_with("/odata/") {
+        base_url.trim_end_matches('/').to_string()
+    } else {
+        format!("{}/CoreApi/OData", base_url.trim_end_matches('/'))
+    }
+}
+
+/// One `$expand` navigation path with its nested query options,
+/// e.g. `Transactions($select=Amount;$filter=Amount gt 0;$top=5)`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpandSpec {
+    pub path: String,
+    #[serde(default)]
+    pub select: Option<String>,
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub top: Option<i32>,
+    // Nested expands on the related entity (multi-level expand)
+    #[serde(default)]
+    pub expand: Vec<ExpandSpec>,
+}
+
+impl ExpandSpec {
+    fn to_odata(&self) -> String {
+        let mut options: Vec<String> = Vec::new();
+
+        if let Some(ref s) = self.select {
+            if !s.is_empty() {
+                options.push(format!("$select={}", s));
+            }
+        }
+        if let Some(ref f) = self.filter {
+            if !f.is_empty() {
+                options.push(format!("$filter={}", f));
+            }
+        }
+        if let Some(top) = self.top {
+            options.push(format!("$top={}", top));
+        }
+        if !self.expand.is_empty() {
+            options.push(format!("$expand={}", expand_to_odata(&self.expand)));
+        }
+
+        // Nested options are separated by ';' inside the parentheses, per OData v4
+        if options.is_empty() {
+            self.path.clone()
+        } else {
+            format!("{}({})", self.path, options.join(";"))
+        }
+    }
+}
+
+/// Serialize expand specs to a `$expand` value (top-level paths separated by commas)
+pub(crate) fn expand_to_odata(specs: &[ExpandSpec]) -> String {
+    specs.iter()
+        .map(ExpandSpec::to_odata)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Build a `$expand` value from structured specs, ready to pass as `expand` to execute_odata_query
+#[tauri::command]
+pub fn build_expand(specs: Vec<ExpandSpec>) -> Result<String, String> {
+    if let Some(spec) = specs.iter().find(|s| s.path.trim().is_empty()) {
+        return Err(format!("Expand spec is missing a navigation path: {:?}", spec));
+    }
+    Ok(expand_to_odata(&specs))
+}
+
+/// Read `@odata.count` (or the older `odata.count`) from a response object
+pub fn extract_odata_count(json: &Value) -> Option<i64> {
+    ["@odata.count", "odata.count"]
+        .iter()
+        .find_map(|key| json.get(*key))
+        .and_then(|v| v.as_i64().or_else(|| v.as_str().and_then(|s| s.parse().ok())))
+}
+
+/// Return just the number of rows matching `filter`, without downloading any of them.
+/// Uses `{entity}/$count` and falls back to `$top=0&$count=true` for servers without the count path.
+#[tauri::command]
+pub async fn execute_odata_count(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    base_url: String,
+    entity: String,
+    filter: Option<String>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    deadline_id: Option<String>,
+) -> Result<i64, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let client = build_client(ctx.network, timeout_seconds, connect_timeout_seconds, None)?;
+    let odata_base = odata_service_root(ctx.network, &base_url)?;
+    let filter = filter.filter(|f| !f.is_empty());
+
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    let mut count_url = format!("{}/{}/$count", odata_base, entity);
+    if let Some(ref f) = filter {
+        count_url = format!("{}?$filter={}", count_url, f);
+    }
+
+    let response = send_with_refresh(&ctx, profile_name.as_deref(), auth.clone(), None, "OData count request", |headers| {
+        client.get(&count_url).headers(headers)
+    }).await?;
+
+    if response.status().is_success() {
+        let body = read_body_limited(response, None).await?;
+        // Plain-text integer, sometimes with a BOM or trailing newline
+        if let Ok(count) = body.trim_start_matches('\u{feff}').trim().parse::<i64>() {
+            return Ok(count);
+        }
+    }
+
+    // Fallback: ask for zero rows plus the inline count
+    let mut fallback_url = format!("{}/{}?$top=0&$count=true", odata_base, entity);
+    if let Some(ref f) = filter {
+        fallback_url = format!("{}&$filter={}", fallback_url, f);
+    }
+
+    let response = send_with_refresh(&ctx, profile_name.as_deref(), auth, None, "OData count request", |headers| {
+        client.get(&fallback_url).headers(headers)
+    }).await?;
+
+    let status = response.status();
+    let body = read_body_limited(response, None).await?;
+    if !status.is_success() {
+        return Err(format!("OData count failed with status {}: {}", status.as_u16(), body).into());
+    }
+
+    let json: Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse OData count response as JSON: {}", e))?;
+
+    extract_odata_count(&json)
+        .ok_or_else(|| "OData response did not include @odata.count".to_string().into())
+}
+
+/// True for the canonical 8-4-4-4-12 hex GUID form
+pub(crate) fn is_guid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    groups.len() == 5
+        && groups.iter().zip([8, 4, 4, 4, 12]).all(|(g, len)| {
+            g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit())
+        })
+}
+
+/// Format an entity key as an OData key literal: numbers and GUIDs bare,
+/// other strings single-quoted with embedded quotes doubled
+pub(crate) fn format_entity_key(key: &Value) -> Result<String, String> {
+    match key {
+        Value::Number(n) => Ok(n.to_string()),
+        Value::String(s) if s.is_empty() => Err("Entity key is empty".to_string()),
+        Value::String(s) if is_guid(s) => Ok(s.clone()),
+        Value::String(s) => Ok(format!("'{}'", urlencoding::encode(&s.replace('\'', "''")))),
+        other => Err(format!("Entity key must be a number or string, got {}", other)),
+    }
+}
+
+/// Fetch a single record by key via `{entity}({key})` and return the object itself (not wrapped in `value`).
+/// A 404 comes back as a typed `NotFound` error.
+#[tauri::command]
+pub async fn fetch_odata_entity(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    base_url: String,
+    entity: String,
+    key: Value,
+    select: Option<String>,
+    expand: Option<String>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    deadline_id: Option<String>,
+) -> Result<Value, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let client = build_client(ctx.network, timeout_seconds, connect_timeout_seconds, None)?;
+    let odata_base = odata_service_root(ctx.network, &base_url)?;
+    let key_literal = format_entity_key(&key)?;
+
+    let mut url = format!("{}/{}({})", odata_base, entity, key_literal);
+    let mut query_params: Vec<String> = Vec::new();
+    if let Some(ref s) = select {
+        if !s.is_empty() {
+            query_params.push(format!("$select={}", s));
+        }
+    }
+    if let Some(ref e) = expand {
+        if !e.is_empty() {
+            query_params.push(format!("$expand={}", e));
+        }
+    }
+    if !query_params.is_empty() {
+        url = format!("{}?{}", url, query_params.join("&"));
+    }
+
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    let response = send_with_refresh(&ctx, profile_name.as_deref(), auth, None, "OData entity request", |headers| {
+        client.get(&url).headers(headers)
+    }).await?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND {
+        return Err(AppError::typed(ErrorKind::NotFound { entity, key: key_literal }));
+    }
+
+    let body = read_body_limited(response, None).await?;
+    if !status.is_success() {
+        return Err(format!("OData entity fetch failed with status {}: {}", status.as_u16(), body).into());
+    }
+
+    serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse OData entity response as JSON: {}", e).into())
+}
+
+/// Where the next page starts, taken from a response's `@odata.nextLink`.
+/// Persist it to resume a large read later by passing `skiptoken` back in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageCursor {
+    pub skiptoken: Option<String>,
+    pub next_link: String,
+}
+
+/// One page of rows plus what a table needs to decide whether to offer more
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page {
+    pub rows: Vec<Value>,
+    pub page: u32,
+    pub page_size: u32,
+    pub has_more: bool,
+    // Total matching rows, when requested with include_total
+    pub total: Option<i64>,
+    // Server-driven continuation, when the response had a nextLink
+    pub cursor: Option<PageCursor>,
+}
+
+/// Read `@odata.nextLink` (or the older `odata.nextLink`) from a response object
+pub(crate) fn next_link(json: &Value) -> Option<&str> {
+    ["@odata.nextLink", "odata.nextLink"]
+        .iter()
+        .find_map(|key| json.get(*key))
+        .and_then(Value::as_str)
+}
+
+/// Make a nextLink absolute. Some servers send a relative link (`Entity?$skiptoken=..` or
+/// `/CoreApi/OData/Entity?..`), which is resolved against the OData service root with `Url::join`.
+/// The next page is requested with the profile's auth headers, so a link to any other origin is
+/// rejected rather than followed - the same rule redirects get (see `RedirectPolicy`).
+pub(crate) fn resolve_next_link(service_root: &str, next_link: &str) -> Result<String, String> {
+    // Trailing slash so a bare `Entity?...` lands under the service root rather than replacing its last segment
+    let root = url::Url::parse(&format!("{}/", service_root.trim_end_matches('/')))
+        .map_err(|e| format!("Invalid OData service root '{}': {}", service_root, e))?;
+    let resolved = root.join(next_link)
+        .map_err(|e| format!("Invalid nextLink '{}': {}", next_link, e))?;
+    if resolved.origin() != root.origin() {
+        return Err(format!(
+            "Refusing to follow nextLink '{}': it points away from the service at {}",
+            next_link,
+            root.origin().ascii_serialization()
+        ));
+    }
+    Ok(resolved.into())
+}
+
+/// Build a cursor from a nextLink, resolving it to an absolute URL when the service root is known,
+/// and pull `$skiptoken` out of its query string wherever the parameter sits
+pub(crate) fn page_cursor(service_root: Option<&str>, next_link: &str) -> PageCursor {
+    let next_link = service_root
+        .and_then(|root| resolve_next_link(root, next_link).ok())
+        .unwrap_or_else(|| next_link.to_string());
+    // Only the query matters for the token, so a still-relative link is parsed against a placeholder origin
+    let skiptoken = url::Url::parse("http://placeholder/")
+        .and_then(|base| base.join(&next_link))
+        .ok()
+        .and_then(|url| {
+            url.query_pairs()
+                .find(|(name, _)| name.eq_ignore_ascii_case("$skiptoken"))
+                .map(|(_, value)| value.into_owned())
+        });
+    PageCursor { skiptoken, next_link }
+}
+
+/// Continuation cursor for a raw `execute_odata_query` result, if the server sent a nextLink.
+/// Pass the `base_url` the query used to get a relative nextLink back as an absolute URL.
+#[tauri::command]
+pub fn get_page_cursor(
+    network: State<'_, NetworkConfig>,
+    response: Value,
+    base_url: Option<String>,
+) -> Result<Option<PageCursor>, String> {
+    let service_root = match base_url {
+        Some(base_url) => Some(odata_service_root(&network, &base_url)?),
+        None => None,
+    };
+    Ok(next_link(&response).map(|link| page_cursor(service_root.as_deref(), link)))
+}
+
+/// The rows of either response shape Nimbus uses: a bare array or `{ value: [...] }`
+pub fn unwrap_odata_value(json: &Value) -> Result<&Vec<Value>, String> {
+    match json {
+        Value::Array(rows) => Ok(rows),
+        Value::Object(object) => match object.get("value") {
+            Some(Value::Array(rows)) => Ok(rows),
+            Some(other) => Err(format!("Expected the OData `value` to be an array, got {}", other)),
+            None => Err("Expected an OData result with a `value` array, got an object without one".to_string()),
+        },
+        other => Err(format!("Expected an OData result (an array or an object with a `value` array), got {}", other)),
+    }
+}
+
+/// Like `unwrap_odata_value`, but copied out and empty for any other shape
+pub(crate) fn result_rows(json: &Value) -> Vec<Value> {
+    unwrap_odata_value(json).cloned().unwrap_or_default()
+}
+
+/// Fetch page `page` (0-based) of `page_size` rows. One extra row is requested to
+/// work out `has_more`, and `@odata.nextLink` is honoured too when the server sends it.
+#[tauri::command]
+pub async fn execute_odata_page(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    base_url: String,
+    entity: String,
+    page: u32,
+    page_size: u32,
+    filter: Option<String>,
+    select: Option<String>,
+    expand: Option<String>,
+    orderby: Option<String>,
+    include_total: Option<bool>,
+    skiptoken: Option<String>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    deadline_id: Option<String>,
+) -> Result<Page, AppError> {
+    if page_size == 0 {
+        return Err("page_size must be at least 1".to_string().into());
+    }
+
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let service_root = odata_service_root(ctx.network, &base_url)?;
+    let skip = page as u64 * page_size as u64;
+    let params = ODataQueryParams {
+        base_url,
+        entity,
+        top: Some(i32::try_from(page_size + 1).map_err(|_| "page_size is too large".to_string())?),
+        // A skiptoken already encodes the position, so $skip would double-count
+        skip: match skiptoken {
+            Some(_) => None,
+            None => Some(i32::try_from(skip).map_err(|_| "page is too far into the result set".to_string())?),
+        },
+        filter,
+        select,
+        expand,
+        orderby,
+        count: include_total,
+        skiptoken,
+        user_id,
+        auth_token,
+        app_token,
+        username,
+        auth_scheme,
+        profile_name,
+        timeout_seconds,
+        connect_timeout_seconds,
+        ..Default::default()
+    };
+
+    let json = run_odata_query(&ctx, &metrics, &fields, params).await?;
+
+    let mut rows = result_rows(&json);
+    let cursor = next_link(&json).map(|link| page_cursor(Some(&service_root), link));
+    let has_extra_row = rows.len() > page_size as usize;
+    rows.truncate(page_size as usize);
+
+    Ok(Page {
+        rows,
+        page,
+        page_size,
+        has_more: has_extra_row || cursor.is_some(),
+        total: if include_total.unwrap_or(false) { extract_odata_count(&json) } else { None },
+        cursor,
+    })
+}
+
+// Page size for execute_odata_all_pages unless asked otherwise
+const DEFAULT_ALL_PAGES_SIZE: u32 = 500;
+
+/// Rows gathered by `execute_odata_all_pages`. With `partial_ok`, a failure partway through still
+/// returns the pages that succeeded plus the error; pass `completed_pages` back as `start_page` to resume.
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialResult {
+    pub rows: Vec<Value>,
+    pub completed_pages: u32,
+    pub error: Option<AppError>,
+}
+
+/// Follow pages of `page_size` rows (500 by default) until the result set ends or `max_pages` is reached.
+/// A page with a nextLink is followed by requesting that link as sent; otherwise the next page skips
+/// the rows received so far, so a server that caps the page size doesn't lose rows.
+/// By default any failure fails the whole call; set `partial_ok` to keep the rows read before it.
+#[tauri::command]
+pub async fn execute_odata_all_pages(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    base_url: String,
+    entity: String,
+    filter: Option<String>,
+    select: Option<String>,
+    expand: Option<String>,
+    orderby: Option<String>,
+    page_size: Option<u32>,
+    start_page: Option<u32>,
+    max_pages: Option<u32>,
+    partial_ok: Option<bool>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    deadline_id: Option<String>,
+) -> Result<PartialResult, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let page_size = page_size.unwrap_or(DEFAULT_ALL_PAGES_SIZE).max(1);
+    let start_page = start_page.unwrap_or(0);
+    let first_skip = start_page as u64 * page_size as u64;
+
+    let mut rows: Vec<Value> = Vec::new();
+    let mut page = start_page;
+    // Set once the server starts driving the paging with nextLinks
+    let mut link: Option<String> = None;
+
+    while max_pages.is_none_or(|max| page - start_page < max) {
+        let skip = first_skip + rows.len() as u64;
+        let params = ODataQueryParams {
+            base_url: base_url.clone(),
+            entity: entity.clone(),
+            top: Some(i32::try_from(page_size).map_err(|_| "page_size is too large".to_string())?),
+            skip: match link {
+                Some(_) => None,
+                None => Some(i32::try_from(skip).map_err(|_| "Result set is too large to page with $skip".to_string())?),
+            },
+            next_link: link.clone(),
+            filter: filter.clone(),
+            select: select.clone(),
+            expand: expand.clone(),
+            orderby: orderby.clone(),
+            user_id,
+            auth_token: auth_token.clone(),
+            app_token: app_token.clone(),
+            username: username.clone(),
+            auth_scheme: auth_scheme.clone(),
+            profile_name: profile_name.clone(),
+            timeout_seconds,
+            connect_timeout_seconds,
+            ..Default::default()
+        };
+
+        let json = match run_odata_query(&ctx, &metrics, &fields, params).await {
+            Ok(json) => json,
+            Err(error) if partial_ok.unwrap_or(false) => {
+                return Ok(PartialResult { rows, completed_pages: page, error: Some(error) });
+            }
+            Err(error) => return Err(error),
+        };
+
+        // An unexpected shape stops paging with an error rather than passing for an empty last page
+        let page_rows = match unwrap_odata_value(&json) {
+            Ok(page_rows) => page_rows,
+            Err(error) => {
+                let error = AppError::from(format!("Page {}: {}", page + 1, error));
+                if partial_ok.unwrap_or(false) {
+                    return Ok(PartialResult { rows, completed_pages: page, error: Some(error) });
+                }
+                return Err(error);
+            }
+        };
+        let page_len = page_rows.len();
+        rows.extend(page_rows.iter().cloned());
+        page += 1;
+
+        // A short page ends the result set, unless the server capped the page size and sent a nextLink
+        link = next_link(&json).map(str::to_string);
+        if page_len == 0 || ((page_len as u32) < page_size && link.is_none()) {
+            break;
+        }
+    }
+
+    Ok(PartialResult { rows, completed_pages: page, error: None })
+}
+
+/// Join key for a row's value: strings as-is so `"42"` and `42` match, nulls never match
+fn join_key(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Enrich `primary` rows with `fields` from the `lookup` row whose `lookup_key` equals the primary
+/// row's `primary_key` (e.g. a cost centre code to its name) when `$expand` isn't available.
+/// Both queries run once, side by side. Rows with no match get the fields as null, and a field the
+/// primary row already has is overwritten. The result keeps the primary query's shape.
+#[tauri::command]
+pub async fn odata_lookup_join(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    field_cache: State<'_, EntityFieldCache>,
+    primary: ODataQueryParams,
+    lookup: ODataQueryParams,
+    primary_key: String,
+    lookup_key: String,
+    fields: Vec<String>,
+) -> Result<Value, AppError> {
+    if primary.dry_run.unwrap_or(false) || lookup.dry_run.unwrap_or(false) {
+        return Err("dry_run isn't supported for a lookup join".to_string().into());
+    }
+    let context = |params: &ODataQueryParams| -> Result<RequestContext<'_>, String> {
+        Ok(RequestContext {
+            store: &store,
+            log: &log,
+            circuits: &circuits,
+            network: &network,
+            locks: &locks,
+            deadline: network.deadlines.resolve(params.deadline_id.as_deref())?,
+        })
+    };
+    let (primary_ctx, lookup_ctx) = (context(&primary)?, context(&lookup)?);
+
+    let (primary_json, lookup_json) = tokio::try_join!(
+        run_odata_query(&primary_ctx, &metrics, &field_cache, primary),
+        run_odata_query(&lookup_ctx, &metrics, &field_cache, lookup),
+    )?;
+
+    // First lookup row wins when keys repeat
+    let mut lookups: HashMap<String, Value> = HashMap::new();
+    for row in result_rows(&lookup_json) {
+        if let Some(key) = join_key(row.get(&lookup_key)) {
+            lookups.entry(key).or_insert(row);
+        }
+    }
+
+    let mut joined = primary_json;
+    let rows = match &mut joined {
+        Value::Array(rows) => rows,
+        Value::Object(object) => match object.get_mut("value") {
+            Some(Value::Array(rows)) => rows,
+            _ => return Err("Primary query result has no rows to join".to_string().into()),
+        },
+        _ => return Err("Primary query result has no rows to join".to_string().into()),
+    };
+
+    for row in rows.iter_mut() {
+        let matched = join_key(row.get(&primary_key)).and_then(|key| lookups.get(&key));
+        let Value::Object(row) = row else {
+            continue;
+        };
+        for field in &fields {
+            let value = matched.and_then(|m| m.get(field)).cloned().unwrap_or(Value::Null);
+            row.insert(field.clone(), value);
+        }
+    }
+
+    Ok(joined)
+}
+
+/// Append `value` as compact JSON with object keys sorted, so key order never changes the output
+fn write_canonical_json(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical_json(value, out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical_json(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// SHA-256 (hex) of the row count and each row as canonical JSON
+pub(crate) fn rows_hash(rows: &[Value]) -> String {
+    let mut canonical = format!("{}\n", rows.len());
+    for row in rows {
+        write_canonical_json(row, &mut canonical);
+        canonical.push('\n');
+    }
+
+    Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Run a query and return a SHA-256 (hex) of its rows and row count, for cheap change detection:
+/// poll with the same params and skip the re-render while the hash is unchanged. Object keys are
+/// sorted before hashing, so only the data and the row order count.
+#[tauri::command]
+pub async fn odata_result_hash(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    params: ODataQueryParams,
+) -> Result<String, AppError> {
+    if params.dry_run.unwrap_or(false) {
+        return Err("dry_run isn't supported when hashing a result".to_string().into());
+    }
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(params.deadline_id.as_deref())?,
+    };
+    let json = run_odata_query(&ctx, &metrics, &fields, params).await?;
+    // A changed response shape is an error, not the hash of "no rows"
+    Ok(rows_hash(unwrap_odata_value(&json)?))
+}
+
+/// Field lists per entity, resolved for `select_all` (managed state, not persisted)
+#[derive(Default)]
+pub struct EntityFieldCache {
+    fields: Mutex<HashMap<String, Vec<String>>>,
+}
+
+/// Every field of `entity`, including adhoc fields, for building a full `$select`.
+/// Read from `$metadata` first, falling back to the keys of a sample row; cached per OData root + entity.
+pub(crate) async fn entity_fields(
+    ctx: &RequestContext<'_>,
+    cache: &EntityFieldCache,
+    client: &reqwest::Client,
+    odata_base: &str,
+    entity: &str,
+    profile_name: Option<&str>,
+    auth: AuthFields,
+) -> Result<Vec<String>, AppError> {
+    let cache_key = format!("{}/{}", odata_base, entity);
+    if let Some(fields) = cache.fields.lock().unwrap_or_else(|e| e.into_inner()).get(&cache_key) {
+        return Ok(fields.clone());
+    }
+
+    // $metadata is CSDL XML; the caller-header layer overrides the default JSON Accept
+    let metadata_url = format!("{}/$metadata", odata_base);
+    let accept_xml = HashMap::from([("Accept".to_string(), "application/xml".to_string())]);
+    let response = send_with_refresh(ctx, profile_name, auth.clone(), Some(accept_xml), "OData metadata request", |headers| {
+        client.get(&metadata_url).headers(headers)
+    }).await?;
+
+    let mut fields = Vec::new();
+    if response.status().is_success() {
+        let body = read_body_limited(response, None).await?;
+        fields = csdl_entity_properties(&body, entity).unwrap_or_default();
+    }
+
+    if fields.is_empty() {
+        let sample_url = format!("{}/{}?$top=1", odata_base, entity);
+        let response = send_with_refresh(ctx, profile_name, auth, None, "OData sample request", |headers| {
+            client.get(&sample_url).headers(headers)
+        }).await?;
+        let status = response.status();
+        let body = read_body_limited(response, None).await?;
+        if !status.is_success() {
+            return Err(format!("Could not list fields of {} (status {}): {}", entity, status.as_u16(), body).into());
+        }
+        let json: Value = serde_json::from_str(&body)
+            .map_err(|e| format!("Failed to parse OData sample row as JSON: {}", e))?;
+        if let Some(Value::Object(row)) = result_rows(&json).first() {
+            // Skip annotations such as @odata.etag and Name@odata.type
+            fields = row.keys().filter(|k| !k.contains('@')).cloned().collect();
+        }
+    }
+
+    if fields.is_empty() {
+        return Err(format!("Could not determine the fields of {}", entity).into());
+    }
+
+    cache.fields.lock().unwrap_or_else(|e| e.into_inner()).insert(cache_key, fields.clone());
+    Ok(fields)
+}
+
+// OData service paths Nimbus has exposed, best first (only /CoreApi/OData returns adhoc fields)
+const ODATA_ENDPOINT_CANDIDATES: &[&str] = &["/CoreApi/OData", "/ODataApi", "/odata"];
+
+// Entity used for the endpoint probe when the caller doesn't name one
+pub(crate) const DEFAULT_PROBE_ENTITY: &str = "User";
+
+/// OData service root detected per Nimbus site (held by `NetworkConfig`, not persisted)
+#[derive(Default)]
+pub struct ODataEndpointCache {
+    endpoints: Mutex<HashMap<String, String>>,
+}
+
+impl ODataEndpointCache {
+    fn get(&self, site: &str) -> Option<String> {
+        self.endpoints.lock().unwrap_or_else(|e| e.into_inner()).get(site).cloned()
+    }
+
+    fn insert(&self, site: String, endpoint: String) {
+        self.endpoints.lock().unwrap_or_else(|e| e.into_inner()).insert(site, endpoint);
+    }
+}
+
+/// The OData service root to query for `base_url`. A bare site URL, or the detected root itself,
+/// uses the endpoint `detect_odata_endpoint`/`verify_profile` found for that site; anything else
+/// (or a site not probed yet) falls back to `odata_base_url`.
+pub(crate) fn odata_service_root(network: &NetworkConfig, base_url: &str) -> Result<String, String> {
+    let normalized = normalize_base_url(base_url)?;
+    let trimmed = normalized.trim_end_matches('/');
+    if let Some(detected) = network.odata_endpoints.get(site_root(trimmed)) {
+        if trimmed == site_root(trimmed) || trimmed == detected {
+            return Ok(detected);
+        }
+    }
+    Ok(odata_base_url(&normalized))
+}
+
+/// The Nimbus site root, with any OData service path the caller included stripped off
+fn site_root(base_url: &str) -> &str {
+    let trimmed = base_url.trim_end_matches('/');
+    ODATA_ENDPOINT_CANDIDATES
+        .iter()
+        .find_map(|path| trimmed.strip_suffix(path))
+        .unwrap_or(trimmed)
+}
+
+/// Probe `/CoreApi/OData`, `/ODataApi` and `/odata` in turn with a `$top=0` query and return the first
+/// service root that answers successfully. The result is cached per base URL; `refresh` probes again.
+#[tauri::command]
+pub async fn detect_odata_endpoint(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    base_url: String,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    probe_entity: Option<String>,
+    refresh: Option<bool>,
+    timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+) -> Result<String, AppError> {
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    let normalized = normalize_base_url(&base_url)?;
+    let root = site_root(&normalized).to_string();
+
+    if !refresh.unwrap_or(false) {
+        if let Some(endpoint) = ctx.network.odata_endpoints.get(&root) {
+            return Ok(endpoint);
+        }
+    }
+
+    let client = build_client(ctx.network, timeout_seconds.or(Some(10)), None, None)?;
+    let probe_entity = probe_entity.filter(|e| !e.is_empty()).unwrap_or_else(|| DEFAULT_PROBE_ENTITY.to_string());
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    let mut failures: Vec<String> = Vec::new();
+    for path in ODATA_ENDPOINT_CANDIDATES {
+        let endpoint = format!("{}{}", root, path);
+        let probe_url = format!("{}/{}?$top=0", endpoint, probe_entity);
+        let result = send_with_refresh(&ctx, profile_name.as_deref(), auth.clone(), None, "OData endpoint probe", |headers| {
+            client.get(&probe_url).headers(headers)
+        }).await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                ctx.network.odata_endpoints.insert(root, endpoint.clone());
+                return Ok(endpoint);
+            }
+            Ok(response) => failures.push(format!("{} ({})", path, response.status().as_u16())),
+            // Auth failures mean the endpoint exists but the credentials are wrong - probing further won't help
+            Err(e @ AppError::Typed { kind: ErrorKind::Unauthorized { .. }, .. }) => return Err(e),
+            Err(e) => failures.push(format!("{} ({})", path, e)),
+        }
+    }
+
+    Err(format!("No OData endpoint responded at {}: {}", root, failures.join(", ")).into())
+}
+
+/// Entity set names from the OData service document at the service root - much lighter than
+/// `$metadata`, and enough for an entity picker. Singletons and function imports are left out.
+/// Servers that answer with the XML (AtomPub) service document are handled too.
+#[tauri::command]
+pub async fn list_odata_sets(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    base_url: String,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+) -> Result<Vec<String>, AppError> {
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    let client = build_client(ctx.network, timeout_seconds, None, None)?;
+    // The trailing slash asks for the service document rather than an entity
+    let url = format!("{}/", odata_service_root(ctx.network, &base_url)?);
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    let response = send_with_refresh(&ctx, profile_name.as_deref(), auth, None, "OData service document request", |headers| {
+        client.get(&url).headers(headers)
+    }).await?;
+    let status = response.status();
+    let content_type = response.headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let body = read_body_limited(response, None).await?;
+    if !status.is_success() {
+        return Err(format!("Service document request failed with status {}: {}", status.as_u16(), body).into());
+    }
+
+    if looks_like_xml(content_type.as_deref(), &body) {
+        return Ok(service_document_sets(&body)?);
+    }
+    let json: Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse service document as JSON: {}", e))?;
+    Ok(unwrap_odata_value(&json)?
+        .iter()
+        .filter(|set| set.get("kind").and_then(Value::as_str).is_none_or(|kind| kind == "EntitySet"))
+        .filter_map(|set| set.get("name").or_else(|| set.get("url")).and_then(Value::as_str))
+        .map(str::to_string)
+        .collect())
+}
+
+/// OData features a Nimbus deployment supports, so the UI can hide options it would reject
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub supports_apply: bool,
+    pub supports_search: bool,
+    pub supports_batch: bool,
+    pub supports_compute: bool,
+    // From the OData-Version / DataServiceVersion header, else the `$metadata` document
+    pub odata_version: Option<String>,
+}
+
+/// Capabilities detected per OData service root (managed state, not persisted)
+#[derive(Default)]
+pub struct CapabilitiesCache {
+    capabilities: Mutex<HashMap<String, Capabilities>>,
+}
+
+/// Whether a capability probe shows the feature, or None when the probe itself failed (rate limited,
+/// circuit open, timed out). Only a rejected session fails the whole detection.
+fn probe_outcome(
+    outcome: Result<reqwest::Response, AppError>,
+    supported: impl FnOnce(reqwest::StatusCode) -> bool,
+) -> Result<Option<bool>, AppError> {
+    match outcome {
+        Ok(response) => Ok(Some(supported(response.status()))),
+        Err(e @ AppError::Typed { kind: ErrorKind::Unauthorized { .. }, .. }) => Err(e),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Detect which optional OData features a deployment supports: `$metadata` gives the version and
+/// any BatchSupported annotation, then cheap `$top=0` style queries against `probe_entity` (default
+/// `User`) try `$search`, `$apply` and `$compute`. Without an annotation, `$batch` counts as supported when
+/// the server answers a GET on it with anything but 404 (it only accepts POST).
+/// A feature whose probe fails counts as unsupported and the result isn't cached; only an auth failure
+/// or a failed `$metadata` request fails the call. Cached per OData service root; `refresh` probes again.
+#[tauri::command]
+pub async fn detect_capabilities(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    cache: State<'_, CapabilitiesCache>,
+    base_url: String,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    probe_entity: Option<String>,
+    refresh: Option<bool>,
+    timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+) -> Result<Capabilities, AppError> {
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    let odata_base = odata_service_root(ctx.network, &base_url)?;
+
+    if !refresh.unwrap_or(false) {
+        if let Some(capabilities) = cache.capabilities.lock().unwrap_or_else(|e| e.into_inner()).get(&odata_base) {
+            return Ok(capabilities.clone());
+        }
+    }
+
+    let client = build_client(ctx.network, timeout_seconds.or(Some(10)), None, None)?;
+    let probe_entity = probe_entity.filter(|e| !e.is_empty()).unwrap_or_else(|| DEFAULT_PROBE_ENTITY.to_string());
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+    let probe = |url: String, headers: Option<HashMap<String, String>>, label: &'static str| {
+        let (ctx, client, auth, profile_name) = (&ctx, &client, auth.clone(), profile_name.as_deref());
+        async move {
+            send_with_refresh(ctx, profile_name, auth, headers, label, |headers| client.get(&url).headers(headers)).await
+        }
+    };
+
+    // $metadata is CSDL XML; the caller-header layer overrides the default JSON Accept
+    let accept_xml = HashMap::from([("Accept".to_string(), "application/xml".to_string())]);
+    let response = probe(format!("{}/$metadata", odata_base), Some(accept_xml), "OData metadata request").await?;
+    let header_version = ["OData-Version", "DataServiceVersion"].iter().find_map(|name| {
+        let value = response.headers().get(*name)?.to_str().ok()?;
+        // v2/v3 servers append the implementation, e.g. `3.0;NetFx`
+        value.split(';').next().map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+    });
+    let service_info = if response.status().is_success() {
+        let body = read_body_limited(response, None).await?;
+        csdl_service_info(&body).unwrap_or_default()
+    } else {
+        CsdlServiceInfo::default()
+    };
+
+    let search_url = format!("{}/{}?$top=0&$search=a", odata_base, probe_entity);
+    let supports_search = probe_outcome(probe(search_url, None, "OData $search probe").await, |s| s.is_success())?;
+
+    let apply_url = format!("{}/{}?$apply=aggregate($count as Total)", odata_base, probe_entity);
+    let supports_apply = probe_outcome(probe(apply_url, None, "OData $apply probe").await, |s| s.is_success())?;
+
+    let compute_url = format!("{}/{}?$top=0&$compute=1 add 1 as ComputeProbe", odata_base, probe_entity);
+    let supports_compute = probe_outcome(probe(compute_url, None, "OData $compute probe").await, |s| s.is_success())?;
+
+    let supports_batch = match service_info.batch_supported {
+        Some(supported) => Some(supported),
+        None => probe_outcome(
+            probe(format!("{}/$batch", odata_base), None, "OData $batch probe").await,
+            |s| s != reqwest::StatusCode::NOT_FOUND,
+        )?,
+    };
+
+    let probes = [supports_apply, supports_search, supports_batch, supports_compute];
+    let capabilities = Capabilities {
+        supports_apply: supports_apply.unwrap_or(false),
+        supports_search: supports_search.unwrap_or(false),
+        supports_batch: supports_batch.unwrap_or(false),
+        supports_compute: supports_compute.unwrap_or(false),
+        odata_version: header_version.or(service_info.version),
+    };
+    // A probe that failed may succeed next time, so don't pin its feature as unsupported
+    if probes.iter().all(Option::is_some) {
+        cache.capabilities.lock().unwrap_or_else(|e| e.into_inner()).insert(odata_base, capabilities.clone());
+    }
+    Ok(capabilities)
+}
+
+/// Outcome of `verify_profile`, one flag per setup step plus what went wrong at each
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerifyReport {
+    pub connectivity: bool,
+    pub endpoint_ok: bool,
+    pub auth_ok: bool,
+    pub messages: Vec<String>,
+}
+
+/// Check a stored profile end to end for a "check my setup" wizard: the host answers, an OData
+/// endpoint exists under its base URL, and its session can run a one-row query. A failed step is
+/// recorded in `messages` and the later steps still run, so the report shows everything wrong at once.
+#[tauri::command]
+pub async fn verify_profile(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    profile_name: String,
+) -> Result<VerifyReport, String> {
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    let mut report = VerifyReport::default();
+
+    let credentials = match session::load_credentials(ctx.store, &profile_name) {
+        Ok(credentials) => credentials,
+        Err(e) => {
+            report.messages.push(format!("Could not load profile '{}': {}", profile_name, e));
+            return Ok(report);
+        }
+    };
+
+    match probe_connectivity(ctx.network, &credentials.base_url, None, None).await {
+        Ok(result) if result.reachable => report.connectivity = true,
+        Ok(result) => report.messages.push(format!(
+            "{} is not reachable: {}",
+            credentials.base_url,
+            result.message.unwrap_or_else(|| "no response".to_string())
+        )),
+        Err(e) => report.messages.push(format!("Base URL '{}' is invalid: {}", credentials.base_url, e)),
+    }
+
+    let root = match normalize_base_url(&credentials.base_url) {
+        Ok(normalized) => site_root(&normalized).to_string(),
+        Err(_) => return Ok(report),
+    };
+    let client = build_client(ctx.network, Some(10), None, None)?;
+    // The stored session as-is: a stale token is refreshed on its 401 like any other request
+    let auth = AuthFields::from(&credentials);
+
+    // An endpoint that refuses the credentials still exists, so only a missing one fails this step
+    let mut endpoint = None;
+    let mut failures: Vec<String> = Vec::new();
+    for path in ODATA_ENDPOINT_CANDIDATES {
+        let candidate = format!("{}{}", root, path);
+        let probe_url = format!("{}/{}?$top=0", candidate, DEFAULT_PROBE_ENTITY);
+        let result = send_with_refresh(&ctx, Some(&profile_name), auth.clone(), None, "OData endpoint probe", |headers| {
+            client.get(&probe_url).headers(headers)
+        }).await;
+
+        match result {
+            Ok(response) if response.status().is_success() => {
+                endpoint = Some(candidate);
+                break;
+            }
+            Err(AppError::Typed { kind: ErrorKind::Unauthorized { .. }, .. }) => {
+                endpoint = Some(candidate);
+                break;
+            }
+            Ok(response) => failures.push(format!("{} ({})", path, response.status().as_u16())),
+            Err(e) => failures.push(format!("{} ({})", path, e)),
+        }
+    }
+
+    let Some(endpoint) = endpoint else {
+        report.messages.push(format!("No OData endpoint found at {}: {}", root, failures.join(", ")));
+        report.messages.push("Skipped the test query: there is no OData endpoint to run it against".to_string());
+        return Ok(report);
+    };
+    report.endpoint_ok = true;
+
+    let query_url = format!("{}/{}?$top=1", endpoint, DEFAULT_PROBE_ENTITY);
+    let result = send_with_refresh(&ctx, Some(&profile_name), auth, None, "Profile test query", |headers| {
+        client.get(&query_url).headers(headers)
+    }).await;
+    match result {
+        Ok(response) if response.status().is_success() => {
+            report.auth_ok = true;
+            ctx.network.odata_endpoints.insert(root, endpoint);
+        }
+        Ok(response) => report.messages.push(format!(
+            "Test query against {} failed with status {}",
+            endpoint,
+            response.status().as_u16()
+        )),
+        Err(e) => report.messages.push(format!(
+            "Test query against {} failed ({} auth): {}",
+            endpoint, credentials.auth_mode, e
+        )),
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const ROOT: &str = "https://nimbus.example.edu/CoreApi/OData";
+
+    #[test]
+    fn resolve_next_link_keeps_absolute_links_to_the_service() {
+        let link = "https://nimbus.example.edu/CoreApi/OData/User?$skiptoken=abc&$top=100";
+        assert_eq!(resolve_next_link(ROOT, link).unwrap(), link);
+    }
+
+    #[test]
+    fn resolve_next_link_rejects_other_origins() {
+        for link in [
+            "https://other.example.edu/CoreApi/OData/User?$skiptoken=abc",
+            "http://nimbus.example.edu/CoreApi/OData/User?$skiptoken=abc",
+            "https://nimbus.example.edu:8443/CoreApi/OData/User?$skiptoken=abc",
+            "//other.example.edu/CoreApi/OData/User",
+        ] {
+            assert!(resolve_next_link(ROOT, link).is_err(), "{}", link);
+        }
+    }
+
+    #[test]
+    fn resolve_next_link_resolves_relative_links_under_the_service_root() {
+        assert_eq!(
+            resolve_next_link(ROOT, "User?$skip=100&$top=100").unwrap(),
+            "https://nimbus.example.edu/CoreApi/OData/User?$skip=100&$top=100"
+        );
+        assert_eq!(
+            resolve_next_link(&format!("{}/", ROOT), "User?$skiptoken=abc").unwrap(),
+            "https://nimbus.example.edu/CoreApi/OData/User?$skiptoken=abc"
+        );
+    }
+
+    #[test]
+    fn service_root_uses_the_detected_endpoint() {
+        let network = NetworkConfig::default();
+        let site = "https://nimbus.example.edu";
+        assert_eq!(odata_service_root(&network, site).unwrap(), ROOT);
+
+        network.odata_endpoints.insert(site.to_string(), format!("{}/ODataApi", site));
+        assert_eq!(odata_service_root(&network, site).unwrap(), "https://nimbus.example.edu/ODataApi");
+        // The detected root passed back in isn't rewritten to /CoreApi/OData
+        assert_eq!(
+            odata_service_root(&network, "https://nimbus.example.edu/ODataApi/").unwrap(),
+            "https://nimbus.example.edu/ODataApi"
+        );
+        // An explicit root other than the detected one is still honoured
+        assert_eq!(odata_service_root(&network, "https://nimbus.example.edu/odata").unwrap(), "https://nimbus.example.edu/odata");
+    }
+
+    #[test]
+    fn resolve_next_link_resolves_host_relative_links() {
+        assert_eq!(
+            resolve_next_link(ROOT, "/CoreApi/OData/User?$skiptoken=abc").unwrap(),
+            "https://nimbus.example.edu/CoreApi/OData/User?$skiptoken=abc"
+        );
+    }
+
+    fn spec(path: &str) -> ExpandSpec {
+        ExpandSpec { path: path.to_string(), select: None, filter: None, top: None, expand: Vec::new() }
+    }
+
+    #[test]
+    fn expand_renders_nested_select_and_expand() {
+        let specs = vec![
+            ExpandSpec {
+                select: Some("Id,Description".to_string()),
+                expand: vec![ExpandSpec {
+                    select: Some("Id,Name".to_string()),
+                    top: Some(5),
+                    expand: vec![spec("Manager")],
+                    ..spec("Locations")
+                }],
+                ..spec("Department")
+            },
+            spec("Roles"),
+        ];
+        assert_eq!(
+            build_expand(specs).unwrap(),
+            "Department($select=Id,Description;$expand=Locations($select=Id,Name;$top=5;$expand=Manager)),Roles"
+        );
+    }
+
+    #[test]
+    fn expand_separates_sibling_nested_expands_with_commas() {
+        let specs = vec![ExpandSpec {
+            filter: Some("Active eq true".to_string()),
+            expand: vec![spec("Location"), ExpandSpec { select: Some("Name".to_string()), ..spec("Role") }],
+            ..spec("Shifts")
+        }];
+        assert_eq!(
+            build_expand(specs).unwrap(),
+            "Shifts($filter=Active eq true;$expand=Location,Role($select=Name))"
+        );
+    }
+
+    #[test]
+    fn expand_rejects_a_spec_without_a_path() {
+        assert!(build_expand(vec![spec("Department"), spec(" ")]).is_err());
+    }
+
+    #[test]
+    fn unwrap_odata_value_accepts_both_result_shapes() {
+        let rows = json!([{ "Id": 1 }, { "Id": 2 }]);
+        assert_eq!(unwrap_odata_value(&rows).unwrap().len(), 2);
+
+        let wrapped = json!({ "@odata.context": "$metadata#User", "value": [{ "Id": 1 }] });
+        assert_eq!(unwrap_odata_value(&wrapped).unwrap(), &vec![json!({ "Id": 1 })]);
+        assert!(unwrap_odata_value(&json!({ "value": [] })).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unwrap_odata_value_rejects_other_shapes() {
+        assert!(unwrap_odata_value(&json!({ "Id": 1 })).is_err());
+        assert!(unwrap_odata_value(&json!({ "value": { "Id": 1 } })).is_err());
+        assert!(unwrap_odata_value(&json!("rows")).is_err());
+        assert!(unwrap_odata_value(&Value::Null).is_err());
+        assert!(result_rows(&json!({ "Id": 1 })).is_empty());
+    }
+
+    #[test]
+    fn extract_odata_count_reads_both_annotations() {
+        assert_eq!(extract_odata_count(&json!({ "@odata.count": 42, "value": [] })), Some(42));
+        assert_eq!(extract_odata_count(&json!({ "odata.count": "17", "value": [] })), Some(17));
+    }
+
+    #[test]
+    fn extract_odata_count_is_none_without_a_usable_count() {
+        assert_eq!(extract_odata_count(&json!([{ "Id": 1 }])), None);
+        assert_eq!(extract_odata_count(&json!({ "value": [] })), None);
+        assert_eq!(extract_odata_count(&json!({ "@odata.count": "many" })), None);
+    }
+
+    #[test]
+    fn failed_capability_probes_count_as_unsupported() {
+        let response = |status: u16| Ok(reqwest::Response::from(http::Response::builder().status(status).body("").unwrap()));
+        assert_eq!(probe_outcome(response(200), |s| s.is_success()).unwrap(), Some(true));
+        assert_eq!(probe_outcome(response(400), |s| s.is_success()).unwrap(), Some(false));
+
+        let rate_limited = AppError::typed(ErrorKind::RateLimited { retry_after_secs: Some(5), limit: None, remaining: None });
+        let circuit_open = AppError::typed(ErrorKind::CircuitOpen { host: "nimbus".to_string(), retry_after_secs: 30 });
+        for error in [rate_limited, circuit_open, AppError::from("Request timed out".to_string())] {
+            assert_eq!(probe_outcome(Err(error), |s| s.is_success()).unwrap(), None);
+        }
+
+        let unauthorized = AppError::typed(ErrorKind::Unauthorized { status: 401, token_likely_expired: true, www_authenticate: None });
+        assert!(probe_outcome(Err(unauthorized), |s| s.is_success()).is_err());
+    }
+}
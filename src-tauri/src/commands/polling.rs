@@ -0,0 +1,186 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::commands::circuit::CircuitBreakers;
+use crate::commands::diagnostics::{QueryMetrics, RequestLog};
+use crate::commands::http::{run_odata_query, ODataQueryParams, RequestContext};
+use crate::commands::network::NetworkConfig;
+use crate::commands::odata::{rows_hash, unwrap_odata_value, EntityFieldCache};
+use crate::commands::profiles::ProfileLocks;
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+
+// Polls are sent as `poll-result:<id>`
+const POLL_RESULT_EVENT: &str = "poll-result";
+
+const MIN_POLL_INTERVAL_SECS: u64 = 5;
+
+// Each consecutive failure doubles the wait, up to this long (or the interval itself, if longer)
+const MAX_POLL_BACKOFF: Duration = Duration::from_secs(15 * 60);
+
+struct Poller {
+    profile_name: Option<String>,
+    task: JoinHandle<()>,
+}
+
+/// Background polls started by `start_polling`, by id (managed state)
+#[derive(Default)]
+pub struct Pollers {
+    polls: Mutex<HashMap<String, Poller>>,
+}
+
+impl Pollers {
+    /// Track a new poll, stopping any earlier one with the same id
+    fn insert(&self, id: String, poller: Poller) {
+        if let Some(previous) = self.polls.lock().unwrap_or_else(|e| e.into_inner()).insert(id, poller) {
+            previous.task.abort();
+        }
+    }
+
+    fn stop(&self, id: &str) -> bool {
+        match self.polls.lock().unwrap_or_else(|e| e.into_inner()).remove(id) {
+            Some(poller) => {
+                poller.task.abort();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop every poll running with `profile_name`'s session, e.g. on logout. Returns how many stopped.
+    pub(crate) fn stop_for_profile(&self, profile_name: &str) -> u32 {
+        let mut polls = self.polls.lock().unwrap_or_else(|e| e.into_inner());
+        let before = polls.len();
+        polls.retain(|_, poller| {
+            let matches = poller.profile_name.as_deref() == Some(profile_name);
+            if matches {
+                poller.task.abort();
+            }
+            !matches
+        });
+        (before - polls.len()) as u32
+    }
+}
+
+/// Payload of a `poll-result:<id>` event: the query result, or the error from a failed fetch
+#[derive(Debug, Clone, Serialize)]
+struct PollResult {
+    id: String,
+    // Unix milliseconds when the fetch finished
+    fetched_at: i64,
+    result: Option<Value>,
+    // Hash of the rows, as `odata_result_hash` computes it
+    hash: Option<String>,
+    error: Option<AppError>,
+    consecutive_failures: u32,
+}
+
+/// Wait before the next poll: the interval, doubled for each consecutive failure up to the cap
+fn poll_delay(interval: Duration, failures: u32) -> Duration {
+    if failures == 0 {
+        return interval;
+    }
+    let backoff = interval.saturating_mul(2u32.saturating_pow(failures.min(16)));
+    backoff.min(MAX_POLL_BACKOFF.max(interval))
+}
+
+/// Re-run a query every `interval_seconds` (at least 5) in the background, sending each result as a
+/// `poll-result:<id>` event; the first fetch happens straight away. With `only_on_change`, a result
+/// is only sent when its rows differ from the last one sent. Failures are always sent and back off
+/// the next attempt. Starting an id that's already polling replaces it. Polls using a profile are
+/// stopped when that profile logs out. `deadline_id` in `params` is ignored.
+#[tauri::command]
+pub fn start_polling(
+    app: AppHandle,
+    pollers: State<'_, Pollers>,
+    id: String,
+    mut params: ODataQueryParams,
+    interval_seconds: u64,
+    only_on_change: Option<bool>,
+) -> Result<(), String> {
+    // Event names only allow these characters
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '/' | ':')) {
+        return Err(format!("Invalid poll id '{}': use letters, digits, '-', '_', '/' or ':'", id));
+    }
+    if params.dry_run.unwrap_or(false) {
+        return Err("dry_run isn't supported when polling".to_string());
+    }
+    if interval_seconds < MIN_POLL_INTERVAL_SECS {
+        return Err(format!("interval_seconds must be at least {}", MIN_POLL_INTERVAL_SECS));
+    }
+    params.deadline_id = None;
+
+    let interval = Duration::from_secs(interval_seconds);
+    let only_on_change = only_on_change.unwrap_or(false);
+    let event = format!("{}:{}", POLL_RESULT_EVENT, id);
+    let profile_name = params.profile_name.clone();
+    let poll_id = id.clone();
+
+    // Sync commands run off the async runtime, so spawn through Tauri's handle to it
+    let task = tauri::async_runtime::spawn(async move {
+        let mut last_hash: Option<String> = None;
+        let mut failures = 0u32;
+        loop {
+            let outcome = {
+                let (store, log, circuits, network, locks) = (
+                    app.state::<KeyStore>(),
+                    app.state::<RequestLog>(),
+                    app.state::<CircuitBreakers>(),
+                    app.state::<NetworkConfig>(),
+                    app.state::<ProfileLocks>(),
+                );
+                let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+                let (metrics, fields) = (app.state::<QueryMetrics>(), app.state::<EntityFieldCache>());
+                // A changed response shape is reported like a failed poll, not hashed as "no rows"
+                run_odata_query(&ctx, &metrics, &fields, params.clone()).await.and_then(|json| {
+                    let hash = rows_hash(unwrap_odata_value(&json)?);
+                    Ok((json, hash))
+                })
+            };
+
+            let mut payload = PollResult {
+                id: poll_id.clone(),
+                fetched_at: chrono::Utc::now().timestamp_millis(),
+                result: None,
+                hash: None,
+                error: None,
+                consecutive_failures: 0,
+            };
+            let send = match outcome {
+                Ok((json, hash)) => {
+                    failures = 0;
+                    let changed = last_hash.as_ref() != Some(&hash);
+                    last_hash = Some(hash.clone());
+                    payload.result = Some(json);
+                    payload.hash = Some(hash);
+                    changed || !only_on_change
+                }
+                Err(error) => {
+                    failures += 1;
+                    payload.error = Some(error);
+                    payload.consecutive_failures = failures;
+                    true
+                }
+            };
+            if send {
+                let _ = app.emit(&event, payload);
+            }
+
+            tokio::time::sleep(poll_delay(interval, failures)).await;
+        }
+    });
+
+    pollers.insert(id, Poller { profile_name, task });
+    Ok(())
+}
+
+/// Stop a poll started by `start_polling`. Returns false if no poll had that id.
+#[tauri::command]
+pub fn stop_polling(pollers: State<'_, Pollers>, id: String) -> bool {
+    pollers.stop(&id)
+}
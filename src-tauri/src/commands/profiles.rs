@@ -0,0 +1,421 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tauri::State;
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard, OwnedMutexGuard};
+
+use crate::commands::credentials::{apptoken_key, login_key, profile_key};
+use crate::crypto::{self, SALT_LEN};
+use crate::keystore::KeyStore;
+use crate::types::{AppTokenCredentials, Credentials, LoginCredentials, ProfileSummary};
+
+// Keyring entry holding the JSON array of known profile names (the keyring itself can't be enumerated)
+const PROFILE_INDEX_KEY: &str = "profile-index";
+
+// Keyring entry holding the name of the profile to auto-load on startup
+const DEFAULT_PROFILE_KEY: &str = "default-profile";
+
+// Confirmation string required by delete_all_profiles
+const DELETE_ALL_CONFIRMATION: &str = "DELETE";
+
+// Bundle layout: MAGIC | salt | nonce | ciphertext (see `crypto::seal`, over the JSON payload)
+const BUNDLE_MAGIC: &[u8] = b"NRB1";
+
+/// Everything stored for one profile, as carried inside an export bundle
+#[derive(Debug, Serialize, Deserialize)]
+struct BundledProfile {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    credentials: Option<Credentials>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    login: Option<LoginCredentials>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    apptoken: Option<AppTokenCredentials>,
+}
+
+/// Serializes keyring writes so concurrent UI actions can't interleave read-modify-write cycles
+/// (managed state). Lock order is always profile before index, so the two can't deadlock.
+#[derive(Default)]
+pub struct ProfileLocks {
+    index: AsyncMutex<()>,
+    profiles: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl ProfileLocks {
+    /// Hold while writing or deleting one profile's entries
+    pub(crate) async fn profile(&self, profile_name: &str) -> OwnedMutexGuard<()> {
+        let lock = self.profiles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(profile_name.to_string())
+            .or_default()
+            .clone();
+        lock.lock_owned().await
+    }
+
+    /// Hold while reading and rewriting the profile index or the default profile
+    pub(crate) async fn index(&self) -> MutexGuard<'_, ()> {
+        self.index.lock().await
+    }
+
+    /// Hold every indexed profile's lock (in a fixed order) and then the index, for changes to all
+    /// profiles at once. Returns the indexed names. Saves index a new profile before writing its
+    /// entries, so one that was indexed while the locks were being taken is picked up by retrying.
+    pub(crate) async fn all_profiles(
+        &self,
+        store: &KeyStore,
+    ) -> Result<(Vec<String>, Vec<OwnedMutexGuard<()>>, MutexGuard<'_, ()>), String> {
+        loop {
+            let mut names = read_profile_index(store)?;
+            names.sort();
+            let mut profile_guards = Vec::with_capacity(names.len());
+            for name in &names {
+                profile_guards.push(self.profile(name).await);
+            }
+            let index_guard = self.index().await;
+
+            let mut indexed = read_profile_index(store)?;
+            indexed.sort();
+            if indexed == names {
+                return Ok((names, profile_guards, index_guard));
+            }
+        }
+    }
+}
+
+/// False for a store from before the index existed (or one that never had a profile saved)
+pub(crate) fn has_profile_index(store: &KeyStore) -> Result<bool, String> {
+    Ok(store.try_get(PROFILE_INDEX_KEY)?.is_some())
+}
+
+pub(crate) fn read_default_profile(store: &KeyStore) -> Result<Option<String>, String> {
+    store.try_get(DEFAULT_PROFILE_KEY)
+}
+
+/// Profile names of every stored `profile:`/`login:`/`apptoken:` entry, sorted - only when the store
+/// can be listed (see `KeyStore::list_keys`)
+pub(crate) fn listed_profile_names(store: &KeyStore) -> Option<Vec<String>> {
+    let mut found: Vec<String> = store.list_keys()?
+        .iter()
+        .filter_map(|key| {
+            ["profile:", "login:", "apptoken:"].iter().find_map(|prefix| key.strip_prefix(prefix))
+        })
+        .map(str::to_string)
+        .collect();
+    found.sort();
+    found.dedup();
+    Some(found)
+}
+
+pub(crate) fn read_profile_index(store: &KeyStore) -> Result<Vec<String>, String> {
+    Ok(read_json(store, PROFILE_INDEX_KEY)?.unwrap_or_default())
+}
+
+pub(crate) fn write_profile_index(store: &KeyStore, names: &[String]) -> Result<(), String> {
+    write_json(store, PROFILE_INDEX_KEY, &names)
+}
+
+/// Record a profile name in the index (no-op if already present)
+pub(crate) async fn add_to_profile_index(store: &KeyStore, locks: &ProfileLocks, profile_name: &str) -> Result<(), String> {
+    let _guard = locks.index().await;
+    let mut names = read_profile_index(store)?;
+    if names.iter().any(|n| n == profile_name) {
+        return Ok(());
+    }
+    names.push(profile_name.to_string());
+    write_profile_index(store, &names)
+}
+
+/// True while any of the profile's session/login/app token entries exist
+fn profile_has_entries(store: &KeyStore, profile_name: &str) -> Result<bool, String> {
+    Ok(store.try_get(&profile_key(profile_name))?.is_some()
+        || store.try_get(&login_key(profile_name))?.is_some()
+        || store.try_get(&apptoken_key(profile_name))?.is_some())
+}
+
+/// Drop a profile from the index once none of its session/login/app token entries remain
+pub(crate) async fn prune_profile_index(store: &KeyStore, locks: &ProfileLocks, profile_name: &str) -> Result<(), String> {
+    if profile_has_entries(store, profile_name)? {
+        return Ok(());
+    }
+    let _guard = locks.index().await;
+    let mut names = read_profile_index(store)?;
+    let before = names.len();
+    names.retain(|n| n != profile_name);
+    if names.len() == before {
+        return Ok(());
+    }
+    write_profile_index(store, &names)?;
+
+    // A deleted profile can't stay the default
+    if store.try_get(DEFAULT_PROFILE_KEY)?.as_deref() == Some(profile_name) {
+        store.try_delete(DEFAULT_PROFILE_KEY)?;
+    }
+    Ok(())
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(store: &KeyStore, key: &str) -> Result<Option<T>, String> {
+    match store.try_get(key)? {
+        Some(json) => serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Failed to deserialize '{}': {}", key, e)),
+        None => Ok(None),
+    }
+}
+
+fn write_json<T: Serialize>(store: &KeyStore, key: &str, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| format!("Failed to serialize '{}': {}", key, e))?;
+    store.set(key, &json)
+}
+
+/// Export the named profiles (session, login and app token entries) as a passphrase-encrypted base64 bundle
+#[tauri::command]
+pub async fn export_profiles(
+    store: State<'_, KeyStore>,
+    profile_names: Vec<String>,
+    passphrase: String,
+) -> Result<String, String> {
+    if passphrase.is_empty() {
+        return Err("A passphrase is required to export profiles".to_string());
+    }
+
+    let mut profiles = Vec::with_capacity(profile_names.len());
+    for name in profile_names {
+        let profile = BundledProfile {
+            credentials: read_json(&store, &profile_key(&name))?,
+            login: read_json(&store, &login_key(&name))?,
+            apptoken: read_json(&store, &apptoken_key(&name))?,
+            name,
+        };
+        if profile.credentials.is_none() && profile.login.is_none() && profile.apptoken.is_none() {
+            return Err(format!("Profile '{}' has no stored credentials", profile.name));
+        }
+        profiles.push(profile);
+    }
+
+    let plaintext = serde_json::to_vec(&profiles)
+        .map_err(|e| format!("Failed to serialize profiles: {}", e))?;
+
+    let salt = crypto::random_salt();
+    let sealed = crypto::seal(&crypto::passphrase_cipher(&passphrase, &salt)?, &plaintext)
+        .map_err(|e| format!("Failed to encrypt profile bundle: {}", e))?;
+
+    let mut bundle = Vec::with_capacity(BUNDLE_MAGIC.len() + SALT_LEN + sealed.len());
+    bundle.extend_from_slice(BUNDLE_MAGIC);
+    bundle.extend_from_slice(&salt);
+    bundle.extend_from_slice(&sealed);
+
+    Ok(BASE64.encode(bundle))
+}
+
+/// Import a bundle produced by `export_profiles`, returning the names of the profiles written.
+/// Existing profiles are skipped unless `overwrite` is set.
+#[tauri::command]
+pub async fn import_profiles(
+    store: State<'_, KeyStore>,
+    locks: State<'_, ProfileLocks>,
+    bundle: String,
+    passphrase: String,
+    overwrite: bool,
+) -> Result<Vec<String>, String> {
+    let bytes = BASE64.decode(bundle.trim())
+        .map_err(|e| format!("Profile bundle is not valid base64: {}", e))?;
+
+    let header_len = BUNDLE_MAGIC.len() + SALT_LEN;
+    if bytes.len() <= header_len + crypto::NONCE_LEN || !bytes.starts_with(BUNDLE_MAGIC) {
+        return Err("Not a Monash Nimbus Reports profile bundle".to_string());
+    }
+
+    let salt = &bytes[BUNDLE_MAGIC.len()..header_len];
+    let cipher = crypto::passphrase_cipher(&passphrase, salt)?;
+
+    // A failed tag check means a wrong passphrase or a tampered bundle - either way nothing is imported
+    let plaintext = crypto::open(&cipher, &bytes[header_len..])
+        .map_err(|_| "Profile bundle could not be decrypted (wrong passphrase or corrupted bundle)".to_string())?;
+
+    let profiles: Vec<BundledProfile> = serde_json::from_slice(&plaintext)
+        .map_err(|e| format!("Failed to parse profile bundle: {}", e))?;
+
+    let mut imported = Vec::new();
+    for profile in profiles {
+        let _guard = locks.profile(&profile.name).await;
+        let exists = store.try_get(&profile_key(&profile.name))?.is_some()
+            || store.try_get(&login_key(&profile.name))?.is_some()
+            || store.try_get(&apptoken_key(&profile.name))?.is_some();
+        if exists && !overwrite {
+            continue;
+        }
+        // Indexed first, so a master key rotation waiting on the profile locks includes these entries
+        add_to_profile_index(&store, &locks, &profile.name).await?;
+
+        if let Some(ref credentials) = profile.credentials {
+            write_json(&store, &profile_key(&profile.name), credentials)?;
+        }
+        if let Some(ref login) = profile.login {
+            write_json(&store, &login_key(&profile.name), login)?;
+        }
+        if let Some(ref apptoken) = profile.apptoken {
+            write_json(&store, &apptoken_key(&profile.name), apptoken)?;
+        }
+        imported.push(profile.name);
+    }
+
+    Ok(imported)
+}
+
+/// List the names of all profiles with stored credentials
+#[tauri::command]
+pub async fn list_profiles(store: State<'_, KeyStore>) -> Result<Vec<String>, String> {
+    read_profile_index(&store)
+}
+
+/// Re-derive the profile index from the stored entries, e.g. after a crash left it out of sync.
+/// Where the store can be listed, every `profile:`/`login:`/`apptoken:` entry gets a name in the
+/// index; otherwise each indexed name is kept only if one of its entries still loads.
+/// Existing names keep their order. Returns the rebuilt list.
+#[tauri::command]
+pub async fn rebuild_profile_index(
+    store: State<'_, KeyStore>,
+    locks: State<'_, ProfileLocks>,
+) -> Result<Vec<String>, String> {
+    let _guard = locks.index().await;
+    let indexed = read_profile_index(&store)?;
+
+    let names = match listed_profile_names(&store) {
+        Some(found) => {
+            let mut names: Vec<String> = indexed.into_iter().filter(|n| found.contains(n)).collect();
+            for name in found {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            names
+        }
+        None => {
+            let mut names = Vec::with_capacity(indexed.len());
+            for name in indexed {
+                if profile_has_entries(&store, &name)? && !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            names
+        }
+    };
+
+    write_profile_index(&store, &names)
+        .map_err(|e| format!("Failed to save profile index: {}", e))?;
+
+    if let Some(default) = store.try_get(DEFAULT_PROFILE_KEY)? {
+        if !names.contains(&default) {
+            store.try_delete(DEFAULT_PROFILE_KEY)?;
+        }
+    }
+    Ok(names)
+}
+
+/// Non-secret view of a stored profile (no auth or app token) for the profile list
+#[tauri::command]
+pub async fn get_profile_summary(store: State<'_, KeyStore>, profile_name: String) -> Result<ProfileSummary, String> {
+    let credentials: Credentials = read_json(&store, &profile_key(&profile_name))?
+        .ok_or_else(|| format!("No stored credentials for profile '{}'", profile_name))?;
+
+    let username = match credentials.username {
+        Some(username) => Some(username),
+        None => read_json::<LoginCredentials>(&store, &login_key(&profile_name))?
+            .map(|login| login.username),
+    };
+
+    Ok(ProfileSummary {
+        name: profile_name,
+        base_url: credentials.base_url,
+        auth_mode: credentials.auth_mode,
+        username,
+        has_session: credentials.auth_token.is_some() || credentials.app_token.is_some(),
+        issued_at: credentials.issued_at,
+    })
+}
+
+/// Mark a stored profile as the one to load automatically on startup
+#[tauri::command]
+pub async fn set_default_profile(
+    store: State<'_, KeyStore>,
+    locks: State<'_, ProfileLocks>,
+    profile_name: String,
+) -> Result<(), String> {
+    let _guard = locks.index().await;
+    if !read_profile_index(&store)?.contains(&profile_name) {
+        return Err(format!("Profile '{}' does not exist", profile_name));
+    }
+    store.set(DEFAULT_PROFILE_KEY, &profile_name)
+        .map_err(|e| format!("Failed to save default profile: {}", e))
+}
+
+/// The startup profile, if one has been chosen
+#[tauri::command]
+pub async fn get_default_profile(store: State<'_, KeyStore>) -> Result<Option<String>, String> {
+    read_default_profile(&store)
+}
+
+/// Which entries a profile has, and whether deleting them would succeed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeleteCheck {
+    pub can_delete: bool,
+    pub session: bool,
+    pub login: bool,
+    pub apptoken: bool,
+    // Why the delete would fail, when it would
+    pub reason: Option<String>,
+}
+
+/// Dry run of deleting a profile for the credential UI: looks up its session, login and app token
+/// entries and checks the store takes writes (via a throwaway sentinel entry), changing nothing of
+/// the profile's. `can_delete` is true only when there is something to delete and the store is writable.
+#[tauri::command]
+pub async fn can_delete_profile(store: State<'_, KeyStore>, profile_name: String) -> Result<DeleteCheck, String> {
+    let session = store.try_get(&profile_key(&profile_name))?.is_some();
+    let login = store.try_get(&login_key(&profile_name))?.is_some();
+    let apptoken = store.try_get(&apptoken_key(&profile_name))?.is_some();
+
+    let reason = if !(session || login || apptoken) {
+        Some(format!("Profile '{}' has no stored entries", profile_name))
+    } else {
+        store.probe_writable()
+            .err()
+            .map(|e| format!("Credential store is not writable: {}", e))
+    };
+
+    Ok(DeleteCheck { can_delete: reason.is_none(), session, login, apptoken, reason })
+}
+
+/// Factory reset: remove the session, login and app token entries of every indexed profile,
+/// then clear the index. Only runs when `confirm` is exactly "DELETE". Returns the number of profiles removed.
+#[tauri::command]
+pub async fn delete_all_profiles(
+    store: State<'_, KeyStore>,
+    locks: State<'_, ProfileLocks>,
+    confirm: String,
+) -> Result<u32, String> {
+    if confirm != DELETE_ALL_CONFIRMATION {
+        return Err(format!("Refusing to delete all profiles: confirm must be \"{}\"", DELETE_ALL_CONFIRMATION));
+    }
+
+    let (names, _profile_guards, _index_guard) = locks.all_profiles(&store).await?;
+    for name in &names {
+        // Entries that are already gone are fine - the goal is that none remain
+        store.try_delete(&profile_key(name))
+            .map_err(|e| format!("Failed to delete credentials for '{}': {}", name, e))?;
+        store.try_delete(&login_key(name))
+            .map_err(|e| format!("Failed to delete login credentials for '{}': {}", name, e))?;
+        store.try_delete(&apptoken_key(name))
+            .map_err(|e| format!("Failed to delete app token credentials for '{}': {}", name, e))?;
+    }
+
+    store.try_delete(PROFILE_INDEX_KEY)
+        .map_err(|e| format!("Failed to clear profile index: {}", e))?;
+    store.try_delete(DEFAULT_PROFILE_KEY)
+        .map_err(|e| format!("Failed to clear default profile: {}", e))?;
+
+    Ok(names.len() as u32)
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::http::ODataQueryParams;
+
+// File in the app data directory holding the saved query library
+const QUERY_LIBRARY_FILE: &str = "saved_queries.json";
+
+const MAX_QUERY_NAME_CHARS: usize = 100;
+
+/// A named query as stored in the library
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedQuery {
+    pub params: ODataQueryParams,
+    // Unix seconds when the query was last saved
+    pub saved_at: i64,
+}
+
+/// The whole library, keyed by query name (also the export/import format)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryLibrary {
+    #[serde(default)]
+    pub queries: BTreeMap<String, SavedQuery>,
+}
+
+/// Held while saving, deleting or importing, so two changes can't both read the library and
+/// the second write drop the first's query (managed state)
+#[derive(Default)]
+pub struct QueryLibraryLock {
+    lock: Mutex<()>,
+}
+
+impl QueryLibraryLock {
+    fn hold(&self) -> std::sync::MutexGuard<'_, ()> {
+        self.lock.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn library_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join(QUERY_LIBRARY_FILE))
+}
+
+fn read_library(path: &PathBuf) -> Result<QueryLibrary, String> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse query library '{}': {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(QueryLibrary::default()),
+        Err(e) => Err(format!("Failed to read query library '{}': {}", path.display(), e)),
+    }
+}
+
+/// Write via a temporary file so a crash mid-write can't leave a truncated library
+fn write_library(path: &PathBuf, library: &QueryLibrary) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(library)
+        .map_err(|e| format!("Failed to serialize query library: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)
+        .map_err(|e| format!("Failed to write query library: {}", e))?;
+    std::fs::rename(&tmp, path)
+        .map_err(|e| format!("Failed to save query library: {}", e))
+}
+
+fn query_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Query name can't be empty".to_string());
+    }
+    if name.chars().count() > MAX_QUERY_NAME_CHARS {
+        return Err(format!("Query name is longer than {} characters", MAX_QUERY_NAME_CHARS));
+    }
+    Ok(name.to_string())
+}
+
+/// Keep only the target and the OData options: no auth, profile or per-run transport settings
+fn odata_options_only(params: ODataQueryParams) -> ODataQueryParams {
+    ODataQueryParams {
+        base_url: params.base_url,
+        entity: params.entity,
+        top: params.top,
+        skip: params.skip,
+        filter: params.filter,
+        select: params.select,
+        select_all: params.select_all,
+        expand: params.expand,
+        expand_specs: params.expand_specs,
+        orderby: params.orderby,
+        count: params.count,
+        compute: params.compute,
+        skiptoken: params.skiptoken,
+        format: params.format,
+        ..Default::default()
+    }
+}
+
+/// Save (or replace) a named query. Auth fields, the profile and transport settings are dropped,
+/// so a loaded query needs auth supplied again before it's run.
+#[tauri::command]
+pub fn save_query(
+    app: AppHandle,
+    library_lock: State<'_, QueryLibraryLock>,
+    name: String,
+    params: ODataQueryParams,
+) -> Result<(), String> {
+    let name = query_name(&name)?;
+    let path = library_path(&app)?;
+    let _guard = library_lock.hold();
+    let mut library = read_library(&path)?;
+    library.queries.insert(name, SavedQuery {
+        params: odata_options_only(params),
+        saved_at: chrono::Utc::now().timestamp(),
+    });
+    write_library(&path, &library)
+}
+
+#[tauri::command]
+pub fn load_query(app: AppHandle, name: String) -> Result<ODataQueryParams, String> {
+    let library = read_library(&library_path(&app)?)?;
+    library.queries
+        .get(name.trim())
+        .map(|query| query.params.clone())
+        .ok_or_else(|| format!("No saved query named '{}'", name))
+}
+
+/// Names of every saved query, alphabetically
+#[tauri::command]
+pub fn list_queries(app: AppHandle) -> Result<Vec<String>, String> {
+    Ok(read_library(&library_path(&app)?)?.queries.into_keys().collect())
+}
+
+/// Remove a saved query; returns false if there was none by that name
+#[tauri::command]
+pub fn delete_query(app: AppHandle, library_lock: State<'_, QueryLibraryLock>, name: String) -> Result<bool, String> {
+    let path = library_path(&app)?;
+    let _guard = library_lock.hold();
+    let mut library = read_library(&path)?;
+    if library.queries.remove(name.trim()).is_none() {
+        return Ok(false);
+    }
+    write_library(&path, &library)?;
+    Ok(true)
+}
+
+/// Write the whole query library to `path` as JSON. Returns the number of queries exported.
+#[tauri::command]
+pub fn export_queries(app: AppHandle, path: String) -> Result<u32, String> {
+    let library = read_library(&library_path(&app)?)?;
+    let json = serde_json::to_string_pretty(&library)
+        .map_err(|e| format!("Failed to serialize query library: {}", e))?;
+    std::fs::write(&path, json)
+        .map_err(|e| format!("Failed to write '{}': {}", path, e))?;
+    Ok(library.queries.len() as u32)
+}
+
+/// Merge a library exported by `export_queries` into this one. Queries whose name already exists
+/// are skipped unless `overwrite` is set. Returns the names imported.
+#[tauri::command]
+pub fn import_queries(
+    app: AppHandle,
+    library_lock: State<'_, QueryLibraryLock>,
+    path: String,
+    overwrite: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    let incoming: QueryLibrary = serde_json::from_str(&json)
+        .map_err(|e| format!("'{}' is not a query library export: {}", path, e))?;
+
+    let library_path = library_path(&app)?;
+    let _guard = library_lock.hold();
+    let mut library = read_library(&library_path)?;
+    let overwrite = overwrite.unwrap_or(false);
+    let mut imported = Vec::new();
+
+    for (name, query) in incoming.queries {
+        let name = query_name(&name)?;
+        if library.queries.contains_key(&name) && !overwrite {
+            continue;
+        }
+        // An export edited by hand could carry auth; strip it like save_query does
+        let query = SavedQuery { params: odata_options_only(query.params), saved_at: query.saved_at };
+        library.queries.insert(name.clone(), query);
+        imported.push(name);
+    }
+
+    if !imported.is_empty() {
+        write_library(&library_path, &library)?;
+    }
+    Ok(imported)
+}
@@ -0,0 +1,82 @@
+use reqwest::StatusCode;
+
+/// Whether a failed request is worth sending again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RetryClass {
+    // Transient: the same request may well succeed shortly
+    Retryable,
+    // The request itself is wrong (bad filter, no permission, ...); retrying only delays the error
+    Terminal,
+}
+
+/// 408 Request Timeout, 429 Too Many Requests and every 5xx are retryable; any other 4xx is terminal.
+/// Success and redirect statuses are not failures and are classed terminal so they're never resent.
+pub(crate) fn classify_status(status: StatusCode) -> RetryClass {
+    match status {
+        StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS => RetryClass::Retryable,
+        status if status.is_server_error() => RetryClass::Retryable,
+        _ => RetryClass::Terminal,
+    }
+}
+
+/// Timeouts and connection failures (DNS, refused, reset) are retryable. Errors building the
+/// request, following redirects or decoding the body would fail the same way again.
+pub(crate) fn classify_error(error: &reqwest::Error) -> RetryClass {
+    if let Some(status) = error.status() {
+        return classify_status(status);
+    }
+    if error.is_timeout() || error.is_connect() || error.is_request() {
+        RetryClass::Retryable
+    } else {
+        RetryClass::Terminal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn timeouts_rate_limits_and_server_errors_are_retryable() {
+        for code in [408, 429, 500, 502, 503, 504] {
+            assert_eq!(classify_status(StatusCode::from_u16(code).unwrap()), RetryClass::Retryable, "{}", code);
+        }
+    }
+
+    #[test]
+    fn other_client_errors_are_terminal() {
+        for code in [400, 401, 403, 404, 405, 409, 410, 422] {
+            assert_eq!(classify_status(StatusCode::from_u16(code).unwrap()), RetryClass::Terminal, "{}", code);
+        }
+        // Not failures at all, so never resent
+        assert_eq!(classify_status(StatusCode::OK), RetryClass::Terminal);
+        assert_eq!(classify_status(StatusCode::FOUND), RetryClass::Terminal);
+    }
+
+    #[test]
+    fn a_request_that_cant_be_built_is_terminal() {
+        let error = reqwest::Client::new().get("not a url").build().unwrap_err();
+        assert_eq!(classify_error(&error), RetryClass::Terminal);
+    }
+
+    #[tokio::test]
+    async fn refused_connections_are_retryable() {
+        // Bind then drop, so nothing is listening on the port
+        let addr = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let error = reqwest::get(format!("http://{}/", addr)).await.unwrap_err();
+        assert_eq!(classify_error(&error), RetryClass::Retryable);
+    }
+
+    #[tokio::test]
+    async fn timeouts_are_retryable() {
+        // Accepts the connection but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = reqwest::Client::builder().timeout(Duration::from_millis(100)).build().unwrap();
+        let error = client.get(format!("http://{}/", addr)).send().await.unwrap_err();
+        assert!(error.is_timeout());
+        assert_eq!(classify_error(&error), RetryClass::Retryable);
+        drop(listener);
+    }
+}
@@ -0,0 +1,186 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::commands::odata::unwrap_odata_value;
+
+/// A column's type as seen in the sampled rows. Dates are recognised from ISO 8601 strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnType {
+    Boolean,
+    Integer,
+    Number,
+    Date,
+    DateTime,
+    String,
+    Object,
+    Array,
+    // Only nulls in the sample
+    Unknown,
+}
+
+/// One field of the sampled rows: its type, whether it was ever null or missing, and a non-null example
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+    pub example: Value,
+    // Rows disagreed on the type, so column_type fell back to string
+    pub mixed: bool,
+}
+
+fn value_type(value: &Value) -> Option<ColumnType> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(_) => ColumnType::Boolean,
+        Value::Number(n) if n.is_f64() => ColumnType::Number,
+        Value::Number(_) => ColumnType::Integer,
+        Value::String(s) if NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok() => ColumnType::Date,
+        Value::String(s) if DateTime::parse_from_rfc3339(s).is_ok()
+            || NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f").is_ok() => ColumnType::DateTime,
+        Value::String(_) => ColumnType::String,
+        Value::Array(_) => ColumnType::Array,
+        Value::Object(_) => ColumnType::Object,
+    })
+}
+
+/// The type covering both, or None when they don't reconcile. Integers widen to numbers and dates to date-times.
+fn merge_types(a: ColumnType, b: ColumnType) -> Option<ColumnType> {
+    use ColumnType::*;
+    match (a, b) {
+        (a, b) if a == b => Some(a),
+        (Unknown, other) | (other, Unknown) => Some(other),
+        (Integer, Number) | (Number, Integer) => Some(Number),
+        (Date, DateTime) | (DateTime, Date) => Some(DateTime),
+        _ => None,
+    }
+}
+
+/// Infer column types from the first `sample_size` rows of an OData result (`{ value: [...] }` or a bare array),
+/// for building grid columns without per-entity metadata. Columns come in the order fields first appear in,
+/// taking each row's fields alphabetically (serde_json keeps object keys sorted, not in response order).
+/// A field missing from some rows counts as nullable; annotations such as `@odata.etag` are skipped.
+#[tauri::command]
+pub fn infer_schema(value: Value, sample_size: usize) -> Result<Vec<ColumnSchema>, String> {
+    if sample_size == 0 {
+        return Err("sample_size must be at least 1".to_string());
+    }
+    let rows = unwrap_odata_value(&value)?;
+    let sample = &rows[..rows.len().min(sample_size)];
+
+    let mut columns: Vec<ColumnSchema> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    let mut seen_in = Vec::new();
+
+    for (i, row) in sample.iter().enumerate() {
+        let Value::Object(row) = row else {
+            return Err(format!("Row {} is not an object", i));
+        };
+        for (name, cell) in row {
+            if name.contains('@') {
+                continue;
+            }
+            let position = *index.entry(name.clone()).or_insert_with(|| {
+                columns.push(ColumnSchema {
+                    name: name.clone(),
+                    column_type: ColumnType::Unknown,
+                    nullable: false,
+                    example: Value::Null,
+                    mixed: false,
+                });
+                seen_in.push(0usize);
+                columns.len() - 1
+            });
+            seen_in[position] += 1;
+
+            let column = &mut columns[position];
+            let Some(cell_type) = value_type(cell) else {
+                column.nullable = true;
+                continue;
+            };
+            if column.example.is_null() {
+                column.example = cell.clone();
+            }
+            if !column.mixed {
+                match merge_types(column.column_type, cell_type) {
+                    Some(merged) => column.column_type = merged,
+                    None => {
+                        column.column_type = ColumnType::String;
+                        column.mixed = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // Missing from some rows counts the same as null
+    for (column, seen) in columns.iter_mut().zip(seen_in) {
+        if seen < sample.len() {
+            column.nullable = true;
+        }
+    }
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column<'a>(columns: &'a [ColumnSchema], name: &str) -> &'a ColumnSchema {
+        columns.iter().find(|c| c.name == name).unwrap()
+    }
+
+    #[test]
+    fn columns_are_ordered_by_first_appearance_then_alphabetically() {
+        let rows = json!({ "value": [{ "Zone": 1, "Id": 2 }, { "Id": 3, "Added": true }] });
+        let names: Vec<String> = infer_schema(rows, 10).unwrap().into_iter().map(|c| c.name).collect();
+        assert_eq!(names, ["Id", "Zone", "Added"]);
+    }
+
+    #[test]
+    fn disagreeing_rows_fall_back_to_string() {
+        let columns = infer_schema(json!([{ "Code": 1 }, { "Code": "A1" }, { "Code": true }]), 10).unwrap();
+        let code = column(&columns, "Code");
+        assert_eq!(code.column_type, ColumnType::String);
+        assert!(code.mixed);
+        assert_eq!(code.example, json!(1));
+    }
+
+    #[test]
+    fn integers_widen_to_numbers_and_dates_to_date_times() {
+        let rows = json!([
+            { "Hours": 7, "Start": "2024-01-31" },
+            { "Hours": 7.5, "Start": "2024-01-31T09:00:00" },
+        ]);
+        let columns = infer_schema(rows, 10).unwrap();
+        assert_eq!(column(&columns, "Hours").column_type, ColumnType::Number);
+        assert_eq!(column(&columns, "Start").column_type, ColumnType::DateTime);
+        assert!(columns.iter().all(|c| !c.mixed && !c.nullable));
+    }
+
+    #[test]
+    fn null_and_missing_fields_are_nullable() {
+        let rows = json!([{ "Id": 1, "Note": null }, { "Id": 2, "Note": "late", "Extra": 5 }, { "Id": 3 }]);
+        let columns = infer_schema(rows, 10).unwrap();
+        let note = column(&columns, "Note");
+        assert_eq!((note.column_type, note.nullable, &note.example), (ColumnType::String, true, &json!("late")));
+        assert!(column(&columns, "Extra").nullable);
+        assert!(!column(&columns, "Id").nullable);
+
+        let only_nulls = infer_schema(json!([{ "Gap": null }]), 10).unwrap();
+        assert_eq!((only_nulls[0].column_type, only_nulls[0].nullable), (ColumnType::Unknown, true));
+    }
+
+    #[test]
+    fn only_the_sample_is_read_and_annotations_are_skipped() {
+        let rows = json!([{ "Id": 1, "@odata.etag": "W/1" }, { "Id": "x" }]);
+        let columns = infer_schema(rows, 1).unwrap();
+        assert_eq!(columns.len(), 1);
+        assert_eq!(column(&columns, "Id").column_type, ColumnType::Integer);
+        assert!(infer_schema(json!([]), 0).is_err());
+    }
+}
@@ -0,0 +1,588 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::Semaphore;
+
+use crate::commands::circuit::CircuitBreakers;
+use crate::commands::credentials::{apptoken_key, login_key, profile_key};
+use crate::commands::diagnostics::RequestLog;
+use crate::commands::filter::quoted_literal;
+use crate::commands::http::{
+    build_client, build_headers, normalize_base_url, read_body_limited, send_logged, send_with_refresh, AuthFields,
+    RequestContext,
+};
+use crate::commands::network::NetworkConfig;
+use crate::commands::odata::{odata_service_root, unwrap_odata_value, DEFAULT_PROBE_ENTITY};
+use crate::commands::polling::Pollers;
+use crate::commands::profiles::{prune_profile_index, read_profile_index, ProfileLocks};
+use crate::error::{AppError, ErrorKind};
+use crate::keystore::KeyStore;
+use crate::types::{AppTokenCredentials, Credentials, LoginCredentials};
+
+// Refresh a session this many seconds before its recorded expiry
+const REFRESH_THRESHOLD_SECS: i64 = 120;
+
+// Nimbus endpoint that invalidates a credential-mode session token
+const LOGOUT_ENDPOINT: &str = "/RESTApi/Authenticate?task=Logout";
+
+// check_all_sessions probes at most this many profiles at once
+const MAX_CONCURRENT_SESSION_CHECKS: usize = 4;
+
+// A dashboard re-render within this long gets the previous check_all_sessions results
+const SESSION_CHECK_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Outcome of `logout`: the local session is always removed, `warning` explains a failed server-side revoke
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogoutResult {
+    pub revoked: bool,
+    pub warning: Option<String>,
+}
+
+/// Claims read from a JWT payload by `inspect_token`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenClaims {
+    pub exp: Option<i64>,
+    pub iat: Option<i64>,
+    pub sub: Option<String>,
+    // The full decoded payload
+    pub raw: Value,
+}
+
+/// Outcome of `touch_session`, and one profile's entry in `check_all_sessions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionStatus {
+    pub profile: String,
+    pub valid: bool,
+    // Seconds until the session expires (negative once past), from the stored expiry or the token's `exp`
+    pub expires_in: Option<i64>,
+    // Unix seconds when the check ran
+    pub last_checked: i64,
+    // Why the check itself failed (no stored session, server unreachable...) - only set by check_all_sessions
+    pub error: Option<String>,
+}
+
+/// The signed-in user behind a profile's session, from `whoami`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserIdentity {
+    pub user_id: i32,
+    pub username: String,
+    pub display_name: String,
+    // Descriptions of the user's active security roles, sorted and without duplicates
+    pub roles: Vec<String>,
+}
+
+/// `whoami` results by profile, each tied to the session token it was looked up with (managed state).
+/// A refreshed token no longer matches, so the identity is looked up again.
+#[derive(Default)]
+pub struct IdentityCache {
+    identities: Mutex<HashMap<String, (Option<String>, UserIdentity)>>,
+}
+
+impl IdentityCache {
+    fn get(&self, profile_name: &str, token: &Option<String>) -> Option<UserIdentity> {
+        let identities = self.identities.lock().unwrap_or_else(|e| e.into_inner());
+        identities.get(profile_name)
+            .filter(|(cached_token, _)| cached_token == token)
+            .map(|(_, identity)| identity.clone())
+    }
+
+    fn insert(&self, profile_name: &str, token: Option<String>, identity: UserIdentity) {
+        self.identities.lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(profile_name.to_string(), (token, identity));
+    }
+
+    pub(crate) fn invalidate(&self, profile_name: &str) {
+        self.identities.lock().unwrap_or_else(|e| e.into_inner()).remove(profile_name);
+    }
+}
+
+/// The token that identifies a stored session: the session token, or the app token in app token mode
+pub(crate) fn session_token(credentials: &Credentials) -> Option<String> {
+    credentials.auth_token.clone().or_else(|| credentials.app_token.clone())
+}
+
+/// The last `check_all_sessions` results, reused for SESSION_CHECK_CACHE_TTL (managed state)
+#[derive(Default)]
+pub struct SessionStatusCache {
+    last: Mutex<Option<(Instant, Vec<SessionStatus>)>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthenticateResponse {
+    #[serde(rename = "UserID")]
+    user_id: Option<i32>,
+    #[serde(rename = "AuthenticationToken")]
+    authentication_token: Option<String>,
+    #[serde(rename = "Authenticated")]
+    authenticated: Option<bool>,
+}
+
+fn load_json<T: serde::de::DeserializeOwned>(store: &KeyStore, key: &str, what: &str) -> Result<T, String> {
+    let json = store.get(key)
+        .map_err(|e| format!("Failed to load {} from keyring: {}", what, e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to deserialize {}: {}", what, e))
+}
+
+/// Load a profile's stored session credentials as they are, without refreshing them
+pub(crate) fn load_credentials(store: &KeyStore, profile_name: &str) -> Result<Credentials, String> {
+    load_json(store, &profile_key(profile_name), "credentials")
+}
+
+fn expires_soon(credentials: &Credentials) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    credentials.expires_at.is_some_and(|expires_at| expires_at - now <= REFRESH_THRESHOLD_SECS)
+}
+
+/// Load a profile's session credentials, re-authenticating first if they are about to expire
+pub(crate) async fn load_fresh_credentials(
+    ctx: &RequestContext<'_>,
+    profile_name: &str,
+) -> Result<Credentials, AppError> {
+    let credentials = load_credentials(ctx.store, profile_name)?;
+    if !expires_soon(&credentials) {
+        return Ok(credentials);
+    }
+    reauthenticate(ctx, profile_name, expires_soon).await
+}
+
+/// Use the stored profile's auth when a profile is named, otherwise the caller-supplied fields
+pub(crate) async fn resolve_auth(
+    ctx: &RequestContext<'_>,
+    profile_name: Option<&str>,
+    passed: AuthFields,
+) -> Result<AuthFields, AppError> {
+    match profile_name {
+        Some(profile_name) => {
+            let credentials = load_fresh_credentials(ctx, profile_name).await?;
+            // The stored profile supplies the tokens; the caller still chooses how they're presented
+            Ok(AuthFields { scheme: passed.scheme, ..AuthFields::from(&credentials) })
+        }
+        None => Ok(passed),
+    }
+}
+
+/// Replay the stored login or app token credentials against Nimbus and persist the new session.
+/// Holds the profile lock throughout, and `still_needed` is checked against the stored session once
+/// the lock is held, so callers that queued behind another refresh reuse its session instead of
+/// authenticating again.
+pub(crate) async fn reauthenticate(
+    ctx: &RequestContext<'_>,
+    profile_name: &str,
+    still_needed: impl FnOnce(&Credentials) -> bool,
+) -> Result<Credentials, AppError> {
+    let store = ctx.store;
+    let _guard = ctx.locks.profile(profile_name).await;
+    let mut credentials = load_credentials(store, profile_name)?;
+    if !still_needed(&credentials) {
+        return Ok(credentials);
+    }
+    let base_url = normalize_base_url(&credentials.base_url)?;
+
+    let (url, body) = if credentials.auth_mode == "apptoken" {
+        let apptoken: AppTokenCredentials = load_json(store, &apptoken_key(profile_name), "app token credentials")?;
+        (
+            format!("{}/RESTApi/Authenticate?task=AuthenticateApp", base_url),
+            json!({
+                "AppToken": apptoken.app_token,
+                "Username": apptoken.username,
+                "UsernameSource": "Fixed",
+                "AppName": "MonashNimbusReports",
+            }),
+        )
+    } else {
+        let login: LoginCredentials = load_json(store, &login_key(profile_name), "login credentials")?;
+        if login.password.is_empty() {
+            return Err(format!("No stored password for profile '{}' - sign in again", profile_name).into());
+        }
+        (
+            format!("{}/RESTApi/Authenticate", base_url),
+            json!({ "Username": login.username, "Password": login.password }),
+        )
+    };
+
+    let client = build_client(ctx.network, Some(30), None, None)?;
+    let headers = build_headers(None, &AuthFields::default())?;
+    let response = send_logged(ctx, client.post(&url).headers(headers).json(&body), "Session refresh").await?;
+
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(format!("Session refresh failed with status {}: {}", status.as_u16(), text).into());
+    }
+
+    let auth: AuthenticateResponse = serde_json::from_str(&text)
+        .map_err(|e| format!("Failed to parse authentication response: {}", e))?;
+
+    if credentials.auth_mode == "apptoken" {
+        if auth.authenticated != Some(true) {
+            return Err("App Token authentication failed - not authenticated".to_string().into());
+        }
+        // App token mode keeps using the app token itself; only the user id may change
+        if auth.user_id.is_some() {
+            credentials.user_id = auth.user_id;
+        }
+    } else {
+        let (Some(user_id), Some(token)) = (auth.user_id, auth.authentication_token) else {
+            return Err("Invalid response - missing UserID or AuthenticationToken".to_string().into());
+        };
+        credentials.user_id = Some(user_id);
+        credentials.auth_token = Some(token);
+    }
+    // The token's own `exp` when it has one, otherwise the same lifetime the previous session had
+    let now = chrono::Utc::now().timestamp();
+    let previous_ttl = credentials.issued_at.zip(credentials.expires_at).map(|(issued, expires)| expires - issued);
+    credentials.issued_at = Some(now);
+    credentials.expires_at = session_token(&credentials)
+        .and_then(|token| inspect_token(token).ok()?.exp)
+        .or(previous_ttl.map(|ttl| now + ttl));
+
+    let credentials_json = serde_json::to_string(&credentials)
+        .map_err(|e| format!("Failed to serialize credentials: {}", e))?;
+    store.set(&profile_key(profile_name), &credentials_json)
+        .map_err(|e| format!("Failed to save credentials to keyring: {}", e))?;
+
+    Ok(credentials)
+}
+
+/// Re-authenticate a stored profile and return its fresh session credentials
+#[tauri::command]
+pub async fn refresh_session(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    identities: State<'_, IdentityCache>,
+    profile_name: String,
+) -> Result<Credentials, AppError> {
+    identities.invalidate(&profile_name);
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    reauthenticate(&ctx, &profile_name, |_| true).await
+}
+
+/// The `User` query for the profile's own row: by id when known, otherwise by username.
+/// The filter is encoded once as a whole, so the username literal itself isn't pre-encoded.
+fn user_lookup_url(odata_base: &str, credentials: &Credentials) -> Option<String> {
+    let user_filter = match (credentials.user_id, &credentials.username) {
+        (Some(user_id), _) => format!("Id eq {}", user_id),
+        (None, Some(username)) => format!("Username eq {}", quoted_literal(username)),
+        (None, None) => return None,
+    };
+    Some(format!(
+        "{}/User?$select=Id,Username,Forename,Surname&$filter={}&$top=1",
+        odata_base, urlencoding::encode(&user_filter)
+    ))
+}
+
+/// Ask Nimbus to invalidate a credential-mode session token
+async fn revoke_session(ctx: &RequestContext<'_>, credentials: &Credentials) -> Result<(), AppError> {
+    let url = format!("{}{}", normalize_base_url(&credentials.base_url)?, LOGOUT_ENDPOINT);
+    let client = build_client(ctx.network, Some(10), None, None)?;
+    let headers = build_headers(None, &AuthFields::from(credentials))?;
+
+    let response = send_logged(ctx, client.post(&url).headers(headers), "Session revoke request").await?;
+
+    if !response.status().is_success() {
+        return Err(format!("Session revoke failed with status {}", response.status().as_u16()).into());
+    }
+    Ok(())
+}
+
+/// Stop the profile's background polls, revoke its session on the server, then delete the local session entry.
+/// Login and app token credentials are kept so the user can sign straight back in.
+/// If the revoke call fails (e.g. offline) the local session is still removed and a warning returned.
+#[tauri::command]
+pub async fn logout(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    pollers: State<'_, Pollers>,
+    identities: State<'_, IdentityCache>,
+    profile_name: String,
+) -> Result<LogoutResult, String> {
+    // Background polls would keep using (and refreshing) the session being removed
+    pollers.stop_for_profile(&profile_name);
+    identities.invalidate(&profile_name);
+
+    let _guard = locks.profile(&profile_name).await;
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    let key = profile_key(&profile_name);
+    let Some(json) = store.try_get(&key)? else {
+        return Ok(LogoutResult { revoked: false, warning: None });
+    };
+    let credentials: Credentials = serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to deserialize credentials: {}", e))?;
+
+    // App tokens are long-lived and managed in Nimbus admin, so there's no session to revoke
+    let result = match credentials.auth_token {
+        Some(_) if credentials.auth_mode != "apptoken" => match revoke_session(&ctx, &credentials).await {
+            Ok(()) => LogoutResult { revoked: true, warning: None },
+            Err(e) => LogoutResult { revoked: false, warning: Some(e.to_string()) },
+        },
+        _ => LogoutResult { revoked: false, warning: None },
+    };
+
+    store.try_delete(&key)
+        .map_err(|e| format!("Failed to delete credentials from keyring: {}", e))?;
+    prune_profile_index(&store, &locks, &profile_name).await?;
+
+    Ok(result)
+}
+
+/// Decode a JWT's payload to show its claims (expiry, issued-at, subject).
+/// This does NOT verify the signature - use it for display and debugging only, never to trust a token.
+#[tauri::command]
+pub fn inspect_token(token: String) -> Result<TokenClaims, String> {
+    let token = token.trim();
+    let token = token.strip_prefix("Bearer ").unwrap_or(token).trim();
+
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 3 || parts.iter().any(|p| p.is_empty()) {
+        return Err("Token is not a JWT (expected three dot-separated parts)".to_string());
+    }
+
+    // Base64url without padding per the JWT spec, but tolerate padded tokens
+    let payload = URL_SAFE_NO_PAD.decode(parts[1].trim_end_matches('='))
+        .map_err(|e| format!("Token payload is not valid base64url: {}", e))?;
+    let raw: Value = serde_json::from_slice(&payload)
+        .map_err(|e| format!("Token payload is not JSON: {}", e))?;
+    if !raw.is_object() {
+        return Err("Token payload is not a JSON object".to_string());
+    }
+
+    // Numeric dates are seconds; some issuers send them as floats
+    let numeric_date = |claim: &str| raw.get(claim).and_then(|v| v.as_i64().or_else(|| v.as_f64().map(|f| f as i64)));
+
+    Ok(TokenClaims {
+        exp: numeric_date("exp"),
+        iat: numeric_date("iat"),
+        sub: raw.get("sub").and_then(Value::as_str).map(str::to_string),
+        raw,
+    })
+}
+
+/// Check whether a profile's stored session is still accepted, for a background heartbeat.
+/// Sends a `$top=0` OData query with the session exactly as stored: it is never refreshed or
+/// rewritten, so a rejected session reports `valid: false` and is left for the caller to act on.
+#[tauri::command]
+pub async fn touch_session(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    profile_name: String,
+) -> Result<SessionStatus, String> {
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    check_session(&ctx, &profile_name).await
+}
+
+async fn check_session(ctx: &RequestContext<'_>, profile_name: &str) -> Result<SessionStatus, String> {
+    let credentials = load_credentials(ctx.store, profile_name)?;
+
+    let now = chrono::Utc::now().timestamp();
+    let expires_at = credentials.expires_at.or_else(|| {
+        credentials.auth_token.as_ref().and_then(|token| inspect_token(token.clone()).ok()?.exp)
+    });
+    let expires_in = expires_at.map(|expires_at| expires_at - now);
+
+    let odata_base = odata_service_root(ctx.network, &credentials.base_url)?;
+    let url = format!("{}/{}?$top=0", odata_base, DEFAULT_PROBE_ENTITY);
+    let client = build_client(ctx.network, Some(10), None, None)?;
+    // No profile name, so a 401 comes straight back instead of triggering a refresh
+    let result = send_with_refresh(ctx, None, AuthFields::from(&credentials), None, "Session check", |headers| {
+        client.get(&url).headers(headers)
+    }).await;
+
+    let status = |valid| SessionStatus {
+        profile: profile_name.to_string(),
+        valid,
+        expires_in,
+        last_checked: chrono::Utc::now().timestamp(),
+        error: None,
+    };
+    match result {
+        Ok(response) if response.status().is_success() => Ok(status(true)),
+        Ok(response) => Err(format!("Session check failed with status {}", response.status().as_u16())),
+        Err(AppError::Typed { kind: ErrorKind::Unauthorized { .. }, .. }) => Ok(status(false)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// `touch_session` for every profile in the index, a few at a time, in index order - for a
+/// dashboard showing which environments need a re-login. A profile whose check fails comes back
+/// `valid: false` with `error` set rather than failing the whole call. Results are reused for
+/// a short while unless `force` is set.
+#[tauri::command]
+pub async fn check_all_sessions(
+    app: AppHandle,
+    cache: State<'_, SessionStatusCache>,
+    force: Option<bool>,
+) -> Result<Vec<SessionStatus>, String> {
+    if !force.unwrap_or(false) {
+        let last = cache.last.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((checked_at, statuses)) = last.as_ref() {
+            if checked_at.elapsed() < SESSION_CHECK_CACHE_TTL {
+                return Ok(statuses.clone());
+            }
+        }
+    }
+
+    let profiles = read_profile_index(&app.state::<KeyStore>())?;
+    let permits = Arc::new(Semaphore::new(MAX_CONCURRENT_SESSION_CHECKS));
+    let tasks: Vec<_> = profiles.into_iter()
+        .map(|profile_name| {
+            let app = app.clone();
+            let permits = permits.clone();
+            tauri::async_runtime::spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                let (store, log, circuits, network, locks) = (
+                    app.state::<KeyStore>(),
+                    app.state::<RequestLog>(),
+                    app.state::<CircuitBreakers>(),
+                    app.state::<NetworkConfig>(),
+                    app.state::<ProfileLocks>(),
+                );
+                let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+                check_session(&ctx, &profile_name).await.unwrap_or_else(|e| SessionStatus {
+                    profile: profile_name.clone(),
+                    valid: false,
+                    expires_in: None,
+                    last_checked: chrono::Utc::now().timestamp(),
+                    error: Some(e),
+                })
+            })
+        })
+        .collect();
+
+    let mut statuses = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        statuses.push(task.await.map_err(|e| format!("Session check task failed: {}", e))?);
+    }
+
+    *cache.last.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), statuses.clone()));
+    Ok(statuses)
+}
+
+/// GET an OData URL with the profile's session and return its rows
+async fn fetch_rows(
+    ctx: &RequestContext<'_>,
+    profile_name: &str,
+    url: &str,
+    label: &str,
+) -> Result<Vec<Value>, AppError> {
+    let auth = resolve_auth(ctx, Some(profile_name), AuthFields::default()).await?;
+    let client = build_client(ctx.network, None, None, None)?;
+    let response = send_with_refresh(ctx, Some(profile_name), auth, None, label, |headers| {
+        client.get(url).headers(headers)
+    }).await?;
+    let status = response.status();
+    let body = read_body_limited(response, None).await?;
+    if !status.is_success() {
+        return Err(format!("{} failed with status {}: {}", label, status.as_u16(), body).into());
+    }
+    let json: Value = serde_json::from_str(&body)
+        .map_err(|e| format!("Failed to parse {} response: {}", label, e))?;
+    Ok(unwrap_odata_value(&json)?.clone())
+}
+
+/// The user signed in with a profile's session: id, username, display name and active security roles.
+/// Looked up once per session and cached, so screens can call this freely; logging out or
+/// refreshing the session looks it up again. App token sessions are matched on their username.
+#[tauri::command]
+pub async fn whoami(
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    identities: State<'_, IdentityCache>,
+    profile_name: String,
+) -> Result<UserIdentity, AppError> {
+    let credentials = load_credentials(&store, &profile_name)?;
+    if let Some(identity) = identities.get(&profile_name, &session_token(&credentials)) {
+        return Ok(identity);
+    }
+
+    let ctx = RequestContext { store: &store, log: &log, circuits: &circuits, network: &network, locks: &locks, deadline: None };
+    let odata_base = odata_service_root(ctx.network, &credentials.base_url)?;
+    let user_url = user_lookup_url(&odata_base, &credentials)
+        .ok_or_else(|| format!("Profile '{}' has no user id or username", profile_name))?;
+    let user = fetch_rows(&ctx, &profile_name, &user_url, "Current user lookup").await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("No Nimbus user matches profile '{}'", profile_name))?;
+
+    let field = |name: &str| user.get(name).and_then(Value::as_str).unwrap_or_default().to_string();
+    let user_id = user.get("Id")
+        .and_then(Value::as_i64)
+        .and_then(|id| i32::try_from(id).ok())
+        .ok_or_else(|| "Current user lookup returned no Id".to_string())?;
+    let username = field("Username");
+    let display_name = match format!("{} {}", field("Forename"), field("Surname")).trim() {
+        "" => username.clone(),
+        name => name.to_string(),
+    };
+
+    let roles_url = format!(
+        "{}/UserSecurityRole?$select=Id&$expand={}&$filter={}",
+        odata_base,
+        urlencoding::encode("SecurityRole($select=Description)"),
+        urlencoding::encode(&format!("UserID eq {} and Active eq true and Deleted eq false", user_id)),
+    );
+    let roles: BTreeSet<String> = fetch_rows(&ctx, &profile_name, &roles_url, "Security role lookup").await?
+        .iter()
+        .filter_map(|row| row.pointer("/SecurityRole/Description").and_then(Value::as_str))
+        .map(str::to_string)
+        .collect();
+
+    let identity = UserIdentity { user_id, username, display_name, roles: roles.into_iter().collect() };
+    // Keyed on the token now stored, in case a 401 during the lookups refreshed the session
+    let token = load_credentials(&store, &profile_name).ok().and_then(|c| session_token(&c));
+    identities.insert(&profile_name, token, identity.clone());
+    Ok(identity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ODATA_BASE: &str = "https://nimbus.example.edu/CoreApi/OData";
+
+    fn credentials(user_id: Option<i32>, username: Option<&str>) -> Credentials {
+        serde_json::from_value(json!({
+            "base_url": "https://nimbus.example.edu",
+            "auth_mode": "apptoken",
+            "user_id": user_id,
+            "username": username,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn user_lookup_encodes_an_email_username_once() {
+        let url = user_lookup_url(ODATA_BASE, &credentials(None, Some("j.o'neil+ops@monash.edu"))).unwrap();
+        assert_eq!(
+            url,
+            "https://nimbus.example.edu/CoreApi/OData/User?$select=Id,Username,Forename,Surname\
+             &$filter=Username%20eq%20%27j.o%27%27neil%2Bops%40monash.edu%27&$top=1"
+        );
+        let filter = url.split("$filter=").nth(1).unwrap().split('&').next().unwrap();
+        assert_eq!(urlencoding::decode(filter).unwrap(), "Username eq 'j.o''neil+ops@monash.edu'");
+    }
+
+    #[test]
+    fn user_lookup_prefers_the_user_id() {
+        let url = user_lookup_url(ODATA_BASE, &credentials(Some(42), Some("j.smith@monash.edu"))).unwrap();
+        assert!(url.contains("$filter=Id%20eq%2042&"), "{}", url);
+        assert!(user_lookup_url(ODATA_BASE, &credentials(None, None)).is_none());
+    }
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::diagnostics::redact_url;
+use crate::types::HttpResponse;
+
+// Sub-folder of the app data directory holding saved responses
+const SNAPSHOT_DIR: &str = "snapshots";
+const BODY_EXTENSION: &str = "body";
+const META_EXTENSION: &str = "meta.json";
+
+/// Sidecar written next to each snapshot body
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotMeta {
+    pub label: String,
+    // Unix seconds when the snapshot was saved
+    pub timestamp: i64,
+    pub status: u16,
+    pub url_redacted: String,
+    pub content_type: Option<String>,
+    // Absolute path of the body file (what save_response_snapshot returns and load_snapshot accepts)
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub meta: SnapshotMeta,
+    pub body: String,
+}
+
+fn snapshot_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join(SNAPSHOT_DIR);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create snapshot directory: {}", e))?;
+    Ok(dir)
+}
+
+/// Keep labels filesystem-safe: letters, digits, '-' and '_' only
+fn slugify(label: &str) -> String {
+    let slug: String = label.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-');
+    if slug.is_empty() { "snapshot".to_string() } else { slug.chars().take(60).collect() }
+}
+
+fn meta_path(body_path: &Path) -> PathBuf {
+    body_path.with_extension(META_EXTENSION)
+}
+
+/// Save a response body plus a metadata sidecar (timestamp, status, redacted URL) for offline review.
+/// Returns the path of the saved body.
+#[tauri::command]
+pub async fn save_response_snapshot(app: AppHandle, response: HttpResponse, label: String) -> Result<String, String> {
+    let dir = snapshot_dir(&app)?;
+    let now = chrono::Utc::now();
+    let stem = format!("{}-{}", now.format("%Y%m%dT%H%M%S%3fZ"), slugify(&label));
+    let body_path = dir.join(format!("{}.{}", stem, BODY_EXTENSION));
+
+    let url_redacted = url::Url::parse(&response.final_url)
+        .map(|u| redact_url(&u))
+        .unwrap_or_default();
+    let content_type = response.headers.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        .map(|(_, v)| v.clone());
+
+    let meta = SnapshotMeta {
+        label,
+        timestamp: now.timestamp(),
+        status: response.status,
+        url_redacted,
+        content_type,
+        path: body_path.to_string_lossy().into_owned(),
+    };
+    let meta_json = serde_json::to_string_pretty(&meta)
+        .map_err(|e| format!("Failed to serialize snapshot metadata: {}", e))?;
+
+    tokio::fs::write(&body_path, response.body.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write snapshot body: {}", e))?;
+    tokio::fs::write(meta_path(&body_path), meta_json)
+        .await
+        .map_err(|e| format!("Failed to write snapshot metadata: {}", e))?;
+
+    Ok(meta.path)
+}
+
+/// All saved snapshots, newest first
+#[tauri::command]
+pub async fn list_snapshots(app: AppHandle) -> Result<Vec<SnapshotMeta>, String> {
+    let dir = snapshot_dir(&app)?;
+    let mut entries = tokio::fs::read_dir(&dir)
+        .await
+        .map_err(|e| format!("Failed to read snapshot directory: {}", e))?;
+
+    let mut snapshots = Vec::new();
+    while let Some(entry) = entries.next_entry()
+        .await
+        .map_err(|e| format!("Failed to read snapshot directory: {}", e))?
+    {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.ends_with(META_EXTENSION) {
+            continue;
+        }
+        // Skip unreadable sidecars rather than failing the whole list
+        let Ok(json) = tokio::fs::read_to_string(entry.path()).await else { continue };
+        if let Ok(meta) = serde_json::from_str::<SnapshotMeta>(&json) {
+            snapshots.push(meta);
+        }
+    }
+
+    snapshots.sort_by_key(|m| std::cmp::Reverse(m.timestamp));
+    Ok(snapshots)
+}
+
+/// Load a snapshot body and its metadata. Only paths inside the snapshot folder are accepted.
+#[tauri::command]
+pub async fn load_snapshot(app: AppHandle, path: String) -> Result<Snapshot, String> {
+    let dir = snapshot_dir(&app)?
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve snapshot directory: {}", e))?;
+    let body_path = Path::new(&path)
+        .canonicalize()
+        .map_err(|e| format!("Snapshot '{}' not found: {}", path, e))?;
+    if !body_path.starts_with(&dir) {
+        return Err(format!("'{}' is not a saved snapshot", path));
+    }
+
+    let meta_json = tokio::fs::read_to_string(meta_path(&body_path))
+        .await
+        .map_err(|e| format!("Failed to read snapshot metadata: {}", e))?;
+    let meta: SnapshotMeta = serde_json::from_str(&meta_json)
+        .map_err(|e| format!("Failed to parse snapshot metadata: {}", e))?;
+    let body = tokio::fs::read_to_string(&body_path)
+        .await
+        .map_err(|e| format!("Failed to read snapshot body: {}", e))?;
+
+    Ok(Snapshot { meta, body })
+}
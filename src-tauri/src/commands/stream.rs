@@ -0,0 +1,175 @@
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::commands::circuit::CircuitBreakers;
+use crate::commands::diagnostics::RequestLog;
+use crate::commands::http::{
+    build_client, resolve_url, send_with_refresh, AuthFields, AuthScheme, RedirectPolicy, RequestContext,
+};
+use crate::commands::network::NetworkConfig;
+use crate::commands::profiles::ProfileLocks;
+use crate::commands::session;
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+
+// Event carrying one parsed row, and the event sent once the stream ends
+const NDJSON_ROW_EVENT: &str = "ndjson-row";
+const NDJSON_COMPLETE_EVENT: &str = "ndjson-complete";
+
+// Streams can legitimately run for minutes, so the default total timeout is longer than for normal GETs
+const DEFAULT_STREAM_TIMEOUT_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Serialize)]
+struct NdjsonRow {
+    stream_id: String,
+    index: u64,
+    value: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct NdjsonComplete {
+    stream_id: String,
+    total: u64,
+    // "ndjson", or "json_array" when the server sent a plain array instead
+    format: &'static str,
+}
+
+fn emit_row(app: &AppHandle, stream_id: &str, index: u64, value: Value) -> Result<(), String> {
+    app.emit(NDJSON_ROW_EVENT, NdjsonRow { stream_id: stream_id.to_string(), index, value })
+        .map_err(|e| format!("Failed to emit row event: {}", e))
+}
+
+/// Parse one NDJSON line (blank lines are skipped) and emit it; returns whether a row was emitted
+fn emit_line(app: &AppHandle, stream_id: &str, line: &[u8], index: u64) -> Result<bool, String> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_start_matches('\u{feff}').trim();
+    if line.is_empty() {
+        return Ok(false);
+    }
+    let value: Value = serde_json::from_str(line)
+        .map_err(|e| format!("Line {} is not valid JSON: {}", index + 1, e))?;
+    emit_row(app, stream_id, index, value)?;
+    Ok(true)
+}
+
+/// GET a newline-delimited JSON endpoint and emit each object as an `ndjson-row` event as it arrives,
+/// keeping memory flat for large exports. Finishes with an `ndjson-complete` event and returns the row count.
+/// If the server answers with a normal JSON array instead, its elements are emitted the same way.
+#[tauri::command]
+pub async fn execute_rest_get_ndjson(
+    app: AppHandle,
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    stream_id: String,
+    url: Option<String>,
+    base_url: Option<String>,
+    endpoint: Option<String>,
+    headers: Option<HashMap<String, String>>,
+    user_id: Option<i32>,
+    auth_token: Option<String>,
+    app_token: Option<String>,
+    username: Option<String>,
+    auth_scheme: Option<AuthScheme>,
+    timeout_seconds: Option<u64>,
+    connect_timeout_seconds: Option<u64>,
+    profile_name: Option<String>,
+    redirect_policy: Option<RedirectPolicy>,
+    deadline_id: Option<String>,
+) -> Result<u64, AppError> {
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(deadline_id.as_deref())?,
+    };
+    let client = build_client(
+        ctx.network,
+        Some(timeout_seconds.unwrap_or(DEFAULT_STREAM_TIMEOUT_SECS)),
+        connect_timeout_seconds,
+        redirect_policy,
+    )?;
+
+    let full_url = resolve_url(url, base_url, endpoint)?;
+
+    let auth = session::resolve_auth(
+        &ctx,
+        profile_name.as_deref(),
+        AuthFields { user_id, auth_token, app_token, username, scheme: auth_scheme.unwrap_or_default(), ..Default::default() },
+    ).await?;
+
+    // Prefer NDJSON but accept JSON, unless the caller asked for something specific
+    let mut headers = headers.unwrap_or_default();
+    if !headers.keys().any(|k| k.eq_ignore_ascii_case("accept")) {
+        headers.insert("Accept".to_string(), "application/x-ndjson, application/json".to_string());
+    }
+
+    let mut response = send_with_refresh(&ctx, profile_name.as_deref(), auth, Some(headers), "NDJSON request", |req_headers| {
+        client.get(&full_url).headers(req_headers)
+    }).await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("NDJSON request failed with status {}: {}", status.as_u16(), body).into());
+    }
+
+    let mut pending: Vec<u8> = Vec::new();
+    let mut total: u64 = 0;
+    let mut is_array: Option<bool> = None;
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read NDJSON stream: {}", e))?
+    {
+        pending.extend_from_slice(&chunk);
+
+        // Decide the format from the first meaningful byte
+        if is_array.is_none() {
+            let text = String::from_utf8_lossy(&pending);
+            if let Some(first) = text.trim_start_matches('\u{feff}').trim_start().chars().next() {
+                is_array = Some(first == '[');
+            }
+        }
+
+        // A plain JSON array can't be split on newlines, so it's buffered whole
+        if is_array == Some(true) {
+            continue;
+        }
+
+        while let Some(newline) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=newline).collect();
+            if emit_line(&app, &stream_id, &line, total)? {
+                total += 1;
+            }
+        }
+    }
+
+    let format = if is_array == Some(true) {
+        let rows: Vec<Value> = serde_json::from_slice(&pending)
+            .map_err(|e| format!("Failed to parse JSON array response: {}", e))?;
+        for value in rows {
+            emit_row(&app, &stream_id, total, value)?;
+            total += 1;
+        }
+        "json_array"
+    } else {
+        // Last line may not end with a newline
+        if emit_line(&app, &stream_id, &pending, total)? {
+            total += 1;
+        }
+        "ndjson"
+    };
+
+    app.emit(NDJSON_COMPLETE_EVENT, NdjsonComplete { stream_id, total, format })
+        .map_err(|e| format!("Failed to emit completion event: {}", e))?;
+
+    Ok(total)
+}
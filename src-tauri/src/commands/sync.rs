@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::circuit::CircuitBreakers;
+use crate::commands::diagnostics::{QueryMetrics, RequestLog};
+use crate::commands::export::export_pages;
+use crate::commands::filter::{build_filter, ComparisonOp, FilterExpr, FilterValue};
+use crate::commands::http::{ODataQueryParams, RequestContext};
+use crate::commands::network::NetworkConfig;
+use crate::commands::odata::EntityFieldCache;
+use crate::commands::profiles::ProfileLocks;
+use crate::error::AppError;
+use crate::keystore::KeyStore;
+
+// File in the app data directory holding the high-water marks of incremental syncs
+const HIGH_WATER_FILE: &str = "sync_high_water.json";
+
+const DEFAULT_SYNC_PAGE_SIZE: u32 = 500;
+
+/// The newest timestamp an incremental sync has seen
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighWater {
+    pub entity: String,
+    pub timestamp_field: String,
+    pub value: Value,
+    // Unix seconds when the mark last moved
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct HighWaterMarks {
+    #[serde(default)]
+    marks: BTreeMap<String, HighWater>,
+}
+
+/// Rows new or changed since the last sync, and where the high-water mark moved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncResult {
+    pub rows: Vec<Value>,
+    // True when there was no stored mark, so every row was fetched
+    pub initial: bool,
+    pub previous_high_water: Option<Value>,
+    pub high_water: Option<Value>,
+}
+
+fn marks_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+    Ok(dir.join(HIGH_WATER_FILE))
+}
+
+fn read_marks(path: &PathBuf) -> Result<HighWaterMarks, String> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Failed to parse sync state '{}': {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HighWaterMarks::default()),
+        Err(e) => Err(format!("Failed to read sync state '{}': {}", path.display(), e)),
+    }
+}
+
+/// Write via a temporary file so a crash mid-write can't lose every mark
+fn write_marks(path: &PathBuf, marks: &HighWaterMarks) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(marks)
+        .map_err(|e| format!("Failed to serialize sync state: {}", e))?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json)
+        .map_err(|e| format!("Failed to write sync state: {}", e))?;
+    std::fs::rename(&tmp, path)
+        .map_err(|e| format!("Failed to save sync state: {}", e))
+}
+
+/// A date-time mark as an instant: RFC 3339, or a timestamp without offset (such as
+/// `2024-01-31T09:00:00`) taken as UTC, the same as `build_filter` sends it
+fn mark_instant(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    match chrono::DateTime::parse_from_rfc3339(value) {
+        Ok(dt) => Some(dt.with_timezone(&chrono::Utc)),
+        Err(_) => chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok().map(|dt| dt.and_utc()),
+    }
+}
+
+/// The mark as a filter literal: date-times (normalized to UTC), numbers, or quoted strings
+fn mark_literal(value: &Value) -> Result<FilterValue, String> {
+    match value {
+        Value::String(s) if mark_instant(s).is_some() => Ok(FilterValue::DateTime(s.clone())),
+        Value::String(s) => Ok(FilterValue::String(s.clone())),
+        Value::Number(n) => Ok(FilterValue::Number(n.clone())),
+        other => Err(format!("Stored high-water value {} can't be compared", other)),
+    }
+}
+
+/// Whether `candidate` is later than `current`: date-times by instant, numbers by value, else as text
+fn is_later(candidate: &Value, current: &Value) -> bool {
+    match (candidate, current) {
+        (Value::String(a), Value::String(b)) => {
+            match (mark_instant(a), mark_instant(b)) {
+                (Some(a), Some(b)) => a > b,
+                _ => a > b,
+            }
+        }
+        (Value::Number(a), Value::Number(b)) => a.as_f64() > b.as_f64(),
+        _ => false,
+    }
+}
+
+/// Fetch only the rows whose `timestamp_field` is past the high-water mark stored under
+/// `high_water_key`, then move the mark to the newest value seen. With no stored mark every
+/// row is fetched. The caller's own `filter` still applies, and rows are read in timestamp order
+/// unless an `orderby` is given. The mark is only saved once every page has been read, so a failed
+/// sync is simply retried from the old mark. Rows stamped exactly at the mark are not re-read.
+#[tauri::command]
+pub async fn sync_odata_incremental(
+    app: AppHandle,
+    store: State<'_, KeyStore>,
+    log: State<'_, RequestLog>,
+    circuits: State<'_, CircuitBreakers>,
+    network: State<'_, NetworkConfig>,
+    locks: State<'_, ProfileLocks>,
+    metrics: State<'_, QueryMetrics>,
+    fields: State<'_, EntityFieldCache>,
+    mut params: ODataQueryParams,
+    timestamp_field: String,
+    high_water_key: String,
+    page_size: Option<u32>,
+) -> Result<SyncResult, AppError> {
+    let high_water_key = high_water_key.trim().to_string();
+    if high_water_key.is_empty() {
+        return Err("A high-water key is required".to_string().into());
+    }
+
+    let path = marks_path(&app)?;
+    let previous = read_marks(&path)?.marks.remove(&high_water_key).map(|mark| mark.value);
+
+    if let Some(ref mark) = previous {
+        let since = build_filter(FilterExpr::Compare {
+            field: timestamp_field.clone(),
+            operator: ComparisonOp::Gt,
+            value: mark_literal(mark)?,
+        })?;
+        params.filter = Some(match params.filter.as_deref().filter(|f| !f.trim().is_empty()) {
+            Some(filter) => format!("({}) and {}", filter, since),
+            None => since,
+        });
+    }
+    if params.orderby.as_deref().is_none_or(str::is_empty) {
+        params.orderby = Some(format!("{} asc", timestamp_field));
+    }
+
+    let ctx = RequestContext {
+        store: &store,
+        log: &log,
+        circuits: &circuits,
+        network: &network,
+        locks: &locks,
+        deadline: network.deadlines.resolve(params.deadline_id.as_deref())?,
+    };
+    let page_size = page_size.unwrap_or(DEFAULT_SYNC_PAGE_SIZE).max(1);
+
+    let mut rows: Vec<Value> = Vec::new();
+    let mut high_water = previous.clone();
+    let collect = |page: &[Value]| {
+        for row in page {
+            if let Some(value) = row.get(&timestamp_field).filter(|v| !v.is_null()) {
+                if high_water.as_ref().is_none_or(|current| is_later(value, current)) {
+                    high_water = Some(value.clone());
+                }
+            }
+        }
+        rows.extend_from_slice(page);
+        Ok(())
+    };
+    export_pages(&ctx, &metrics, &fields, &params, page_size, collect, |_, _| {}).await?;
+
+    // Re-read before writing, so marks saved by other syncs meanwhile are kept
+    if let Some(value) = high_water.as_ref().filter(|_| high_water != previous) {
+        let mut marks = read_marks(&path)?;
+        marks.marks.insert(high_water_key, HighWater {
+            entity: params.entity.clone(),
+            timestamp_field,
+            value: value.clone(),
+            updated_at: chrono::Utc::now().timestamp(),
+        });
+        write_marks(&path, &marks)?;
+    }
+
+    Ok(SyncResult { rows, initial: previous.is_none(), previous_high_water: previous, high_water })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn timestamps_without_an_offset_are_date_time_marks() {
+        assert!(matches!(mark_literal(&json!("2024-01-31T09:00:00")).unwrap(), FilterValue::DateTime(_)));
+        assert!(matches!(mark_literal(&json!("2024-01-31T09:00:00.123")).unwrap(), FilterValue::DateTime(_)));
+        assert!(matches!(mark_literal(&json!("2024-01-31T09:00:00+10:00")).unwrap(), FilterValue::DateTime(_)));
+        assert!(matches!(mark_literal(&json!("A-100")).unwrap(), FilterValue::String(_)));
+        assert!(mark_literal(&json!(true)).is_err());
+    }
+
+    #[test]
+    fn later_marks_compare_by_instant() {
+        assert!(is_later(&json!("2024-01-31T09:00:00.5"), &json!("2024-01-31T09:00:00")));
+        // Earlier as text, but 10:00 at +10:00 is midnight UTC
+        assert!(is_later(&json!("2024-01-31T09:00:00"), &json!("2024-01-31T10:00:00+10:00")));
+        assert!(!is_later(&json!("2024-01-31T09:00:00Z"), &json!("2024-01-31T09:00:00")));
+        assert!(is_later(&json!(10), &json!(9.5)));
+    }
+}
@@ -1,5 +1,17 @@
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Mutex;
+use tauri::State;
+use tokio::io::AsyncWriteExt;
+
+use crate::commands::retry::{classify_error, classify_status, RetryClass};
+
+// Default minimum gap between real GitHub checks (campus NAT shares the unauthenticated rate limit)
+const DEFAULT_CHECK_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+// Per-request timeout for the update check, which runs at startup and mustn't hold it up
+const UPDATE_CHECK_TIMEOUT_SECS: u64 = 8;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VersionInfo {
@@ -8,6 +20,138 @@ pub struct VersionInfo {
     pub update_available: bool,
     pub release_url: Option<String>,
     pub release_notes: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+    // Unix seconds when GitHub was actually queried (older than "now" when served from cache)
+    #[serde(default)]
+    pub checked_at: i64,
+}
+
+/// Returned instead of an error when GitHub couldn't be reached, so startup carries on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCheckUnavailable {
+    // Always true; lets the frontend tell this apart from a `VersionInfo`
+    pub unavailable: bool,
+    pub reason: String,
+    // The last successful check, if there is one
+    pub cached: Option<VersionInfo>,
+}
+
+/// Outcome of `check_for_updates`: a `VersionInfo` as before, or `UpdateCheckUnavailable`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum UpdateCheckResult {
+    Checked(VersionInfo),
+    Unavailable(UpdateCheckUnavailable),
+}
+
+/// Why an update check failed: `Transient` (see `retry::classify_error`/`classify_status`) is worth
+/// a retry and ends up as `UpdateCheckUnavailable`; anything else is a real error
+enum CheckError {
+    Transient(String),
+    Failed(String),
+}
+
+impl From<String> for CheckError {
+    fn from(message: String) -> Self {
+        CheckError::Failed(message)
+    }
+}
+
+impl CheckError {
+    fn from_send(error: reqwest::Error) -> Self {
+        let message = format!("Failed to fetch releases: {}", error);
+        match classify_error(&error) {
+            RetryClass::Retryable => CheckError::Transient(message),
+            RetryClass::Terminal => CheckError::Failed(message),
+        }
+    }
+
+    /// GitHub answers an exhausted rate limit with a 403 and `x-ratelimit-remaining: 0`, which
+    /// passes like a 429; any other 403 is a real refusal
+    async fn from_status(response: reqwest::Response) -> Self {
+        let status = response.status();
+        let rate_limited = status == reqwest::StatusCode::FORBIDDEN
+            && response.headers()
+                .get("x-ratelimit-remaining")
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.trim() == "0");
+        let message = format!(
+            "GitHub API returned status {}: {}",
+            status,
+            response.text().await.unwrap_or_default()
+        );
+        match (rate_limited, classify_status(status)) {
+            (true, _) | (_, RetryClass::Retryable) => CheckError::Transient(message),
+            (false, RetryClass::Terminal) => CheckError::Failed(message),
+        }
+    }
+}
+
+/// Last update check result plus any GitHub rate-limit backoff
+#[derive(Default)]
+pub struct UpdateCheckCache {
+    inner: Mutex<UpdateCheckCacheInner>,
+}
+
+#[derive(Default)]
+struct UpdateCheckCacheInner {
+    last: Option<CachedCheck>,
+    rate_limited_until: Option<i64>,
+}
+
+struct CachedCheck {
+    key: String,
+    info: VersionInfo,
+}
+
+impl UpdateCheckCache {
+    fn cached(&self, key: &str) -> Option<VersionInfo> {
+        let inner = self.inner.lock().ok()?;
+        inner.last.as_ref()
+            .filter(|c| c.key == key)
+            .map(|c| c.info.clone())
+    }
+
+    fn store(&self, key: String, info: VersionInfo) {
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.last = Some(CachedCheck { key, info });
+        }
+    }
+
+    fn rate_limited_until(&self) -> Option<i64> {
+        self.inner.lock().ok()?.rate_limited_until
+    }
+
+    /// Remember when GitHub's rate limit resets if the response says we've used it all
+    fn note_rate_limit(&self, headers: &reqwest::header::HeaderMap) {
+        let header = |name: &str| {
+            headers.get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<i64>().ok())
+        };
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.rate_limited_until = match (header("x-ratelimit-remaining"), header("x-ratelimit-reset")) {
+                (Some(0), Some(reset)) => Some(reset),
+                (Some(0), None) => Some(chrono::Utc::now().timestamp() + 60),
+                _ => None,
+            };
+        }
+    }
+}
+
+/// A downloadable file attached to a GitHub release
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    pub browser_download_url: String,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    // Filled from a `<name>.sha256` or `SHA256SUMS` asset on the same release, when present
+    #[serde(default)]
+    pub sha256: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -15,6 +159,12 @@ struct GitHubRelease {
     tag_name: String,
     html_url: String,
     body: Option<String>,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    prerelease: bool,
 }
 
 /// Get current app version from Cargo.toml
@@ -24,19 +174,87 @@ pub fn get_current_version() -> String {
 }
 
 /// Check GitHub releases for a newer version
-/// Returns version info including whether an update is available
+/// Returns version info including whether an update is available.
+/// With `full_changelog`, `release_notes` covers every release newer than the running version.
+/// Results are cached for `min_interval_seconds` (default 6 hours) unless `force` is set,
+/// and the cache is also served while GitHub reports the rate limit as exhausted.
+/// Each request times out after 8s and a timeout or connection failure is retried once; if that
+/// fails too, or GitHub refuses the check as rate limited, the result is `UpdateCheckUnavailable`
+/// rather than an error.
 #[tauri::command]
 pub async fn check_for_updates(
+    cache: State<'_, UpdateCheckCache>,
     owner: String,
     repo: String,
     github_token: Option<String>,
-) -> Result<VersionInfo, String> {
-    let current = env!("CARGO_PKG_VERSION").to_string();
+    full_changelog: Option<bool>,
+    force: Option<bool>,
+    min_interval_seconds: Option<u64>,
+) -> Result<UpdateCheckResult, String> {
+    let now = chrono::Utc::now().timestamp();
+    let full_changelog = full_changelog.unwrap_or(false);
+    let key = format!("{}/{}#{}", owner, repo, full_changelog);
+    let cached = cache.cached(&key);
 
-    let client = Client::builder()
+    if let Some(until) = cache.rate_limited_until() {
+        if now < until {
+            return cached.map(UpdateCheckResult::Checked).ok_or_else(|| format!(
+                "GitHub API rate limit exhausted, try again in {}s",
+                until - now
+            ));
+        }
+    }
+
+    let min_interval = min_interval_seconds.unwrap_or(DEFAULT_CHECK_INTERVAL_SECS) as i64;
+    if !force.unwrap_or(false) {
+        if let Some(info) = cached {
+            if now - info.checked_at < min_interval {
+                return Ok(UpdateCheckResult::Checked(info));
+            }
+        }
+    }
+
+    let fetch = || fetch_version_info(&cache, &owner, &repo, github_token.as_deref(), full_changelog);
+    let result = match fetch().await {
+        // Retrying while GitHub reports the rate limit as spent would only be refused again
+        Err(CheckError::Transient(_)) if cache.rate_limited_until().is_none() => fetch().await,
+        result => result,
+    };
+
+    match result {
+        Ok(info) => {
+            cache.store(key, info.clone());
+            Ok(UpdateCheckResult::Checked(info))
+        }
+        Err(CheckError::Transient(reason)) => Ok(UpdateCheckResult::Unavailable(UpdateCheckUnavailable {
+            unavailable: true,
+            reason,
+            cached: cache.cached(&key),
+        })),
+        Err(CheckError::Failed(e)) => Err(e),
+    }
+}
+
+/// Client for the update check only: a short timeout so a bad connection fails fast
+fn update_check_client() -> Result<Client, String> {
+    Client::builder()
         .user_agent("MonashNimbusReports/1.0")
+        .timeout(std::time::Duration::from_secs(UPDATE_CHECK_TIMEOUT_SECS))
         .build()
-        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+async fn fetch_version_info(
+    cache: &UpdateCheckCache,
+    owner: &str,
+    repo: &str,
+    github_token: Option<&str>,
+    full_changelog: bool,
+) -> Result<VersionInfo, CheckError> {
+    let current = env!("CARGO_PKG_VERSION").to_string();
+    let checked_at = chrono::Utc::now().timestamp();
+
+    let client = update_check_client()?;
 
     let url = format!(
         "https://api.github.com/repos/{}/{}/releases/latest",
@@ -50,10 +268,9 @@ pub async fn check_for_updates(
         request = request.header("Authorization", format!("Bearer {}", token));
     }
 
-    let response = request
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch releases: {}", e))?;
+    let response = request.send().await.map_err(CheckError::from_send)?;
+
+    cache.note_rate_limit(response.headers());
 
     if response.status() == 404 {
         // No releases yet
@@ -63,18 +280,16 @@ pub async fn check_for_updates(
             update_available: false,
             release_url: None,
             release_notes: None,
+            assets: Vec::new(),
+            checked_at,
         });
     }
 
     if !response.status().is_success() {
-        return Err(format!(
-            "GitHub API returned status {}: {}",
-            response.status(),
-            response.text().await.unwrap_or_default()
-        ));
+        return Err(CheckError::from_status(response).await);
     }
 
-    let release: GitHubRelease = response
+    let mut release: GitHubRelease = response
         .json()
         .await
         .map_err(|e| format!("Failed to parse release info: {}", e))?;
@@ -85,25 +300,307 @@ pub async fn check_for_updates(
     // Simple version comparison (assumes semver)
     let update_available = is_newer_version(&current, &latest);
 
+    // The checksum files are only worth fetching for a release that's about to be offered
+    if update_available {
+        attach_checksums(&client, &mut release.assets).await;
+    }
+
+    let release_notes = if update_available && full_changelog {
+        fetch_changelog(cache, &client, owner, repo, github_token, &current).await?
+    } else {
+        release.body
+    };
+
     Ok(VersionInfo {
         current_version: current,
         latest_version: Some(latest),
         update_available,
         release_url: Some(release.html_url),
-        release_notes: release.body,
+        release_notes,
+        assets: release.assets,
+        checked_at,
     })
 }
 
+/// Concatenate the notes of every published release newer than `current`, newest first,
+/// each under a `## <tag>` heading
+async fn fetch_changelog(
+    cache: &UpdateCheckCache,
+    client: &Client,
+    owner: &str,
+    repo: &str,
+    github_token: Option<&str>,
+    current: &str,
+) -> Result<Option<String>, CheckError> {
+    let url = format!(
+        "https://api.github.com/repos/{}/{}/releases?per_page=100",
+        owner, repo
+    );
+
+    let mut request = client.get(&url);
+    if let Some(token) = github_token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    // Failures are classified like the first request's, so a timeout is retried the same way
+    let response = request.send().await.map_err(CheckError::from_send)?;
+    cache.note_rate_limit(response.headers());
+
+    if !response.status().is_success() {
+        return Err(CheckError::from_status(response).await);
+    }
+
+    let releases: Vec<GitHubRelease> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse release list: {}", e))?;
+
+    let mut newer: Vec<GitHubRelease> = releases
+        .into_iter()
+        .filter(|r| !r.draft && !r.prerelease)
+        .filter(|r| is_newer_version(current, r.tag_name.trim_start_matches('v')))
+        .collect();
+    newer.sort_by_key(|r| std::cmp::Reverse(parse_version(r.tag_name.trim_start_matches('v'))));
+
+    if newer.is_empty() {
+        return Ok(None);
+    }
+
+    let sections: Vec<String> = newer
+        .into_iter()
+        .map(|r| {
+            let body = r.body.unwrap_or_default();
+            format!("## {}\n\n{}", r.tag_name, body.trim())
+        })
+        .collect();
+
+    Ok(Some(sections.join("\n\n")))
+}
+
+/// Fill in `sha256` for each asset from checksum files published alongside it.
+/// Checksums are best-effort here - `download_update` is what enforces them.
+async fn attach_checksums(client: &Client, assets: &mut [ReleaseAsset]) {
+    let checksum_files: Vec<(String, String)> = assets
+        .iter()
+        .filter(|a| a.name.ends_with(".sha256") || a.name.eq_ignore_ascii_case("SHA256SUMS"))
+        .map(|a| (a.name.clone(), a.browser_download_url.clone()))
+        .collect();
+
+    for (name, url) in checksum_files {
+        let text = match client.get(&url).send().await {
+            Ok(response) if response.status().is_success() => response.text().await.unwrap_or_default(),
+            _ => continue,
+        };
+
+        // `<name>.sha256` holds the hash for `<name>`; SHA256SUMS holds "<hash>  <file>" lines
+        if let Some(target) = name.strip_suffix(".sha256") {
+            if let Some(hash) = text.split_whitespace().next() {
+                set_checksum(assets, target, hash);
+            }
+        } else {
+            for line in text.lines() {
+                let mut parts = line.split_whitespace();
+                if let (Some(hash), Some(file)) = (parts.next(), parts.next()) {
+                    set_checksum(assets, file.trim_start_matches('*'), hash);
+                }
+            }
+        }
+    }
+}
+
+fn set_checksum(assets: &mut [ReleaseAsset], name: &str, hash: &str) {
+    if let Some(asset) = assets.iter_mut().find(|a| a.name == name) {
+        asset.sha256 = Some(hash.to_ascii_lowercase());
+    }
+}
+
+/// List the downloadable files on the release tagged `tag`, with checksums attached where published
+#[tauri::command]
+pub async fn get_release_assets(
+    owner: String,
+    repo: String,
+    tag: String,
+    github_token: Option<String>,
+) -> Result<Vec<ReleaseAsset>, String> {
+    let client = Client::builder()
+        .user_agent("MonashNimbusReports/1.0")
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let url = format!(
+        "https://api.github.com/repos/{}/{}/releases/tags/{}",
+        owner, repo, urlencoding::encode(&tag)
+    );
+
+    let mut request = client.get(&url);
+    if let Some(ref token) = github_token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch release: {}", e))?;
+
+    if response.status() == 404 {
+        return Err(format!("No release tagged '{}'", tag));
+    }
+    if !response.status().is_success() {
+        return Err(format!(
+            "GitHub API returned status {}: {}",
+            response.status(),
+            response.text().await.unwrap_or_default()
+        ));
+    }
+
+    let mut release: GitHubRelease = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse release info: {}", e))?;
+
+    attach_checksums(&client, &mut release.assets).await;
+    Ok(release.assets)
+}
+
+/// Installer extensions for the running OS, most preferred first
+fn platform_extensions() -> &'static [&'static str] {
+    match std::env::consts::OS {
+        "windows" => &[".msi", "-setup.exe", ".exe"],
+        "macos" => &[".dmg", ".app.tar.gz"],
+        "linux" => &[".appimage", ".deb", ".rpm"],
+        _ => &[],
+    }
+}
+
+/// Name fragments that identify a build for the running CPU architecture
+fn arch_markers() -> &'static [&'static str] {
+    match std::env::consts::ARCH {
+        "x86_64" => &["x86_64", "x64", "amd64"],
+        "aarch64" => &["aarch64", "arm64"],
+        "x86" => &["i686", "x86"],
+        _ => &[],
+    }
+}
+
+/// Pick the installer for the running OS and architecture: the most preferred extension wins,
+/// and among those a name mentioning this architecture (or none at all, e.g. a universal dmg) beats another arch
+pub(crate) fn platform_asset(assets: &[ReleaseAsset]) -> Option<&ReleaseAsset> {
+    let all_markers = ["x86_64", "x64", "amd64", "aarch64", "arm64", "i686", "x86", "universal"];
+    let extensions = platform_extensions();
+
+    assets
+        .iter()
+        .filter_map(|asset| {
+            let name = asset.name.to_ascii_lowercase();
+            let ext_rank = extensions.iter().position(|ext| name.ends_with(ext))?;
+            let arch_rank = if arch_markers().iter().any(|m| name.contains(m)) || name.contains("universal") {
+                0
+            } else if all_markers.iter().any(|m| name.contains(m)) {
+                // Built for a different architecture
+                return None;
+            } else {
+                1
+            };
+            Some(((ext_rank, arch_rank), asset))
+        })
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, asset)| asset)
+}
+
+/// The asset from `assets` to offer on this machine, if any matches its OS and architecture
+#[tauri::command]
+pub fn select_platform_asset(assets: Vec<ReleaseAsset>) -> Option<ReleaseAsset> {
+    platform_asset(&assets).cloned()
+}
+
+/// Download a release asset to `output_path`, verifying its SHA-256 as it streams.
+/// The file is written to `<output_path>.part` and only moved into place once the hash matches.
+/// Returns the number of bytes written.
+#[tauri::command]
+pub async fn download_update(
+    asset_url: String,
+    expected_sha256: String,
+    output_path: String,
+) -> Result<u64, String> {
+    let expected = expected_sha256.trim().to_ascii_lowercase();
+    if expected.len() != 64 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err("expected_sha256 must be a 64-character hex string".to_string());
+    }
+
+    let client = Client::builder()
+        .user_agent("MonashNimbusReports/1.0")
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let mut response = client
+        .get(&asset_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download update: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Update download returned status {}", response.status()));
+    }
+
+    let partial_path = format!("{}.part", output_path);
+    let mut file = tokio::fs::File::create(&partial_path)
+        .await
+        .map_err(|e| format!("Failed to create '{}': {}", partial_path, e))?;
+
+    let mut hasher = Sha256::new();
+    let mut written: u64 = 0;
+
+    let result: Result<(), String> = async {
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed while downloading update: {}", e))?
+        {
+            hasher.update(&chunk);
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write update file: {}", e))?;
+            written += chunk.len() as u64;
+        }
+        file.flush()
+            .await
+            .map_err(|e| format!("Failed to write update file: {}", e))
+    }
+    .await;
+
+    drop(file);
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&partial_path).await;
+        return Err(e);
+    }
+
+    let actual = format!("{:x}", hasher.finalize());
+    if actual != expected {
+        let _ = tokio::fs::remove_file(&partial_path).await;
+        return Err(format!(
+            "Checksum mismatch for downloaded update (expected {}, got {})",
+            expected, actual
+        ));
+    }
+
+    tokio::fs::rename(&partial_path, &output_path)
+        .await
+        .map_err(|e| format!("Failed to move update into place: {}", e))?;
+
+    Ok(written)
+}
+
+/// Split a version string into its numeric major/minor/patch parts
+fn parse_version(v: &str) -> Vec<u32> {
+    v.split('.')
+        .filter_map(|part| part.parse::<u32>().ok())
+        .collect()
+}
+
 /// Compare two semver versions, returns true if latest > current
 fn is_newer_version(current: &str, latest: &str) -> bool {
-    let parse = |v: &str| -> Vec<u32> {
-        v.split('.')
-            .filter_map(|part| part.parse::<u32>().ok())
-            .collect()
-    };
-
-    let current_parts = parse(current);
-    let latest_parts = parse(latest);
+    let current_parts = parse_version(current);
+    let latest_parts = parse_version(latest);
 
     for i in 0..3 {
         let c = current_parts.get(i).copied().unwrap_or(0);
@@ -0,0 +1,233 @@
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde_json::{Map, Value};
+
+/// True when a body is XML rather than JSON, going by Content-Type or the first non-blank character
+pub(crate) fn looks_like_xml(content_type: Option<&str>, body: &str) -> bool {
+    let xml_type = content_type.is_some_and(|ct| {
+        let ct = ct.to_ascii_lowercase();
+        ct.contains("/xml") || ct.contains("+xml")
+    });
+    xml_type || body.trim_start_matches('\u{feff}').trim_start().starts_with('<')
+}
+
+/// An element being built: its local name, attributes/children, and accumulated text
+struct Element {
+    name: String,
+    fields: Map<String, Value>,
+    text: String,
+}
+
+impl Element {
+    fn open(start: &BytesStart) -> Result<Self, String> {
+        let name = String::from_utf8_lossy(start.local_name().as_ref()).into_owned();
+        let mut fields = Map::new();
+        for attr in start.attributes() {
+            let attr = attr.map_err(|e| format!("Invalid XML attribute on <{}>: {}", name, e))?;
+            // Namespace declarations carry no data
+            if attr.key.as_namespace_binding().is_some() {
+                continue;
+            }
+            let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned();
+            let value = attr.unescape_value()
+                .map_err(|e| format!("Invalid XML attribute value on <{}>: {}", name, e))?;
+            fields.insert(format!("@{}", key), Value::String(value.into_owned()));
+        }
+        Ok(Self { name, fields, text: String::new() })
+    }
+
+    /// Text-only elements become strings, empty ones null, and mixed ones keep their text under `#text`
+    fn into_value(mut self) -> (String, Value) {
+        let value = if self.fields.is_empty() {
+            if self.text.is_empty() { Value::Null } else { Value::String(self.text) }
+        } else {
+            if !self.text.is_empty() {
+                self.fields.insert("#text".to_string(), Value::String(self.text));
+            }
+            Value::Object(self.fields)
+        };
+        (self.name, value)
+    }
+}
+
+/// Add a child, turning repeated element names into an array
+fn insert_child(fields: &mut Map<String, Value>, name: String, value: Value) {
+    match fields.get_mut(&name) {
+        Some(Value::Array(items)) => items.push(value),
+        Some(existing) => {
+            let first = existing.take();
+            *existing = Value::Array(vec![first, value]);
+        }
+        None => {
+            fields.insert(name, value);
+        }
+    }
+}
+
+/// Convert an XML document to JSON: elements keyed by local name (namespace prefixes dropped),
+/// attributes as `@name`, repeated elements as arrays
+pub(crate) fn xml_to_json(xml: &str) -> Result<Value, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut stack = vec![Element { name: String::new(), fields: Map::new(), text: String::new() }];
+    loop {
+        let event = reader.read_event()
+            .map_err(|e| format!("Invalid XML at byte {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(start) => stack.push(Element::open(&start)?),
+            Event::Empty(start) => {
+                let (name, value) = Element::open(&start)?.into_value();
+                if let Some(parent) = stack.last_mut() {
+                    insert_child(&mut parent.fields, name, value);
+                }
+            }
+            Event::End(_) => {
+                if stack.len() < 2 {
+                    return Err("Invalid XML: unexpected closing tag".to_string());
+                }
+                if let Some(element) = stack.pop() {
+                    let (name, value) = element.into_value();
+                    if let Some(parent) = stack.last_mut() {
+                        insert_child(&mut parent.fields, name, value);
+                    }
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(|e| format!("Invalid XML text: {}", e))?;
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&text);
+                }
+            }
+            Event::CData(data) => {
+                if let Some(element) = stack.last_mut() {
+                    element.text.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if stack.len() != 1 {
+        return Err("Invalid XML: document ended with unclosed elements".to_string());
+    }
+    Ok(Value::Object(stack.remove(0).fields))
+}
+
+fn attribute(start: &BytesStart, name: &[u8]) -> Option<String> {
+    start.attributes()
+        .flatten()
+        .find(|a| a.key.local_name().as_ref() == name)
+        .and_then(|a| a.unescape_value().ok().map(|v| v.into_owned()))
+}
+
+/// Property names of the entity type behind `entity_set` in an OData CSDL `$metadata` document.
+/// Falls back to treating `entity_set` as the type name; empty if neither is found.
+pub(crate) fn csdl_entity_properties(xml: &str, entity_set: &str) -> Result<Vec<String>, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut properties: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
+    let mut set_types: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    let mut current_type: Option<String> = None;
+
+    loop {
+        let event = reader.read_event()
+            .map_err(|e| format!("Invalid $metadata XML at byte {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(ref start) | Event::Empty(ref start) => {
+                let is_start = matches!(event, Event::Start(_));
+                match start.local_name().as_ref() {
+                    b"EntityType" if is_start => current_type = attribute(start, b"Name"),
+                    b"Property" => {
+                        if let (Some(ref type_name), Some(name)) = (&current_type, attribute(start, b"Name")) {
+                            properties.entry(type_name.clone()).or_default().push(name);
+                        }
+                    }
+                    b"EntitySet" => {
+                        if let (Some(name), Some(entity_type)) = (attribute(start, b"Name"), attribute(start, b"EntityType")) {
+                            // EntityType is namespace-qualified, e.g. "Nimbus.Models.User"
+                            let local = entity_type.rsplit('.').next().unwrap_or(&entity_type).to_string();
+                            set_types.insert(name, local);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(ref end) if end.local_name().as_ref() == b"EntityType" => current_type = None,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    let type_name = set_types.iter()
+        .find(|(set, _)| set.eq_ignore_ascii_case(entity_set))
+        .map(|(_, t)| t.clone())
+        .unwrap_or_else(|| entity_set.to_string());
+
+    Ok(properties.into_iter()
+        .find(|(t, _)| t.eq_ignore_ascii_case(&type_name))
+        .map(|(_, props)| props)
+        .unwrap_or_default())
+}
+
+/// What a CSDL `$metadata` document says about the service as a whole
+#[derive(Debug, Default)]
+pub(crate) struct CsdlServiceInfo {
+    // `m:DataServiceVersion` on DataServices (OData v2/v3), otherwise `Version` on the Edmx root
+    pub version: Option<String>,
+    // The Capabilities BatchSupported annotation, when the service declares one
+    pub batch_supported: Option<bool>,
+}
+
+pub(crate) fn csdl_service_info(xml: &str) -> Result<CsdlServiceInfo, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut edmx_version = None;
+    let mut data_service_version = None;
+    let mut batch_supported = None;
+
+    loop {
+        let event = reader.read_event()
+            .map_err(|e| format!("Invalid $metadata XML at byte {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(ref start) | Event::Empty(ref start) => match start.local_name().as_ref() {
+                b"Edmx" => edmx_version = attribute(start, b"Version"),
+                b"DataServices" => data_service_version = attribute(start, b"DataServiceVersion"),
+                b"Annotation" if attribute(start, b"Term").is_some_and(|term| term.ends_with(".BatchSupported")) => {
+                    // A Bool term without a value means true
+                    batch_supported = Some(attribute(start, b"Bool").is_none_or(|b| b != "false"));
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(CsdlServiceInfo { version: data_service_version.or(edmx_version), batch_supported })
+}
+
+/// Entity set names from an AtomPub service document (`<collection href="Users">`), in document order
+pub(crate) fn service_document_sets(xml: &str) -> Result<Vec<String>, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut sets = Vec::new();
+    loop {
+        let event = reader.read_event()
+            .map_err(|e| format!("Invalid service document XML at byte {}: {}", reader.buffer_position(), e))?;
+        match event {
+            Event::Start(ref start) | Event::Empty(ref start) if start.local_name().as_ref() == b"collection" => {
+                if let Some(href) = attribute(start, b"href") {
+                    sets.push(href);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(sets)
+}
@@ -0,0 +1,77 @@
+//! Symmetric encryption shared by the credential store and profile bundles.
+//!
+//! Keys come from a passphrase via Argon2 (with a random salt kept alongside the
+//! data), and data is sealed with ChaCha20-Poly1305 under a fresh random nonce,
+//! stored in front of the ciphertext as `nonce | ciphertext`.
+
+use argon2::Argon2;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+pub(crate) const SALT_LEN: usize = 16;
+pub(crate) const NONCE_LEN: usize = 12;
+
+pub(crate) fn random_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// A cipher under a fresh random key, for data that never outlives the process
+pub(crate) fn random_cipher() -> ChaCha20Poly1305 {
+    ChaCha20Poly1305::new(&ChaCha20Poly1305::generate_key(&mut OsRng))
+}
+
+/// The cipher for `passphrase`, with its key derived by Argon2 (default parameters) under `salt`
+pub(crate) fn passphrase_cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, String> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("Failed to derive key from passphrase: {}", e))?;
+    Ok(ChaCha20Poly1305::new(&key))
+}
+
+/// Encrypt `plaintext` under a fresh nonce, returning `nonce | ciphertext`
+pub(crate) fn seal(cipher: &ChaCha20Poly1305, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "Encryption failed".to_string())?;
+    let mut sealed = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(&nonce);
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypt the output of `seal`. A failed tag check means the wrong key or tampered data.
+pub(crate) fn open(cipher: &ChaCha20Poly1305, sealed: &[u8]) -> Result<Vec<u8>, String> {
+    if sealed.len() < NONCE_LEN {
+        return Err("Sealed data is too short".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decryption failed (wrong key or corrupted data)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_data_opens_with_the_same_passphrase_and_salt() {
+        let salt = random_salt();
+        let sealed = seal(&passphrase_cipher("correct horse", &salt).unwrap(), b"secret").unwrap();
+        assert_eq!(open(&passphrase_cipher("correct horse", &salt).unwrap(), &sealed).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn sealed_data_does_not_open_with_another_passphrase_or_salt() {
+        let salt = random_salt();
+        let sealed = seal(&passphrase_cipher("correct horse", &salt).unwrap(), b"secret").unwrap();
+        assert!(open(&passphrase_cipher("battery staple", &salt).unwrap(), &sealed).is_err());
+        assert!(open(&passphrase_cipher("correct horse", &random_salt()).unwrap(), &sealed).is_err());
+        assert!(open(&passphrase_cipher("correct horse", &salt).unwrap(), &sealed[..NONCE_LEN - 1]).is_err());
+    }
+}
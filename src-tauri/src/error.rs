@@ -0,0 +1,124 @@
+use serde::Serialize;
+use std::fmt;
+
+/// Failure categories the frontend can branch on (serialized with a `kind` tag)
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind")]
+pub enum ErrorKind {
+    ResponseTooLarge { limit_bytes: u64 },
+    RequestTooLarge { limit_bytes: u64, size_bytes: u64 },
+    RateLimited {
+        retry_after_secs: Option<u64>,
+        limit: Option<u64>,
+        remaining: Option<u64>,
+    },
+    CircuitOpen { host: String, retry_after_secs: u64 },
+    NotFound { entity: String, key: String },
+    Unauthorized {
+        status: u16,
+        token_likely_expired: bool,
+        www_authenticate: Option<String>,
+    },
+    Cancelled,
+    DeadlineExceeded { deadline_id: String },
+    MissingFields { rows: Vec<MissingRowFields> },
+}
+
+/// Required fields absent from one result row (`row` is the 0-based index in the result)
+#[derive(Debug, Clone, Serialize)]
+pub struct MissingRowFields {
+    pub row: usize,
+    pub fields: Vec<String>,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::ResponseTooLarge { limit_bytes } => {
+                write!(f, "Response exceeded the {} byte limit", limit_bytes)
+            }
+            ErrorKind::RequestTooLarge { limit_bytes, size_bytes } => {
+                write!(f, "Request body is {} bytes, over the {} byte limit", size_bytes, limit_bytes)
+            }
+            ErrorKind::RateLimited { retry_after_secs: Some(secs), .. } => {
+                write!(f, "Nimbus is rate limiting requests, try again in {}s", secs)
+            }
+            ErrorKind::RateLimited { .. } => {
+                write!(f, "Nimbus is rate limiting requests, try again shortly")
+            }
+            ErrorKind::CircuitOpen { host, retry_after_secs } => {
+                write!(f, "{} is failing repeatedly; requests paused for {}s", host, retry_after_secs)
+            }
+            ErrorKind::NotFound { entity, key } => {
+                write!(f, "No {} record with key {}", entity, key)
+            }
+            ErrorKind::Unauthorized { token_likely_expired: true, .. } => {
+                write!(f, "Nimbus rejected the session - it has probably expired, please sign in again")
+            }
+            ErrorKind::Unauthorized { status: 403, .. } => {
+                write!(f, "Nimbus denied access (403) - this account lacks permission for the request")
+            }
+            ErrorKind::Unauthorized { status, .. } => {
+                write!(f, "Nimbus rejected the request as unauthorized ({})", status)
+            }
+            ErrorKind::Cancelled => write!(f, "Request was cancelled"),
+            ErrorKind::DeadlineExceeded { deadline_id } => {
+                write!(f, "Deadline {} ran out before the request completed", deadline_id)
+            }
+            ErrorKind::MissingFields { rows } => {
+                // Name the first few rows; the full list is in `rows`
+                let shown: Vec<String> = rows.iter().take(5).map(|r| {
+                    let noun = if r.fields.len() == 1 { "field" } else { "fields" };
+                    format!("row {} missing {} {}", r.row, noun, r.fields.join(", "))
+                }).collect();
+                write!(f, "{}", shown.join("; "))?;
+                if rows.len() > shown.len() {
+                    write!(f, " (and {} more rows)", rows.len() - shown.len())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Error returned by the HTTP commands.
+/// Plain failures serialize as a string, exactly like the `Result<_, String>` commands;
+/// typed failures serialize as `{ kind, message, ...details }`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum AppError {
+    Message(String),
+    Typed {
+        message: String,
+        #[serde(flatten)]
+        kind: ErrorKind,
+    },
+}
+
+impl AppError {
+    pub fn typed(kind: ErrorKind) -> Self {
+        AppError::Typed { message: kind.to_string(), kind }
+    }
+
+    /// Append `context` to the message, keeping the kind
+    pub fn with_context(self, context: &str) -> Self {
+        match self {
+            AppError::Message(message) => AppError::Message(format!("{} ({})", message, context)),
+            AppError::Typed { message, kind } => AppError::Typed { message: format!("{} ({})", message, context), kind },
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Message(message) | AppError::Typed { message, .. } => f.write_str(message),
+        }
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Message(message)
+    }
+}
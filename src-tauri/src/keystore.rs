@@ -0,0 +1,379 @@
+//! Credential storage backend.
+//!
+//! Entries normally live in the OS keyring. When the keyring has no usable
+//! backend (headless Linux, locked-down VDI images) and the fallback has been
+//! opted into via `MONASH_NIMBUS_KEYRING_FALLBACK=1`, entries are kept in an
+//! encrypted in-process map instead. That map is never written to disk and is
+//! gone when the app exits.
+//!
+//! Credential entries can additionally be sealed under an app-level master
+//! passphrase (ChaCha20-Poly1305, key derived with Argon2). Once one is set the
+//! store must be unlocked each session before those entries can be read or written.
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::ChaCha20Poly1305;
+use keyring::Entry;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::crypto;
+
+pub const SERVICE_NAME: &str = "monash-nimbus-reports";
+
+const FALLBACK_ENV_VAR: &str = "MONASH_NIMBUS_KEYRING_FALLBACK";
+
+// Throwaway entry written and removed by `probe_keyring`
+const PROBE_KEY: &str = "keyring-probe";
+
+// Master passphrase salt (base64) - present only once a master passphrase has been set
+const MASTER_SALT_KEY: &str = "master-key-salt";
+// MASTER_CHECK_VALUE sealed under the master key, to tell a wrong passphrase from a corrupt entry
+const MASTER_CHECK_KEY: &str = "master-key-check";
+const MASTER_CHECK_VALUE: &str = "monash-nimbus-reports";
+
+// Entries sealed under the master key; everything else (profile index, default profile...) stays readable while locked
+const SEALED_KEY_PREFIXES: &[&str] = &["profile:", "login:", "apptoken:"];
+// Marks a value sealed under the master key: SEALED_PREFIX + base64(nonce | ciphertext)
+const SEALED_PREFIX: &str = "sealed:v1:";
+
+pub struct KeyStore {
+    fallback_enabled: bool,
+    fallback_active: AtomicBool,
+    memory: Mutex<HashMap<String, Vec<u8>>>,
+    cipher: ChaCha20Poly1305,
+    // Derived from the master passphrase by `unlock_master_key`; None while locked or never set
+    master: Mutex<Option<ChaCha20Poly1305>>,
+}
+
+impl KeyStore {
+    pub fn new(fallback_enabled: bool) -> Self {
+        Self {
+            fallback_enabled,
+            fallback_active: AtomicBool::new(false),
+            memory: Mutex::new(HashMap::new()),
+            cipher: crypto::random_cipher(),
+            master: Mutex::new(None),
+        }
+    }
+
+    /// A store that never touches the OS keyring, for tests
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        let store = Self::new(true);
+        store.fallback_active.store(true, Ordering::SeqCst);
+        store
+    }
+
+    /// Build the store, enabling the in-memory fallback only when the env var opts in
+    pub fn from_env() -> Self {
+        let enabled = std::env::var(FALLBACK_ENV_VAR)
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        Self::new(enabled)
+    }
+
+    pub fn fallback_enabled(&self) -> bool {
+        self.fallback_enabled
+    }
+
+    /// True once the keyring has failed and entries are being held in memory
+    pub fn keyring_unavailable(&self) -> bool {
+        self.fallback_active.load(Ordering::SeqCst)
+    }
+
+    pub fn get(&self, key: &str) -> Result<String, String> {
+        self.try_get(key)?
+            .ok_or_else(|| keyring::Error::NoEntry.to_string())
+    }
+
+    /// Like `get`, but a missing entry is `Ok(None)` rather than an error
+    pub fn try_get(&self, key: &str) -> Result<Option<String>, String> {
+        match self.raw_try_get(key)? {
+            Some(value) if value.starts_with(SEALED_PREFIX) => {
+                let master = self.master.lock().map_err(|_| "Credential store lock poisoned".to_string())?;
+                let cipher = master.as_ref().ok_or_else(locked_error)?;
+                unseal(cipher, &value).map(Some)
+            }
+            value => Ok(value),
+        }
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> Result<(), String> {
+        if !is_sealed_key(key) {
+            return self.raw_set(key, value);
+        }
+        let sealed = {
+            let master = self.master.lock().map_err(|_| "Credential store lock poisoned".to_string())?;
+            match master.as_ref() {
+                Some(cipher) => Some(seal(cipher, value)?),
+                None => None,
+            }
+        };
+        match sealed {
+            Some(sealed) => self.raw_set(key, &sealed),
+            // Never write a credential in the clear once a master passphrase is set
+            None if self.master_key_enabled()? => Err(locked_error()),
+            None => self.raw_set(key, value),
+        }
+    }
+
+    /// True once a master passphrase has been set, whether or not it's been unlocked this session
+    pub fn master_key_enabled(&self) -> Result<bool, String> {
+        Ok(self.raw_try_get(MASTER_SALT_KEY)?.is_some())
+    }
+
+    pub fn master_key_unlocked(&self) -> bool {
+        self.master.lock().map(|m| m.is_some()).unwrap_or(false)
+    }
+
+    /// Derive the master key from `passphrase` and keep it for this session
+    pub fn unlock_master_key(&self, passphrase: &str) -> Result<(), String> {
+        let cipher = self.stored_master_cipher(passphrase)?
+            .ok_or_else(|| "No master passphrase has been set".to_string())?;
+        *self.master.lock().map_err(|_| "Credential store lock poisoned".to_string())? = Some(cipher);
+        Ok(())
+    }
+
+    /// Re-seal the credential entries in `keys` under `new_passphrase`, returning how many were rewritten.
+    /// `old_passphrase` must match the current master passphrase, or be None when none is set yet
+    /// (entries are then sealed for the first time). Every entry is decrypted before anything is
+    /// written, so a wrong passphrase or undecryptable entry changes nothing; a failed write puts
+    /// back the entries already rewritten.
+    pub fn rotate_master_key(
+        &self,
+        keys: &[String],
+        old_passphrase: Option<&str>,
+        new_passphrase: &str,
+    ) -> Result<u32, String> {
+        if new_passphrase.is_empty() {
+            return Err("The new master passphrase can't be empty".to_string());
+        }
+        let old_cipher = match old_passphrase {
+            Some(old_passphrase) => Some(
+                self.stored_master_cipher(old_passphrase)?
+                    .ok_or_else(|| "No master passphrase has been set".to_string())?,
+            ),
+            None if self.master_key_enabled()? => {
+                return Err("A master passphrase is already set - rotate it instead".to_string());
+            }
+            None => None,
+        };
+
+        // Decrypt everything up front so a bad entry aborts before any write
+        let mut entries = Vec::new();
+        for key in keys.iter().filter(|key| is_sealed_key(key)) {
+            let Some(stored) = self.raw_try_get(key)? else {
+                continue;
+            };
+            let plaintext = match (&old_cipher, stored.starts_with(SEALED_PREFIX)) {
+                (Some(cipher), true) => unseal(cipher, &stored)
+                    .map_err(|e| format!("'{}' can't be decrypted with the old passphrase: {}", key, e))?,
+                (None, true) => return Err(format!("'{}' is sealed but no master passphrase is set", key)),
+                (_, false) => stored.clone(),
+            };
+            entries.push((key.as_str(), stored, plaintext));
+        }
+
+        let salt = crypto::random_salt();
+        let new_cipher = crypto::passphrase_cipher(new_passphrase, &salt)?;
+        let mut writes = Vec::with_capacity(entries.len() + 2);
+        for (key, stored, plaintext) in &entries {
+            writes.push((*key, Some(stored.clone()), seal(&new_cipher, plaintext)?));
+        }
+        // Salt and check go last, so an interrupted rotation still opens with the old passphrase
+        writes.push((MASTER_SALT_KEY, self.raw_try_get(MASTER_SALT_KEY)?, BASE64.encode(salt)));
+        writes.push((MASTER_CHECK_KEY, self.raw_try_get(MASTER_CHECK_KEY)?, seal(&new_cipher, MASTER_CHECK_VALUE)?));
+
+        for (i, (key, _, value)) in writes.iter().enumerate() {
+            if let Err(e) = self.raw_set(key, value) {
+                for (key, previous, _) in writes[..i].iter().rev() {
+                    let restored = match previous {
+                        Some(previous) => self.raw_set(key, previous),
+                        None => self.try_delete(key).map(|_| ()),
+                    };
+                    if let Err(restore_error) = restored {
+                        eprintln!("Failed to restore '{}' after an aborted master key rotation: {}", key, restore_error);
+                    }
+                }
+                return Err(format!("Failed to write '{}' during master key rotation: {}", key, e));
+            }
+        }
+
+        *self.master.lock().map_err(|_| "Credential store lock poisoned".to_string())? = Some(new_cipher);
+        Ok(entries.len() as u32)
+    }
+
+    /// The cipher for `passphrase` under the stored salt, checked against the stored check value.
+    /// None when no master passphrase has been set.
+    fn stored_master_cipher(&self, passphrase: &str) -> Result<Option<ChaCha20Poly1305>, String> {
+        let Some(salt) = self.raw_try_get(MASTER_SALT_KEY)? else {
+            return Ok(None);
+        };
+        let salt = BASE64.decode(salt.trim())
+            .map_err(|e| format!("Corrupt master key salt: {}", e))?;
+        let cipher = crypto::passphrase_cipher(passphrase, &salt)?;
+        let check = self.raw_try_get(MASTER_CHECK_KEY)?
+            .ok_or_else(|| "Master key check entry is missing".to_string())?;
+        match unseal(&cipher, &check) {
+            Ok(value) if value == MASTER_CHECK_VALUE => Ok(Some(cipher)),
+            _ => Err("Incorrect master passphrase".to_string()),
+        }
+    }
+
+    fn raw_try_get(&self, key: &str) -> Result<Option<String>, String> {
+        if !self.keyring_unavailable() {
+            match Entry::new(SERVICE_NAME, key).and_then(|entry| entry.get_password()) {
+                Ok(value) => return Ok(Some(value)),
+                Err(keyring::Error::NoEntry) => return Ok(None),
+                Err(e) if self.should_fall_back(&e) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        self.memory_get(key)
+    }
+
+    fn raw_set(&self, key: &str, value: &str) -> Result<(), String> {
+        if !self.keyring_unavailable() {
+            match Entry::new(SERVICE_NAME, key).and_then(|entry| entry.set_password(value)) {
+                Ok(()) => return Ok(()),
+                Err(e) if self.should_fall_back(&e) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        self.memory_set(key, value)
+    }
+
+    pub fn delete(&self, key: &str) -> Result<(), String> {
+        if self.try_delete(key)? {
+            Ok(())
+        } else {
+            Err(keyring::Error::NoEntry.to_string())
+        }
+    }
+
+    /// Like `delete`, but a missing entry is `Ok(false)` rather than an error
+    pub fn try_delete(&self, key: &str) -> Result<bool, String> {
+        if !self.keyring_unavailable() {
+            match Entry::new(SERVICE_NAME, key).and_then(|entry| entry.delete_credential()) {
+                Ok(()) => return Ok(true),
+                Err(keyring::Error::NoEntry) => return Ok(false),
+                Err(e) if self.should_fall_back(&e) => {}
+                Err(e) => return Err(e.to_string()),
+            }
+        }
+        self.memory_delete(key)
+    }
+
+    /// Every stored key, when the active backend can list them. Only the in-memory fallback can:
+    /// the OS keyrings are looked up by exact key, so this is `None` while they're in use.
+    pub fn list_keys(&self) -> Option<Vec<String>> {
+        if !self.keyring_unavailable() {
+            return None;
+        }
+        let memory = self.memory.lock().unwrap_or_else(|e| e.into_inner());
+        Some(memory.keys().cloned().collect())
+    }
+
+    /// Name of the platform keyring backend this build uses
+    pub fn backend_name() -> &'static str {
+        if cfg!(target_os = "windows") {
+            "Windows Credential Manager"
+        } else if cfg!(target_os = "macos") {
+            "macOS Keychain"
+        } else if cfg!(target_os = "linux") {
+            "Secret Service"
+        } else {
+            "none"
+        }
+    }
+
+    /// Set, read back and delete a sentinel entry directly in the OS keyring (never the fallback).
+    /// The sentinel is removed even when the read-back fails.
+    pub fn probe_keyring(&self) -> Result<(), String> {
+        let entry = Entry::new(SERVICE_NAME, PROBE_KEY).map_err(|e| e.to_string())?;
+        let sentinel = format!("probe-{}", chrono::Utc::now().timestamp_millis());
+
+        entry.set_password(&sentinel).map_err(|e| format!("Write failed: {}", e))?;
+        let read_back = entry.get_password().map_err(|e| format!("Read failed: {}", e));
+        let deleted = entry.delete_credential().map_err(|e| format!("Delete failed: {}", e));
+
+        if read_back? != sentinel {
+            return Err("Read back a different value than was written".to_string());
+        }
+        deleted
+    }
+
+    /// Check the active backend takes writes without touching any real entry: the in-memory
+    /// fallback always does, the OS keyring is checked with the `probe_keyring` sentinel
+    pub fn probe_writable(&self) -> Result<(), String> {
+        if self.keyring_unavailable() {
+            return Ok(());
+        }
+        self.probe_keyring()
+    }
+
+    /// Switch to the in-memory store if the error means "no keyring backend" and the fallback is allowed
+    fn should_fall_back(&self, error: &keyring::Error) -> bool {
+        let backend_missing = matches!(
+            error,
+            keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_)
+        );
+        if !backend_missing || !self.fallback_enabled {
+            return false;
+        }
+        if !self.fallback_active.swap(true, Ordering::SeqCst) {
+            eprintln!("OS keyring unavailable ({}), using in-memory credential store", error);
+        }
+        true
+    }
+
+    fn memory_get(&self, key: &str) -> Result<Option<String>, String> {
+        let memory = self.memory.lock().map_err(|_| "Credential store lock poisoned".to_string())?;
+        let Some(sealed) = memory.get(key) else {
+            return Ok(None);
+        };
+        let plaintext = crypto::open(&self.cipher, sealed)
+            .map_err(|e| format!("Failed to decrypt in-memory credential entry: {}", e))?;
+        String::from_utf8(plaintext)
+            .map(Some)
+            .map_err(|e| format!("Invalid credential encoding: {}", e))
+    }
+
+    fn memory_set(&self, key: &str, value: &str) -> Result<(), String> {
+        let sealed = crypto::seal(&self.cipher, value.as_bytes())
+            .map_err(|e| format!("Failed to encrypt in-memory credential entry: {}", e))?;
+
+        let mut memory = self.memory.lock().map_err(|_| "Credential store lock poisoned".to_string())?;
+        memory.insert(key.to_string(), sealed);
+        Ok(())
+    }
+
+    fn memory_delete(&self, key: &str) -> Result<bool, String> {
+        let mut memory = self.memory.lock().map_err(|_| "Credential store lock poisoned".to_string())?;
+        Ok(memory.remove(key).is_some())
+    }
+}
+
+fn is_sealed_key(key: &str) -> bool {
+    SEALED_KEY_PREFIXES.iter().any(|prefix| key.starts_with(prefix))
+}
+
+fn locked_error() -> String {
+    "Credential store is locked - unlock it with the master passphrase first".to_string()
+}
+
+fn seal(cipher: &ChaCha20Poly1305, value: &str) -> Result<String, String> {
+    let sealed = crypto::seal(cipher, value.as_bytes())
+        .map_err(|e| format!("Failed to encrypt credential entry: {}", e))?;
+    Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(sealed)))
+}
+
+fn unseal(cipher: &ChaCha20Poly1305, value: &str) -> Result<String, String> {
+    let sealed = value.strip_prefix(SEALED_PREFIX)
+        .ok_or_else(|| "Entry is not sealed".to_string())
+        .and_then(|encoded| BASE64.decode(encoded).map_err(|e| format!("Corrupt sealed entry: {}", e)))?;
+    let plaintext = crypto::open(cipher, &sealed)
+        .map_err(|e| format!("Failed to decrypt credential entry: {}", e))?;
+    String::from_utf8(plaintext).map_err(|e| format!("Invalid credential encoding: {}", e))
+}
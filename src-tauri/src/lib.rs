@@ -1,44 +1,239 @@
 mod commands;
+mod crypto;
+mod error;
+mod keystore;
 mod types;
 
+use commands::capture::{export_har, export_support_bundle, get_capture_status, set_capture};
+use commands::circuit::{get_circuit_state, reset_circuit, CircuitBreakers};
+use commands::config::{get_config, load_saved_config, update_config};
 use commands::credentials::{
-    save_credentials, load_credentials, delete_credentials,
-    save_login_credentials, load_login_credentials, delete_login_credentials,
-    save_apptoken_credentials, load_apptoken_credentials, delete_apptoken_credentials
+    get_credential_store_status, keyring_status, migrate_credentials, migrate_on_startup,
+    save_credentials, load_credentials, delete_credentials, clear_session_token,
+    save_login_credentials, load_login_credentials, delete_login_credentials, clear_login_password,
+    save_apptoken_credentials, load_apptoken_credentials, delete_apptoken_credentials,
+    enable_master_key, unlock_master_key, rotate_master_key
 };
+use commands::diagnostics::{
+    get_request_log, clear_request_log, get_query_metrics, reset_query_metrics, QueryMetrics, RequestLog
+};
+use commands::dedup::dedup_odata;
+use commands::diff::diff_odata;
+use commands::export::{export_odata_to_csv, export_odata_to_zip};
+use commands::filter::{
+    build_filter, build_orderby, date_range_filter, validate_odata_filter, validate_odata_orderby,
+};
+use commands::flatten::flatten_odata;
 use commands::http::{
-    execute_odata_query, execute_rest_get, execute_rest_post
+    build_odata_url, execute_odata_query, execute_odata_query_v2, execute_odata_typed, execute_rest_get,
+    execute_rest_get_json, execute_rest_get_paged, execute_rest_post, execute_rest_post_form,
+    execute_rest_post_raw, probe_odata, validate_post_body,
+};
+use commands::idempotency::IdempotencyCache;
+use commands::network::{
+    begin_deadline, benchmark_download, cancel_all_requests, check_connectivity, get_inflight_count,
+    get_network_settings, inspect_tls, prewarm, remaining_budget, reset_http_client, set_connection_tuning,
+    set_accept_language, set_max_concurrency, set_min_tls_version, set_user_agent, timed_request, NetworkConfig,
+};
+use commands::odata::{
+    build_expand, detect_capabilities, detect_odata_endpoint, execute_odata_all_pages, execute_odata_count,
+    execute_odata_page, fetch_odata_entity, get_page_cursor, list_odata_sets, odata_lookup_join, odata_result_hash,
+    verify_profile, CapabilitiesCache, EntityFieldCache,
 };
+use commands::polling::{start_polling, stop_polling, Pollers};
+use commands::profiles::{
+    list_profiles, get_profile_summary,
+    set_default_profile, get_default_profile,
+    export_profiles, import_profiles,
+    delete_all_profiles, rebuild_profile_index, can_delete_profile, ProfileLocks
+};
+use commands::queries::{
+    save_query, load_query, list_queries, delete_query, export_queries, import_queries, QueryLibraryLock
+};
+use commands::schema::infer_schema;
+use commands::session::{
+    refresh_session, logout, inspect_token, touch_session, check_all_sessions, whoami, IdentityCache,
+    SessionStatusCache
+};
+use commands::snapshots::{save_response_snapshot, list_snapshots, load_snapshot};
+use commands::stream::execute_rest_get_ndjson;
+use commands::sync::sync_odata_incremental;
 use commands::version::{
-    get_current_version, check_for_updates
+    get_current_version, check_for_updates, download_update, get_release_assets, select_platform_asset,
+    UpdateCheckCache
 };
+use keystore::KeyStore;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let store = KeyStore::from_env();
+    // A failed migration leaves the old entries untouched; migrate_credentials can retry it later
+    match migrate_on_startup(&store) {
+        Ok(Some(report)) => eprintln!(
+            "Migrated credentials from schema {} to {} ({} profiles)",
+            report.from_version, report.to_version, report.migrated.len()
+        ),
+        Ok(None) => {}
+        Err(e) => eprintln!("Credential migration failed: {}", e),
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(store)
+        .manage(UpdateCheckCache::default())
+        .manage(RequestLog::default())
+        .manage(QueryMetrics::default())
+        .manage(CircuitBreakers::default())
+        .manage(NetworkConfig::default())
+        .manage(IdempotencyCache::default())
+        .manage(EntityFieldCache::default())
+        .manage(CapabilitiesCache::default())
+        .manage(ProfileLocks::default())
+        .manage(Pollers::default())
+        .manage(SessionStatusCache::default())
+        .manage(IdentityCache::default())
+        .manage(QueryLibraryLock::default())
+        .setup(|app| {
+            // A bad or unreadable config leaves the defaults in place rather than blocking startup
+            if let Err(e) = load_saved_config(app.handle()) {
+                eprintln!("Saved config not applied: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             // Credential management (session tokens)
+            get_credential_store_status,
+            keyring_status,
+            migrate_credentials,
             save_credentials,
             load_credentials,
             delete_credentials,
+            clear_session_token,
             // Login credentials (username/password)
             save_login_credentials,
             load_login_credentials,
             delete_login_credentials,
+            clear_login_password,
             // App Token credentials (app_token/username)
             save_apptoken_credentials,
             load_apptoken_credentials,
             delete_apptoken_credentials,
+            // App-level master passphrase over the keyring
+            enable_master_key,
+            unlock_master_key,
+            rotate_master_key,
+            // Profile index and summaries (no secrets)
+            list_profiles,
+            get_profile_summary,
+            set_default_profile,
+            get_default_profile,
+            // Profile export/import (encrypted bundles)
+            export_profiles,
+            import_profiles,
+            delete_all_profiles,
+            can_delete_profile,
+            rebuild_profile_index,
+            // Session refresh (replays stored login/app token credentials) and logout
+            refresh_session,
+            logout,
+            inspect_token,
+            touch_session,
+            check_all_sessions,
+            whoami,
             // HTTP client (read-only operations)
             execute_odata_query,
+            execute_odata_query_v2,
+            execute_odata_typed,
+            probe_odata,
+            build_odata_url,
             execute_rest_get,
+            execute_rest_get_json,
+            execute_rest_get_paged,
             execute_rest_post,
+            validate_post_body,
+            execute_rest_post_form,
+            execute_rest_post_raw,
+            execute_rest_get_ndjson,
+            // Saved response snapshots (local audit trail)
+            save_response_snapshot,
+            list_snapshots,
+            load_snapshot,
+            // Saved query library
+            save_query,
+            load_query,
+            list_queries,
+            delete_query,
+            export_queries,
+            import_queries,
+            // App configuration (saved to app data)
+            get_config,
+            update_config,
+            // Connectivity diagnostics and client settings
+            check_connectivity,
+            inspect_tls,
+            timed_request,
+            prewarm,
+            benchmark_download,
+            cancel_all_requests,
+            set_max_concurrency,
+            get_inflight_count,
+            begin_deadline,
+            remaining_budget,
+            get_network_settings,
+            set_user_agent,
+            set_accept_language,
+            set_connection_tuning,
+            set_min_tls_version,
+            reset_http_client,
+            // Recent request log (redacted)
+            get_request_log,
+            clear_request_log,
+            set_capture,
+            get_capture_status,
+            export_support_bundle,
+            export_har,
+            // Per-entity query timings
+            get_query_metrics,
+            reset_query_metrics,
+            // Per-host circuit breaker
+            get_circuit_state,
+            reset_circuit,
+            // OData query helpers
+            execute_odata_count,
+            detect_odata_endpoint,
+            detect_capabilities,
+            list_odata_sets,
+            verify_profile,
+            fetch_odata_entity,
+            execute_odata_page,
+            execute_odata_all_pages,
+            odata_lookup_join,
+            odata_result_hash,
+            get_page_cursor,
+            export_odata_to_csv,
+            export_odata_to_zip,
+            build_expand,
+            validate_odata_filter,
+            build_filter,
+            date_range_filter,
+            build_orderby,
+            validate_odata_orderby,
+            flatten_odata,
+            diff_odata,
+            dedup_odata,
+            infer_schema,
+            sync_odata_incremental,
+            // Background polling (results sent as poll-result:<id> events)
+            start_polling,
+            stop_polling,
             // Version checking
             get_current_version,
             check_for_updates,
+            download_update,
+            get_release_assets,
+            select_platform_asset,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 /// Session credentials (from successful authentication)
 /// Supports both credential-based and App Token auth modes
@@ -16,12 +17,22 @@ pub struct Credentials {
     pub app_token: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
+    // Unix seconds when the session was stored, and when it stops being accepted (if known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issued_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<i64>,
+    // Extra headers this tenant needs on every request (e.g. a tenant id); caller headers still override them
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub default_headers: HashMap<String, String>,
 }
 
 /// Login credentials (username/password for storage)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginCredentials {
     pub username: String,
+    // Empty after clear_login_password
+    #[serde(default)]
     pub password: String,
 }
 
@@ -37,4 +48,48 @@ pub struct HttpResponse {
     pub status: u16,
     pub body: String,
     pub headers: std::collections::HashMap<String, String>,
+    // URL the response actually came from - differs from the request URL after a redirect
+    #[serde(default)]
+    pub final_url: String,
+}
+
+/// A REST response whose body has been parsed as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonResponse {
+    pub status: u16,
+    pub json: serde_json::Value,
+    pub headers: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub final_url: String,
+}
+
+/// Which credential backend is active, so the UI can warn when nothing will persist
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialStoreStatus {
+    pub keyring_unavailable: bool,
+    pub fallback_enabled: bool,
+    // A master passphrase seals the credential entries; until it's unlocked they can't be read
+    pub master_key_enabled: bool,
+    pub master_key_unlocked: bool,
+}
+
+/// Result of a live round-trip against the OS keyring
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyringStatus {
+    pub available: bool,
+    // The platform backend in use, e.g. "Secret Service"
+    pub backend: String,
+    // Why the round-trip failed, when it did
+    pub error: Option<String>,
+}
+
+/// Non-secret profile metadata for the profile list (tokens stripped)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileSummary {
+    pub name: String,
+    pub base_url: String,
+    pub auth_mode: String,
+    pub username: Option<String>,
+    pub has_session: bool,
+    pub issued_at: Option<i64>,
 }
@@ -257,6 +257,14 @@ export const useConnectionStore = create<ConnectionState>()(
         connections: state.connections,
         activeConnectionName: state.activeConnectionName,
       }),
+      // The backend can't list the keyring, so name the saved connections for stores from before the profile index
+      onRehydrateStorage: () => (state) => {
+        const profileNames = state?.connections.map((c) => c.name) ?? [];
+        if (profileNames.length === 0) return;
+        invoke("migrate_credentials", { profileNames }).catch((err) => {
+          console.error("Credential migration failed:", err);
+        });
+      },
     }
   )
 );